@@ -19,7 +19,7 @@ impl GateType {
     #[inline]
     pub const fn from_bit(bit: bool) -> Self {
         // SAFETY: bool is guaranteed to be 0 or 1, which matches our repr(u8) discriminants
-        unsafe { std::mem::transmute(bit as u8) }
+        unsafe { core::mem::transmute(bit as u8) }
     }
 
     /// Convert to a bit value.
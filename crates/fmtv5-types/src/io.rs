@@ -0,0 +1,63 @@
+//! I/O error types: `std::io`'s with the `std` feature, a minimal stand-in
+//! without it.
+//!
+//! Header and gate decoding are shared by both builds, so they return
+//! [`Result`] from here. With `std` these are the `std::io` types
+//! themselves, so nothing changes for `std` users.
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use alloc::string::{String, ToString};
+    use core::fmt;
+
+    /// The subset of `std::io::ErrorKind` the format types report.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub enum ErrorKind {
+        /// Data read doesn't describe a valid circuit.
+        InvalidData,
+        /// An argument is out of range for the format.
+        InvalidInput,
+        /// The input ended before a complete record.
+        UnexpectedEof,
+    }
+
+    /// An error kind and message, like `std::io::Error`.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        msg: String,
+    }
+
+    impl Error {
+        /// Create an error of `kind` with a message.
+        pub fn new(kind: ErrorKind, msg: impl fmt::Display) -> Self {
+            Self {
+                kind,
+                msg: msg.to_string(),
+            }
+        }
+
+        /// The kind of error.
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.msg)
+        }
+    }
+
+    impl core::error::Error for Error {}
+
+    /// `Result` with [`Error`], like `std::io::Result`.
+    pub type Result<T> = core::result::Result<T, Error>;
+}
@@ -1,12 +1,23 @@
 //! CKT - High-performance Boolean circuit format library
+//!
+//! Without the `std` feature this is a `no_std` + `alloc` crate exposing only
+//! the v5c header, gate and block types, for verifiers that can't link `std`.
 
 #![expect(missing_docs, reason = "please let me merge")]
 #![allow(unreachable_pub, reason = "pleeeeease let me merge")] // clippy is wrong about this being unfulfilled
 #![expect(missing_debug_implementations, reason = "merge pls")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 mod gate_type;
+pub mod io;
 
 #[cfg(feature = "v5")]
 pub mod v5;
 
 pub use gate_type::GateType;
+
+// The dev-dependencies are only used by the std tests
+#[cfg(all(test, not(feature = "std")))]
+use {monoio as _, tempfile as _};
@@ -0,0 +1,528 @@
+//! Delta-encoded v5a variant
+//!
+//! Same header and outputs as v5a, with [`FLAG_DELTA_WIRES`] set in the first
+//! reserved header byte. Instead of fixed-width SoA blocks, gates are stored
+//! as a stream of LEB128 varints, one record per gate:
+//!
+//! - `credits << 1 | gate_type` (0 = XOR, 1 = AND)
+//! - zigzag(`in1 - prev_out`)
+//! - zigzag(`in2 - prev_out`)
+//! - zigzag(`out - prev_out`)
+//!
+//! where `prev_out` is the previous gate's output wire, starting at the last
+//! primary input (`primary_inputs + 1`). Gate outputs are usually numbered
+//! consecutively and inputs are usually recent, so most fields fit in one
+//! or two bytes instead of 34 bits.
+//!
+//! Checksum order matches v5a: gate stream || outputs || header[after checksum].
+
+use std::fs::File as StdFile;
+use std::io::{Error, ErrorKind, Read, Result, Seek};
+use std::path::Path;
+
+use blake3::Hasher;
+use monoio::fs::{File, OpenOptions};
+
+use crate::GateType;
+use crate::v5::GateCounts;
+use crate::v5::a::reader::{decode_outputs_le40, read_outputs_section};
+use crate::v5::a::writer::{CircuitStats, encode_header_v5a_le, encode_outputs_le34};
+use crate::v5::a::{
+    FLAG_DELTA_WIRES, FORMAT_TYPE_A, GateV5a, HEADER_SIZE_V5A, HeaderV5a, MAGIC, MAX_CREDITS,
+    MAX_WIRE_ID, VERSION, parse_header_with_flags,
+};
+
+/// Longest LEB128 encoding of a u64
+const MAX_VARINT_LEN: usize = 10;
+
+/// Shortest gate record: four one-byte varints
+const MIN_RECORD_LEN: u64 = 4;
+
+const IO_BUFFER_CAP: usize = 1024 * 1024; // 1 MiB
+
+fn zigzag(delta: i64) -> u64 {
+    ((delta << 1) ^ (delta >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn push_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// Delta-encoded v5a writer
+pub struct DeltaWriterV5a {
+    file: File,
+    primary_inputs: u64,
+    outputs: Vec<u64>,
+    memo: [u8; 32],
+    next_offset: u64,
+    io_buf: Vec<u8>,
+    prev_out: u64,
+    xor_gates_written: u64,
+    and_gates_written: u64,
+    hasher: Hasher,
+}
+
+impl DeltaWriterV5a {
+    /// Create a new writer. Writes a placeholder header, then outputs.
+    pub async fn new(
+        path: impl AsRef<Path>,
+        primary_inputs: u64,
+        outputs: Vec<u64>,
+        memo: [u8; 32],
+    ) -> Result<Self> {
+        let outputs_bytes = encode_outputs_le34(&outputs)?;
+
+        let mut opts = OpenOptions::new();
+        opts.create(true).write(true).truncate(true);
+        let file = opts.open(path.as_ref()).await?;
+
+        let next_offset = (HEADER_SIZE_V5A + outputs_bytes.len()) as u64;
+        let mut placeholder = vec![0u8; HEADER_SIZE_V5A];
+        placeholder.extend_from_slice(&outputs_bytes);
+        let (res, _) = file.write_all_at(placeholder, 0).await;
+        res?;
+
+        Ok(Self {
+            file,
+            primary_inputs,
+            outputs,
+            memo,
+            next_offset,
+            io_buf: Vec::with_capacity(IO_BUFFER_CAP),
+            prev_out: primary_inputs + 1,
+            xor_gates_written: 0,
+            and_gates_written: 0,
+            hasher: Hasher::new(),
+        })
+    }
+
+    /// Write a single gate.
+    pub async fn write_gate(&mut self, gate: GateV5a) -> Result<()> {
+        if gate.in1 > MAX_WIRE_ID || gate.in2 > MAX_WIRE_ID || gate.out > MAX_WIRE_ID {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "wire ID exceeds 34-bit limit",
+            ));
+        }
+        if gate.credits > MAX_CREDITS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "credits exceed 24-bit limit",
+            ));
+        }
+
+        let type_bit = match gate.gate_type {
+            GateType::XOR => {
+                self.xor_gates_written += 1;
+                0
+            }
+            GateType::AND => {
+                self.and_gates_written += 1;
+                1
+            }
+        };
+        // Wire IDs are at most 34 bits, so differences can't overflow i64
+        let prev = self.prev_out as i64;
+        push_varint(&mut self.io_buf, (gate.credits as u64) << 1 | type_bit);
+        push_varint(&mut self.io_buf, zigzag(gate.in1 as i64 - prev));
+        push_varint(&mut self.io_buf, zigzag(gate.in2 as i64 - prev));
+        push_varint(&mut self.io_buf, zigzag(gate.out as i64 - prev));
+        self.prev_out = gate.out;
+
+        if self.io_buf.len() >= IO_BUFFER_CAP {
+            self.flush_io_buffer().await?;
+        }
+        Ok(())
+    }
+
+    /// Write multiple gates (slice).
+    pub async fn write_gates(&mut self, gates: &[GateV5a]) -> Result<()> {
+        for &g in gates {
+            self.write_gate(g).await?;
+        }
+        Ok(())
+    }
+
+    /// Finalize: flush pending data, compute checksum, and write header.
+    pub async fn finalize(mut self) -> Result<CircuitStats> {
+        self.flush_io_buffer().await?;
+
+        let num_outputs = self.outputs.len() as u64;
+        self.hasher.update(&encode_outputs_le34(&self.outputs)?);
+        self.hasher.update(&MAGIC);
+        self.hasher.update(&[VERSION]);
+        self.hasher.update(&[FORMAT_TYPE_A]);
+        self.hasher.update(&[FLAG_DELTA_WIRES, 0]);
+        self.hasher.update(&self.memo);
+        self.hasher.update(&self.xor_gates_written.to_le_bytes());
+        self.hasher.update(&self.and_gates_written.to_le_bytes());
+        self.hasher.update(&self.primary_inputs.to_le_bytes());
+        self.hasher.update(&num_outputs.to_le_bytes());
+        let checksum = *self.hasher.finalize().as_bytes();
+
+        let header_bytes = encode_header_v5a_le(
+            FLAG_DELTA_WIRES,
+            &checksum,
+            self.xor_gates_written,
+            self.and_gates_written,
+            self.primary_inputs,
+            num_outputs,
+            self.memo,
+        );
+        let (res, _) = self.file.write_all_at(header_bytes.to_vec(), 0).await;
+        res?;
+        self.file.sync_all().await?;
+        self.file.close().await?;
+
+        Ok(CircuitStats {
+            total_gates: self.xor_gates_written + self.and_gates_written,
+            xor_gates: self.xor_gates_written,
+            and_gates: self.and_gates_written,
+            primary_inputs: self.primary_inputs,
+            num_outputs,
+            checksum,
+        })
+    }
+
+    async fn flush_io_buffer(&mut self) -> Result<()> {
+        if self.io_buf.is_empty() {
+            return Ok(());
+        }
+        self.hasher.update(&self.io_buf);
+        let len = self.io_buf.len() as u64;
+        let (res, mut buf) = self
+            .file
+            .write_all_at(std::mem::take(&mut self.io_buf), self.next_offset)
+            .await;
+        res?;
+        buf.clear();
+        self.io_buf = buf;
+        self.next_offset += len;
+        Ok(())
+    }
+}
+
+/// Delta-encoded v5a reader
+///
+/// Gates are decoded sequentially. The checksum is verified once the last
+/// gate has been read, so a corrupt file fails on the final `next_gate`.
+pub struct DeltaReaderV5a {
+    file: StdFile,
+    header: HeaderV5a,
+    outputs: Vec<u64>,
+    outputs_bytes: Vec<u8>,
+    header_bytes: [u8; HEADER_SIZE_V5A],
+    buf: Vec<u8>,
+    pos: usize,
+    prev_out: u64,
+    gates_remaining: u64,
+    hasher: Hasher,
+}
+
+impl DeltaReaderV5a {
+    /// Open a delta-encoded v5a file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let mut file = StdFile::open(path)?;
+
+        let mut header_bytes = [0u8; HEADER_SIZE_V5A];
+        file.read_exact(&mut header_bytes)?;
+        let header = parse_header_with_flags(&header_bytes, FLAG_DELTA_WIRES)?;
+
+        let outputs_bytes = read_outputs_section(&mut file, header.num_outputs)?;
+        let outputs = decode_outputs_le40(&outputs_bytes)?;
+
+        Ok(Self {
+            file,
+            header,
+            outputs,
+            outputs_bytes,
+            header_bytes,
+            buf: Vec::with_capacity(IO_BUFFER_CAP),
+            pos: 0,
+            prev_out: header.primary_inputs + 1,
+            gates_remaining: header.total_gates(),
+            hasher: Hasher::new(),
+        })
+    }
+
+    pub fn header(&self) -> HeaderV5a {
+        self.header
+    }
+
+    pub fn outputs(&self) -> &[u64] {
+        &self.outputs
+    }
+
+    pub fn gate_counts(&self) -> GateCounts {
+        self.header.gate_counts()
+    }
+
+    /// Decode the next gate, or `None` after the last one.
+    pub fn next_gate(&mut self) -> Result<Option<GateV5a>> {
+        if self.gates_remaining == 0 {
+            return Ok(None);
+        }
+
+        let tag = self.read_varint()?;
+        let in1 = self.read_wire()?;
+        let in2 = self.read_wire()?;
+        let out = self.read_wire()?;
+
+        let credits = tag >> 1;
+        if credits > MAX_CREDITS as u64 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "credits exceed 24-bit limit",
+            ));
+        }
+        let gate_type = if tag & 1 == 1 {
+            GateType::AND
+        } else {
+            GateType::XOR
+        };
+        self.prev_out = out;
+
+        self.gates_remaining -= 1;
+        if self.gates_remaining == 0 {
+            self.finish()?;
+        }
+
+        Ok(Some(GateV5a {
+            in1,
+            in2,
+            out,
+            credits: credits as u32,
+            gate_type,
+        }))
+    }
+
+    /// Decode all remaining gates.
+    pub fn read_all(&mut self) -> Result<Vec<GateV5a>> {
+        // The header's gate count isn't tied to the file size, so reserve
+        // no more than the unread bytes could hold
+        let unread = self
+            .file
+            .metadata()?
+            .len()
+            .saturating_sub(self.file.stream_position()?)
+            + (self.buf.len() - self.pos) as u64;
+        let capacity = self.gates_remaining.min(unread / MIN_RECORD_LEN);
+        let mut gates = Vec::with_capacity(capacity as usize);
+        while let Some(gate) = self.next_gate()? {
+            gates.push(gate);
+        }
+        Ok(gates)
+    }
+
+    fn read_wire(&mut self) -> Result<u64> {
+        let delta = unzigzag(self.read_varint()?);
+        let wire = (self.prev_out as i64).wrapping_add(delta);
+        if !(0..=MAX_WIRE_ID as i64).contains(&wire) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("decoded wire ID {} out of range", wire),
+            ));
+        }
+        Ok(wire as u64)
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut v = 0u64;
+        for i in 0..MAX_VARINT_LEN {
+            let byte = self.read_byte()?;
+            v |= ((byte & 0x7F) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(Error::new(ErrorKind::InvalidData, "varint too long"))
+    }
+
+    fn read_byte(&mut self) -> Result<u8> {
+        if self.pos == self.buf.len() && self.refill()? == 0 {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "gate stream truncated",
+            ));
+        }
+        let byte = self.buf[self.pos];
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn refill(&mut self) -> Result<usize> {
+        self.buf.resize(IO_BUFFER_CAP, 0);
+        let n = self.file.read(&mut self.buf)?;
+        self.buf.truncate(n);
+        self.pos = 0;
+        self.hasher.update(&self.buf);
+        Ok(n)
+    }
+
+    // Called after the last gate: reject trailing bytes and verify checksum
+    fn finish(&mut self) -> Result<()> {
+        if self.pos != self.buf.len() || self.refill()? != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "trailing data after gate stream",
+            ));
+        }
+        let mut hasher = std::mem::take(&mut self.hasher);
+        hasher.update(&self.outputs_bytes);
+        hasher.update(&self.header_bytes[0..40]);
+        hasher.update(&self.header_bytes[72..104]);
+        if hasher.finalize().as_bytes() != &self.header.checksum {
+            return Err(Error::new(ErrorKind::InvalidData, "checksum mismatch"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v5::a::reader::CircuitReaderV5a;
+    use crate::v5::a::writer::CircuitWriterV5a;
+    use crate::v5::a::{CREDITS_OUTPUT, GATES_PER_BLOCK};
+    use tempfile::tempdir;
+
+    // Chain where each gate mixes the previous output with a recent wire
+    fn mk_gates(primary_inputs: u64, n: u64) -> Vec<GateV5a> {
+        (0..n)
+            .map(|i| {
+                let out = primary_inputs + 2 + i;
+                GateV5a {
+                    in1: out - 1,
+                    in2: 2 + (i * 7) % (out - 2),
+                    out,
+                    credits: if i + 1 == n { CREDITS_OUTPUT } else { 1 },
+                    gate_type: if i.is_multiple_of(3) {
+                        GateType::AND
+                    } else {
+                        GateType::XOR
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zigzag_round_trip() {
+        for v in [0i64, 1, -1, 63, -64, 1 << 33, -(1 << 33)] {
+            assert_eq!(unzigzag(zigzag(v)), v);
+        }
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[monoio::test]
+    async fn test_round_trip_and_smaller_than_v5a() {
+        let dir = tempdir().unwrap();
+        let plain_path = dir.path().join("plain.v5a");
+        let delta_path = dir.path().join("delta.v5a");
+
+        let primary_inputs = 64;
+        let gates = mk_gates(primary_inputs, 10 * GATES_PER_BLOCK as u64 + 17);
+        let outputs = vec![gates.last().unwrap().out, 2];
+        let memo = [7u8; 32];
+
+        let mut w = CircuitWriterV5a::new(&plain_path, primary_inputs, outputs.clone(), memo)
+            .await
+            .unwrap();
+        w.write_gates(&gates).await.unwrap();
+        let plain_stats = w.finalize().await.unwrap();
+
+        let mut w = DeltaWriterV5a::new(&delta_path, primary_inputs, outputs.clone(), memo)
+            .await
+            .unwrap();
+        w.write_gates(&gates).await.unwrap();
+        let delta_stats = w.finalize().await.unwrap();
+        assert_eq!(delta_stats.xor_gates, plain_stats.xor_gates);
+        assert_eq!(delta_stats.and_gates, plain_stats.and_gates);
+
+        let plain_size = std::fs::metadata(&plain_path).unwrap().len();
+        let delta_size = std::fs::metadata(&delta_path).unwrap().len();
+        assert!(
+            delta_size * 2 < plain_size,
+            "delta {} bytes vs plain {} bytes",
+            delta_size,
+            plain_size
+        );
+
+        let mut reader = DeltaReaderV5a::open(&delta_path).unwrap();
+        assert_eq!(reader.header().reserved, [FLAG_DELTA_WIRES, 0]);
+        assert_eq!(reader.header().memo, memo);
+        assert_eq!(reader.outputs(), &outputs[..]);
+        assert_eq!(
+            reader.gate_counts(),
+            CircuitReaderV5a::open(&plain_path).unwrap().gate_counts()
+        );
+
+        let decoded = reader.read_all().unwrap();
+        assert_eq!(decoded.len(), gates.len());
+        for (a, b) in decoded.iter().zip(&gates) {
+            assert_eq!(
+                (a.in1, a.in2, a.out, a.credits, a.gate_type),
+                (b.in1, b.in2, b.out, b.credits, b.gate_type)
+            );
+        }
+        assert!(reader.next_gate().unwrap().is_none());
+    }
+
+    #[monoio::test]
+    async fn test_flag_and_checksum_are_enforced() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("delta.v5a");
+        let gates = mk_gates(4, 100);
+
+        let mut w = DeltaWriterV5a::new(&path, 4, vec![gates[99].out], [0u8; 32])
+            .await
+            .unwrap();
+        w.write_gates(&gates).await.unwrap();
+        w.finalize().await.unwrap();
+
+        // Fixed-width reader refuses delta files
+        let err = CircuitReaderV5a::open(&path).err().unwrap();
+        assert!(err.to_string().contains("delta-encoded"));
+
+        // Flip a bit in the last gate's credits/type tag
+        let mut bytes = std::fs::read(&path).unwrap();
+        let len = bytes.len();
+        bytes[len - 4] ^= 0x01;
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut reader = DeltaReaderV5a::open(&path).unwrap();
+        let err = reader.read_all().unwrap_err();
+        assert_eq!(err.to_string(), "checksum mismatch");
+    }
+
+    #[monoio::test]
+    async fn test_inflated_gate_count_fails_without_reserving_it() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("delta.v5a");
+        let gates = mk_gates(4, 100);
+
+        let mut w = DeltaWriterV5a::new(&path, 4, vec![gates[99].out], [0u8; 32])
+            .await
+            .unwrap();
+        w.write_gates(&gates).await.unwrap();
+        w.finalize().await.unwrap();
+
+        // Claim 2^60 XOR gates, far beyond what the file holds
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[72..80].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut reader = DeltaReaderV5a::open(&path).unwrap();
+        let err = reader.read_all().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
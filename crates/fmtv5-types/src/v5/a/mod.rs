@@ -5,10 +5,13 @@
 //! - 24-bit credits for memory management
 //! - Structure-of-Arrays layout for optimal SIMD processing
 //! - 256 gates per block
+//! - Optional delta-encoded wire IDs (see [`delta`])
 
 use std::io::{self, Error, ErrorKind};
 
 use crate::GateType;
+use crate::v5::GateCounts;
+pub mod delta;
 #[cfg(test)]
 mod integration;
 pub mod reader;
@@ -36,6 +39,9 @@ pub const MAGIC: [u8; 4] = *b"Zk2u";
 pub const VERSION: u8 = 0x06;
 pub const FORMAT_TYPE_A: u8 = 0x00;
 
+/// Header flag (first reserved byte) marking delta-encoded wire IDs
+pub const FLAG_DELTA_WIRES: u8 = 0x01;
+
 // SoA block segment sizes (fixed by spec)
 pub const IN1_OFFSET: usize = 0;
 pub const IN2_OFFSET: usize = IN1_OFFSET + IN_STREAM_SIZE; // 1088
@@ -75,9 +81,28 @@ impl HeaderV5a {
             .checked_add(self.and_gates)
             .expect("total gate overflow")
     }
+
+    pub fn gate_counts(&self) -> GateCounts {
+        GateCounts::new(self.xor_gates, self.and_gates)
+    }
 }
 
 fn parse_header(bytes: &[u8; HEADER_SIZE_V5A]) -> io::Result<HeaderV5a> {
+    if bytes[6] == FLAG_DELTA_WIRES && bytes[7] == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "delta-encoded v5a (open with delta::DeltaReaderV5a)",
+        ));
+    }
+    parse_header_with_flags(bytes, 0)
+}
+
+/// Parse a v5a header whose first reserved byte must equal `flags`.
+///
+/// Pass 0 for plain v5a and [`FLAG_DELTA_WIRES`] for delta-encoded v5a.
+/// Lets callers that only need the header read its bytes themselves rather
+/// than opening a reader, which starts streaming the gate region.
+pub fn parse_header_with_flags(bytes: &[u8; HEADER_SIZE_V5A], flags: u8) -> io::Result<HeaderV5a> {
     if bytes[0..4] != MAGIC {
         return Err(Error::new(ErrorKind::InvalidData, "bad magic"));
     }
@@ -90,7 +115,7 @@ fn parse_header(bytes: &[u8; HEADER_SIZE_V5A]) -> io::Result<HeaderV5a> {
             "bad format_type (expected 0x00 for v5a)",
         ));
     }
-    if bytes[6] != 0 || bytes[7] != 0 {
+    if bytes[6] != flags || bytes[7] != 0 {
         return Err(Error::new(
             ErrorKind::InvalidData,
             "reserved bytes not zero",
@@ -112,7 +137,7 @@ fn parse_header(bytes: &[u8; HEADER_SIZE_V5A]) -> io::Result<HeaderV5a> {
         magic: MAGIC,
         version: bytes[4],
         format_type: bytes[5],
-        reserved: [0, 0],
+        reserved: [flags, 0],
         memo,
         checksum,
         xor_gates,
@@ -146,4 +171,33 @@ mod tests {
         let total = in1_size + in2_size + out_size + credits_size + types_size;
         assert_eq!(total, BLOCK_SIZE_BYTES);
     }
+
+    /// Hand-written little-endian v5a header, independent of host byte order.
+    fn le_header_bytes() -> [u8; HEADER_SIZE_V5A] {
+        let mut b = [0u8; HEADER_SIZE_V5A];
+        b[0..4].copy_from_slice(b"Zk2u");
+        b[4] = 0x06;
+        b[5] = 0x00;
+        b[8..40].fill(0xAB); // memo
+        b[40..72].fill(0xCD); // checksum
+        b[72..80].copy_from_slice(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        b[80..88].copy_from_slice(&[0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        b[88..96].copy_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        b[96..104].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00]);
+        b
+    }
+
+    #[test]
+    fn test_parse_header_little_endian_layout() {
+        let header = parse_header(&le_header_bytes()).unwrap();
+        assert_eq!(header.magic, MAGIC);
+        assert_eq!(header.version, VERSION);
+        assert_eq!(header.format_type, FORMAT_TYPE_A);
+        assert_eq!(header.memo, [0xAB; 32]);
+        assert_eq!(header.checksum, [0xCD; 32]);
+        assert_eq!(header.xor_gates, 0x0102_0304_0506_0708);
+        assert_eq!(header.and_gates, 0x10);
+        assert_eq!(header.primary_inputs, 0x100);
+        assert_eq!(header.num_outputs, 0xFFFF_FFFF);
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::io::{Error, ErrorKind, Read, Result};
 use std::path::{Path, PathBuf};
 use std::pin::pin;
@@ -9,16 +10,45 @@ use monoio::{FusionDriver, select};
 
 use crate::GateType;
 use crate::v5::a::{
-    BLOCK_SIZE_BYTES, GATES_PER_BLOCK, GateV5a, HEADER_SIZE_V5A, HeaderV5a, parse_header,
+    BLOCK_SIZE_BYTES, GATES_PER_BLOCK, GateV5a, HEADER_SIZE_V5A, HeaderV5a, MAX_WIRE_ID,
+    parse_header,
 };
-use crate::v5::decode_block_v5a;
+use crate::v5::{GateCounts, decode_block_v5a};
 use cynosure::site_d::triplebuffer::{
     AlignedBuffer, BUFFER_ALIGN, BufferStats, TripleBufReader, TripleBufWriter, triple_buffer,
 };
 
+/// Read the `num_outputs` 5-byte output entries that follow the header,
+/// with `file` positioned just past the header.
+///
+/// The declared count is checked against the file size before anything is
+/// allocated, so a truncated file or a corrupt count is an error rather than
+/// a huge allocation or a bare `UnexpectedEof`.
+pub(super) fn read_outputs_section(file: &mut std::fs::File, num_outputs: u64) -> Result<Vec<u8>> {
+    let available = file
+        .metadata()?
+        .len()
+        .saturating_sub(HEADER_SIZE_V5A as u64);
+    let len = num_outputs
+        .checked_mul(5)
+        .filter(|&len| len <= available)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "header declares {} outputs but only {} bytes follow the header",
+                    num_outputs, available
+                ),
+            )
+        })?;
+    let mut bytes = vec![0u8; len as usize];
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
 // Outputs are 5-byte little-endian entries that must fit in 34 bits.
 // We store them as u64 for convenience.
-fn decode_outputs_le40(bytes: &[u8]) -> Result<Vec<u64>> {
+pub(super) fn decode_outputs_le40(bytes: &[u8]) -> Result<Vec<u64>> {
     if !bytes.len().is_multiple_of(5) {
         return Err(Error::new(
             ErrorKind::InvalidData,
@@ -32,10 +62,13 @@ fn decode_outputs_le40(bytes: &[u8]) -> Result<Vec<u64>> {
         let mut buf = [0u8; 8];
         buf[0..5].copy_from_slice(&bytes[base..base + 5]);
         let v = u64::from_le_bytes(buf);
-        if (v >> 34) != 0 {
+        if v > MAX_WIRE_ID {
             return Err(Error::new(
                 ErrorKind::InvalidData,
-                "output wire id has nonzero upper bits",
+                format!(
+                    "output {} wire id {:#x} exceeds MAX_WIRE_ID {:#x}",
+                    i, v, MAX_WIRE_ID
+                ),
             ));
         }
         out.push(v);
@@ -55,6 +88,26 @@ pub struct DecodedBlockSoA<'a> {
     pub gates_in_block: usize,
 }
 
+impl DecodedBlockSoA<'_> {
+    /// Gate `i` of the block, gathered from the SoA arrays.
+    #[inline]
+    pub fn gate(&self, i: usize) -> GateV5a {
+        GateV5a {
+            in1: self.in1[i],
+            in2: self.in2[i],
+            out: self.out[i],
+            credits: self.credits[i],
+            gate_type: self.gate_types[i],
+        }
+    }
+
+    /// Iterate over the block's gates in order, for consumers that don't
+    /// need the SoA layout.
+    pub fn gates(&self) -> impl ExactSizeIterator<Item = GateV5a> + '_ {
+        (0..self.gates_in_block).map(|i| self.gate(i))
+    }
+}
+
 // ================= Reader =================
 
 pub struct CircuitReaderV5a {
@@ -64,6 +117,10 @@ pub struct CircuitReaderV5a {
     // Stream accounting
     gates_remaining: u64,
 
+    // Where the gate region lives, for restarting the stream on seek
+    path: PathBuf,
+    gate_region_start: u64,
+
     // Triple buffer + IO thread
     reader: TripleBufReader,
     stop_tx: Option<AsyncSender<()>>,
@@ -102,13 +159,8 @@ impl CircuitReaderV5a {
         let header = parse_header(&hdr_bytes)?;
 
         // Outputs region
-        let outputs_bytes_len = (header.num_outputs as usize)
-            .checked_mul(5)
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "outputs length overflow"))?;
-        let mut outputs_bytes = vec![0u8; outputs_bytes_len];
-        if outputs_bytes_len > 0 {
-            f.read_exact(&mut outputs_bytes)?;
-        }
+        let outputs_bytes = read_outputs_section(&mut f, header.num_outputs)?;
+        let outputs_bytes_len = outputs_bytes.len();
         let outputs = decode_outputs_le40(&outputs_bytes)?;
 
         // Stream boundaries for gate region (blocks area)
@@ -119,50 +171,25 @@ impl CircuitReaderV5a {
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "gate region bytes overflow"))?
             as u64;
 
-        // Triple buffer and IO thread
-        let (mut writer, reader, writer_buf) = triple_buffer();
-
         // Gate region file offsets
         let start_off = HEADER_SIZE_V5A as u64 + outputs_bytes_len as u64;
         let end_off = start_off + gate_region_bytes;
-
-        // Aligned O_DIRECT region [aligned_start, aligned_end)
-        let aligned_mask = (BUFFER_ALIGN as u64) - 1;
-        let aligned_start = start_off & !aligned_mask;
-        let aligned_end = end_off & !aligned_mask;
-        let prefix_skip = (start_off - aligned_start) as usize;
-        let tail_len = (end_off - aligned_end) as usize;
-
-        // Async stop channel for IO thread
-        let (stop_tx, stop_rx) = bounded_async(1);
-        let io_path = path_ref.to_path_buf();
-
-        let jh = thread::spawn(move || {
-            if let Err(e) = io_thread_run(
-                io_path,
-                aligned_start,
-                aligned_end,
-                tail_len,
-                &mut writer,
-                stop_rx,
-                writer_buf,
-            ) {
-                eprintln!("v5a IO thread error: {e}");
-            }
-        });
+        let stream = spawn_io_thread(path_ref, start_off, end_off);
 
         Ok(Self {
             header,
             outputs,
             gates_remaining: total_gates,
-            reader,
-            stop_tx: Some(stop_tx),
-            io_jh: Some(jh),
+            path: path_ref.to_path_buf(),
+            gate_region_start: start_off,
+            reader: stream.reader,
+            stop_tx: Some(stream.stop_tx),
+            io_jh: Some(stream.io_jh),
             cur_buf: None,
             cur_pos: 0,
             bytes_remaining: gate_region_bytes,
-            prefix_skip,
-            first_chunk: prefix_skip > 0,
+            prefix_skip: stream.prefix_skip,
+            first_chunk: stream.prefix_skip > 0,
             block_staging: [0u8; BLOCK_SIZE_BYTES],
             in1: [0u64; GATES_PER_BLOCK],
             in2: [0u64; GATES_PER_BLOCK],
@@ -182,6 +209,10 @@ impl CircuitReaderV5a {
         &self.outputs
     }
 
+    pub fn gate_counts(&self) -> GateCounts {
+        self.header.gate_counts()
+    }
+
     // Async, allocation-free SoA decode.
     // Returns Ok(None) on end-of-stream.
     pub async fn next_block_soa(&mut self) -> Result<Option<DecodedBlockSoA<'_>>> {
@@ -235,23 +266,105 @@ impl CircuitReaderV5a {
 
     // Async convenience: returns owned AoS Vec. Less efficient than next_block_soa.
     pub async fn next_block(&mut self) -> Result<Option<Vec<GateV5a>>> {
-        let block = match self.next_block_soa().await {
-            Ok(Some(block)) => block,
-            Err(e) => return Err(e),
-            Ok(None) => return Ok(None),
-        };
-        let n = block.gates_in_block;
-        let mut v = Vec::with_capacity(n);
-        for i in 0..n {
-            v.push(GateV5a {
-                in1: block.in1[i],
-                in2: block.in2[i],
-                out: block.out[i],
-                credits: block.credits[i],
-                gate_type: block.gate_types[i],
-            });
+        Ok(self
+            .next_block_soa()
+            .await?
+            .map(|block| block.gates().collect()))
+    }
+
+    /// Reposition the reader so the next block returned is the one holding
+    /// gate `gate_index`.
+    ///
+    /// Seeking has block granularity: the next block starts at gate
+    /// `gate_index - gate_index % GATES_PER_BLOCK`, which may be before the
+    /// requested gate. Its `block_index` tells the caller where it begins.
+    /// Seeking backwards is allowed. The I/O thread is restarted at the
+    /// new offset, so this costs a thread spawn.
+    pub async fn seek_to_gate(&mut self, gate_index: u64) -> Result<()> {
+        let total_gates = self.header.total_gates();
+        if gate_index >= total_gates {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "gate index {} out of range for {} gates",
+                    gate_index, total_gates
+                ),
+            ));
+        }
+
+        let block_index = gate_index / GATES_PER_BLOCK as u64;
+        let blocks_total = total_gates.div_ceil(GATES_PER_BLOCK as u64);
+        let start_off = self.gate_region_start + block_index * BLOCK_SIZE_BYTES as u64;
+        let end_off = self.gate_region_start + blocks_total * BLOCK_SIZE_BYTES as u64;
+
+        self.stop_io_thread();
+        let stream = spawn_io_thread(&self.path, start_off, end_off);
+        self.reader = stream.reader;
+        self.stop_tx = Some(stream.stop_tx);
+        self.io_jh = Some(stream.io_jh);
+        self.prefix_skip = stream.prefix_skip;
+        self.first_chunk = stream.prefix_skip > 0;
+
+        self.cur_buf = None;
+        self.cur_pos = 0;
+        self.bytes_remaining = end_off - start_off;
+        self.gates_remaining = total_gates - block_index * GATES_PER_BLOCK as u64;
+        self.block_index = block_index;
+        Ok(())
+    }
+
+    /// Check that every gate only reads wires that already exist.
+    ///
+    /// A wire exists if it is a constant or primary input, or if an earlier
+    /// gate wrote it. Gates writing one of their own inputs are rejected
+    /// too, as executing them reads a wire while overwriting it. The first
+    /// offending gate is reported by index as `InvalidData`. This streams the whole file, remembering every
+    /// gate output, so it is opt-in. The reader is rewound to the first gate
+    /// afterwards, ready for normal streaming.
+    pub async fn validate_topological(&mut self) -> Result<()> {
+        if self.header.total_gates() == 0 {
+            return Ok(());
+        }
+        self.seek_to_gate(0).await?;
+
+        let permanent = self.header.primary_inputs + 2;
+        let mut written = HashSet::new();
+        let mut gate_index = 0u64;
+        while let Some(block) = self.next_block_soa().await? {
+            for gate in block.gates() {
+                if gate.out == gate.in1 || gate.out == gate.in2 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("gate {} writes wire {} it reads", gate_index, gate.out),
+                    ));
+                }
+                for wire in [gate.in1, gate.in2] {
+                    if wire >= permanent && !written.contains(&wire) {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "gate {} reads wire {} before any gate writes it",
+                                gate_index, wire
+                            ),
+                        ));
+                    }
+                }
+                written.insert(gate.out);
+                gate_index += 1;
+            }
+        }
+
+        self.seek_to_gate(0).await
+    }
+
+    fn stop_io_thread(&mut self) {
+        // Signal IO thread to stop and join
+        if let Some(tx) = self.stop_tx.take() {
+            let _ = tx.to_sync().send(());
+        }
+        if let Some(jh) = self.io_jh.take() {
+            let _ = jh.join();
         }
-        Ok(Some(v))
     }
 
     // ======== internal: fill the staging buffer with the next packed block ========
@@ -322,13 +435,53 @@ impl CircuitReaderV5a {
 
 impl Drop for CircuitReaderV5a {
     fn drop(&mut self) {
-        // Signal IO thread to stop and join
-        if let Some(tx) = self.stop_tx.take() {
-            let _ = tx.to_sync().send(());
-        }
-        if let Some(jh) = self.io_jh.take() {
-            let _ = jh.join();
+        self.stop_io_thread();
+    }
+}
+
+struct IoStream {
+    reader: TripleBufReader,
+    stop_tx: AsyncSender<()>,
+    io_jh: thread::JoinHandle<()>,
+    // Bytes before start_off in the first aligned chunk
+    prefix_skip: usize,
+}
+
+// Spawn an IO thread streaming the file region [start_off, end_off).
+fn spawn_io_thread(path: &Path, start_off: u64, end_off: u64) -> IoStream {
+    // Triple buffer and IO thread
+    let (mut writer, reader, writer_buf) = triple_buffer();
+
+    // Aligned O_DIRECT region [aligned_start, aligned_end)
+    let aligned_mask = (BUFFER_ALIGN as u64) - 1;
+    let aligned_start = start_off & !aligned_mask;
+    let aligned_end = end_off & !aligned_mask;
+    let prefix_skip = (start_off - aligned_start) as usize;
+    let tail_len = (end_off - aligned_end) as usize;
+
+    // Async stop channel for IO thread
+    let (stop_tx, stop_rx) = bounded_async(1);
+    let io_path = path.to_path_buf();
+
+    let io_jh = thread::spawn(move || {
+        if let Err(e) = io_thread_run(
+            io_path,
+            aligned_start,
+            aligned_end,
+            tail_len,
+            &mut writer,
+            stop_rx,
+            writer_buf,
+        ) {
+            eprintln!("v5a IO thread error: {e}");
         }
+    });
+
+    IoStream {
+        reader,
+        stop_tx,
+        io_jh,
+        prefix_skip,
     }
 }
 
@@ -380,13 +533,9 @@ fn io_thread_run(
                 let mut buf = write_buf;
 
                 while off < aligned_end {
-                    // race read with stop
-                    let read_fut = file.read_at(buf, off);
-                    let stop_fut = stop_rx.recv();
-                    let (res, b) = select! {
-                        _ = stop_fut => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    // Never race a read with stop: dropping the op mid-flight frees
+                    // `buf` while the kernel may still be writing into it
+                    let (res, b) = file.read_at(buf, off).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -411,12 +560,7 @@ fn io_thread_run(
                     opts.read(true);
                     let file_tail = opts.open(&path).await?;
 
-                    let read_fut = file_tail.read_at(buf, aligned_end);
-                    let stop_fut = stop_rx.recv();
-                    let (res, b) = select! {
-                        _ = stop_fut => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    let (res, b) = file_tail.read_at(buf, aligned_end).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -438,12 +582,7 @@ fn io_thread_run(
                 let mut buf = write_buf;
 
                 while off < end {
-                    let read_fut = file.read_at(buf, off);
-                    let stop_fut = stop_rx.recv();
-                    let (res, b) = select! {
-                        _ = stop_fut => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    let (res, b) = file.read_at(buf, off).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -468,12 +607,42 @@ fn io_thread_run(
 
 /// Verifies the checksum of a v5a file
 pub async fn verify_v5a_checksum(path: impl AsRef<Path>) -> Result<bool> {
-    let path = path.as_ref();
-
     let mut opts = monoio::fs::OpenOptions::new();
     opts.read(true);
-    let file = opts.open(path).await?;
+    let file = opts.open(path.as_ref()).await?;
+
+    let (stored, computed) = compute_v5a_checksum(&file, None).await?;
+    Ok(computed == stored)
+}
+
+/// Recomputes the checksum of a v5a file and rewrites it in the header.
+///
+/// The header must parse and the file length must match the layout it
+/// describes; only the 32 checksum bytes are written. Returns whether the
+/// stored checksum was stale.
+pub async fn repair_v5a_checksum(path: impl AsRef<Path>) -> Result<bool> {
+    let mut opts = monoio::fs::OpenOptions::new();
+    opts.read(true).write(true);
+    let file = opts.open(path.as_ref()).await?;
 
+    let file_len = std::fs::metadata(path.as_ref())?.len();
+    let (stored, computed) = compute_v5a_checksum(&file, Some(file_len)).await?;
+    if computed == stored {
+        return Ok(false);
+    }
+
+    let (res, _) = file.write_all_at(computed.to_vec(), 40).await;
+    res?;
+    file.sync_all().await?;
+    Ok(true)
+}
+
+/// Returns the (stored, computed) checksums, first checking the file is
+/// exactly `file_len` bytes if given.
+async fn compute_v5a_checksum(
+    file: &monoio::fs::File,
+    file_len: Option<u64>,
+) -> Result<([u8; 32], [u8; 32])> {
     // Header
     let (res, header_vec) = file.read_exact_at(vec![0u8; HEADER_SIZE_V5A], 0).await;
     res?;
@@ -482,7 +651,8 @@ pub async fn verify_v5a_checksum(path: impl AsRef<Path>) -> Result<bool> {
         .try_into()
         .map_err(|_| Error::new(ErrorKind::InvalidData, "header size mismatch"))?;
     let hdr = parse_header(&header)?;
-    let file_checksum = &header[40..72];
+    let mut stored = [0u8; 32];
+    stored.copy_from_slice(&header[40..72]);
     let outputs_len = (hdr.num_outputs as usize)
         .checked_mul(5)
         .ok_or_else(|| Error::new(ErrorKind::InvalidData, "outputs length overflow"))?;
@@ -498,6 +668,17 @@ pub async fn verify_v5a_checksum(path: impl AsRef<Path>) -> Result<bool> {
         .checked_mul(BLOCK_SIZE_BYTES)
         .ok_or_else(|| Error::new(ErrorKind::InvalidData, "blocks bytes overflow"))?;
 
+    let expected_len = (HEADER_SIZE_V5A + outputs_len + blocks_bytes) as u64;
+    if let Some(len) = file_len.filter(|&len| len != expected_len) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "file is {} bytes but header describes {} bytes",
+                len, expected_len
+            ),
+        ));
+    }
+
     if blocks_bytes > 0 {
         let start = (HEADER_SIZE_V5A + outputs_len) as u64;
         let chunk = 1 << 20;
@@ -526,7 +707,7 @@ pub async fn verify_v5a_checksum(path: impl AsRef<Path>) -> Result<bool> {
     hasher.update(&header[0..40]);
     hasher.update(&header[72..104]);
 
-    Ok(hasher.finalize().as_bytes() == file_checksum)
+    Ok((stored, *hasher.finalize().as_bytes()))
 }
 
 #[cfg(test)]
@@ -566,6 +747,66 @@ mod tests {
         w.finalize().await.unwrap();
     }
 
+    #[monoio::test]
+    async fn reader_dropped_mid_stream_leaves_heap_intact() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("early_drop.v5a");
+        let n = 200 * GATES_PER_BLOCK as u64;
+        let gates: Vec<GateV5a> = (0..n).map(mk_gate).collect();
+        write_file(&path, 2, vec![4], [0u8; 32], &gates).await;
+
+        // Stop the IO thread while reads are in flight, then reuse the heap;
+        // a read dropped mid-flight would land in the fresh allocation
+        for round in 0..200 {
+            let mut r = CircuitReaderV5a::open(&path).unwrap();
+            if round % 2 == 1 {
+                assert!(r.next_block_soa().await.unwrap().is_some());
+            }
+            drop(r);
+            let fresh = vec![round as u8; 1 << 20];
+            assert!(fresh.iter().all(|&b| b == round as u8));
+        }
+
+        let mut r = CircuitReaderV5a::open(&path).unwrap();
+        let mut seen = 0;
+        while let Some(block) = r.next_block_soa().await.unwrap() {
+            for i in 0..block.gates_in_block {
+                assert_eq!(block.out[i], gates[seen + i].out);
+            }
+            seen += block.gates_in_block;
+        }
+        assert_eq!(seen as u64, n);
+    }
+
+    #[monoio::test]
+    async fn reader_open_rejects_truncated_or_out_of_range_outputs() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("outputs.v5a");
+        write_file(&path, 4, vec![2, 3, 4, 5], [0u8; 32], &[]).await;
+
+        // Cut the file off two bytes into the second output
+        let truncated = dir.path().join("truncated.v5a");
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&truncated, &bytes[..HEADER_SIZE_V5A + 7]).unwrap();
+        let err = CircuitReaderV5a::open(&truncated).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "header declares 4 outputs but only 7 bytes follow the header"
+        );
+
+        // Set bit 34 of the third output's wire id
+        let mut bytes = bytes;
+        bytes[HEADER_SIZE_V5A + 2 * 5 + 4] |= 0x04;
+        std::fs::write(&path, &bytes).unwrap();
+        let err = CircuitReaderV5a::open(&path).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "output 2 wire id 0x400000004 exceeds MAX_WIRE_ID 0x3ffffffff"
+        );
+    }
+
     #[monoio::test]
     async fn reader_open_header_outputs_and_no_blocks() {
         let dir = tempdir().unwrap();
@@ -767,4 +1008,191 @@ mod tests {
 
         assert!(!verify_v5a_checksum(&path).await.unwrap());
     }
+
+    #[monoio::test]
+    async fn gates_iterator_matches_soa_arrays() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("iter.v5a");
+
+        // One full block and a partial one
+        let n = GATES_PER_BLOCK as u64 + 17;
+        let gates: Vec<_> = (0..n).map(mk_gate).collect();
+        write_file(&path, 9, vec![7, 8], [0u8; 32], &gates).await;
+
+        let mut r = CircuitReaderV5a::open(&path).unwrap();
+        let mut seen = 0;
+        while let Some(block) = r.next_block_soa().await.unwrap() {
+            let iter = block.gates();
+            assert_eq!(iter.len(), block.gates_in_block);
+            let mut count = 0;
+            for (i, g) in iter.enumerate() {
+                assert_eq!(g.in1, block.in1[i]);
+                assert_eq!(g.in2, block.in2[i]);
+                assert_eq!(g.out, block.out[i]);
+                assert_eq!(g.credits, block.credits[i]);
+                assert_eq!(g.gate_type, block.gate_types[i]);
+                assert_eq!(g.out, gates[seen + i].out);
+                count += 1;
+            }
+            assert_eq!(count, block.gates_in_block);
+            seen += count;
+        }
+        assert_eq!(seen as u64, n);
+    }
+
+    #[monoio::test]
+    async fn validate_topological_reports_forward_reference() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("topo.v5a");
+
+        // Wires 0..4 are constants and primary inputs; the XOR writing 5
+        // comes after the AND that reads it
+        let gate = |in1, in2, out, gate_type| GateV5a {
+            in1,
+            in2,
+            out,
+            credits: 1,
+            gate_type,
+        };
+        let mut gates = vec![
+            gate(2, 3, 4, GateType::AND),
+            gate(4, 5, 6, GateType::AND),
+            gate(2, 3, 5, GateType::XOR),
+        ];
+        write_file(&path, 2, vec![6], [0u8; 32], &gates).await;
+
+        let mut r = CircuitReaderV5a::open(&path).unwrap();
+        let err = r.validate_topological().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("gate 1 reads wire 5"), "{}", err);
+
+        // Sorted, the same gates pass and the reader starts from the top
+        gates.swap(1, 2);
+        write_file(&path, 2, vec![6], [0u8; 32], &gates).await;
+        let mut r = CircuitReaderV5a::open(&path).unwrap();
+        r.validate_topological().await.unwrap();
+        let block = r.next_block_soa().await.unwrap().unwrap();
+        assert_eq!(block.gates_in_block, 3);
+        assert_eq!(block.out[2], 6);
+    }
+
+    #[monoio::test]
+    async fn validate_topological_reports_self_referential_gate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("self.v5a");
+
+        let gate = |in1, in2, out, gate_type| GateV5a {
+            in1,
+            in2,
+            out,
+            credits: 1,
+            gate_type,
+        };
+        let valid = vec![
+            gate(2, 3, 4, GateType::AND),
+            gate(4, 2, 5, GateType::XOR),
+            gate(5, 4, 6, GateType::AND),
+        ];
+        write_file(&path, 2, vec![6], [0u8; 32], &valid).await;
+        CircuitReaderV5a::open(&path)
+            .unwrap()
+            .validate_topological()
+            .await
+            .unwrap();
+
+        // Wire 4 exists by then, so only the self-reference is wrong
+        for (in1, in2) in [(4, 2), (2, 4)] {
+            let mut gates = valid.clone();
+            gates[1] = gate(in1, in2, 4, GateType::XOR);
+            write_file(&path, 2, vec![6], [0u8; 32], &gates).await;
+            let err = CircuitReaderV5a::open(&path)
+                .unwrap()
+                .validate_topological()
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert!(
+                err.to_string().contains("gate 1 writes wire 4 it reads"),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[monoio::test]
+    async fn seek_to_gate_starts_at_containing_block() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("seek.v5a");
+
+        let n = GATES_PER_BLOCK as u64 * 5 + 40;
+        let gates: Vec<_> = (0..n).map(mk_gate).collect();
+        write_file(&path, 9, vec![7, 8], [0u8; 32], &gates).await;
+
+        let mut r = CircuitReaderV5a::open(&path).unwrap();
+        // Read a little first so the seek has to discard buffered data
+        r.next_block_soa().await.unwrap().unwrap();
+
+        let target = GATES_PER_BLOCK as u64 * 3 + 10;
+        r.seek_to_gate(target).await.unwrap();
+        let block = r.next_block_soa().await.unwrap().unwrap();
+        assert_eq!(block.block_index, 3);
+        let start = GATES_PER_BLOCK * 3;
+        for (i, g) in gates[start..start + GATES_PER_BLOCK].iter().enumerate() {
+            assert_eq!(block.in1[i], g.in1);
+            assert_eq!(block.out[i], g.out);
+            assert_eq!(block.credits[i], g.credits);
+        }
+        assert_eq!(block.out[10], gates[target as usize].out);
+
+        // Backwards, then into the partial last block and on to the end
+        r.seek_to_gate(5).await.unwrap();
+        let block = r.next_block_soa().await.unwrap().unwrap();
+        assert_eq!(block.block_index, 0);
+        assert_eq!(block.out[5], gates[5].out);
+
+        r.seek_to_gate(n - 1).await.unwrap();
+        let block = r.next_block_soa().await.unwrap().unwrap();
+        assert_eq!(block.block_index, 5);
+        assert_eq!(block.gates_in_block, 40);
+        assert!(r.next_block_soa().await.unwrap().is_none());
+
+        let err = r.seek_to_gate(n).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[monoio::test]
+    async fn repair_rewrites_stale_checksum() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("repair.v5a");
+
+        let gates: Vec<_> = (0..300u64).map(mk_gate).collect();
+        write_file(&path, 9, vec![7, 8], [0u8; 32], &gates).await;
+        assert!(!repair_v5a_checksum(&path).await.unwrap());
+
+        // Corrupt only the stored checksum
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[40..72].fill(0xEE);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(!verify_v5a_checksum(&path).await.unwrap());
+
+        assert!(repair_v5a_checksum(&path).await.unwrap());
+        assert!(verify_v5a_checksum(&path).await.unwrap());
+        let repaired = std::fs::read(&path).unwrap();
+        assert_eq!(repaired[..40], bytes[..40]);
+        assert_eq!(repaired[72..], bytes[72..]);
+    }
+
+    #[monoio::test]
+    async fn repair_rejects_truncated_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("truncated.v5a");
+
+        let gates: Vec<_> = (0..300u64).map(mk_gate).collect();
+        write_file(&path, 9, vec![7, 8], [0u8; 32], &gates).await;
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+
+        let err = repair_v5a_checksum(&path).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
 }
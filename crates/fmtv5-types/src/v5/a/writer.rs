@@ -9,6 +9,8 @@
 
 use blake3::Hasher;
 use monoio::fs::{File, OpenOptions};
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 
@@ -18,6 +20,8 @@ use crate::v5::a::{
     IN2_OFFSET, MAGIC, OUT_OFFSET, TYPES_OFFSET, VERSION,
 };
 
+#[cfg(debug_assertions)]
+use super::CREDITS_OUTPUT;
 use super::{BLOCK_SIZE_BYTES, GATES_PER_BLOCK, GateV5a, MAX_CREDITS, MAX_WIRE_ID};
 
 // Default I/O aggregation buffer size (tunable).
@@ -168,6 +172,11 @@ pub struct CircuitWriterV5a {
 
     // Checksum
     hasher: Hasher,
+
+    // Claimed credits and observed reads per wire, once
+    // `write_gates_with_credits` has been called (debug builds only)
+    #[cfg(debug_assertions)]
+    credit_tally: Option<HashMap<u64, (u32, u32)>>,
 }
 
 impl CircuitWriterV5a {
@@ -218,6 +227,8 @@ impl CircuitWriterV5a {
             xor_gates_written: 0,
             and_gates_written: 0,
             hasher,
+            #[cfg(debug_assertions)]
+            credit_tally: None,
         })
     }
 
@@ -232,8 +243,23 @@ impl CircuitWriterV5a {
     }
 
     /// Write a single gate.
+    ///
+    /// Each block is encoded and hashed as soon as it fills, and the I/O
+    /// buffer is flushed once it reaches capacity, so generators can stream
+    /// gates without holding the whole circuit in memory.
     pub async fn write_gate(&mut self, gate: GateV5a) -> Result<()> {
         self.block.push(gate)?;
+        #[cfg(debug_assertions)]
+        if let Some(tally) = &mut self.credit_tally {
+            for wire in [gate.in1, gate.in2] {
+                if let Some((_, reads)) = tally.get_mut(&wire) {
+                    *reads += 1;
+                }
+            }
+            if gate.credits != CREDITS_OUTPUT {
+                tally.insert(gate.out, (gate.credits, 0));
+            }
+        }
         match gate.gate_type {
             GateType::AND => self.and_gates_written += 1,
             GateType::XOR => self.xor_gates_written += 1,
@@ -253,6 +279,22 @@ impl CircuitWriterV5a {
         Ok(())
     }
 
+    /// Write gates whose credits were assigned by an external analyzer.
+    ///
+    /// Credits are trusted as-is, exactly as with [`write_gates`](Self::write_gates):
+    /// the writer never recomputes them, so a release build accepts whatever
+    /// it is given. In debug builds, every gate written from the first call
+    /// on is tallied, and [`finalize`](Self::finalize) panics if any wire
+    /// produced since then was read a different number of times than its
+    /// credits claim. Wires marked as outputs are not checked.
+    pub async fn write_gates_with_credits(&mut self, gates: &[GateV5a]) -> Result<()> {
+        #[cfg(debug_assertions)]
+        if self.credit_tally.is_none() {
+            self.credit_tally = Some(HashMap::new());
+        }
+        self.write_gates(gates).await
+    }
+
     /// Write multiple gates (generic iterator).
     pub async fn write_gates_iter<I: IntoIterator<Item = GateV5a>>(
         &mut self,
@@ -266,6 +308,24 @@ impl CircuitWriterV5a {
 
     /// Finalize: flush pending data, compute checksum, and write header.
     pub async fn finalize(mut self) -> Result<CircuitStats> {
+        #[cfg(debug_assertions)]
+        if let Some(tally) = self.credit_tally.take() {
+            let mut mismatches: Vec<_> = tally
+                .into_iter()
+                .filter(|(_, (claimed, reads))| claimed != reads)
+                .collect();
+            mismatches.sort_unstable_by_key(|&(wire, _)| wire);
+            if let Some(&(wire, (claimed, reads))) = mismatches.first() {
+                panic!(
+                    "{} wires have wrong credits, first is wire {} with {} credits but {} reads",
+                    mismatches.len(),
+                    wire,
+                    claimed,
+                    reads
+                );
+            }
+        }
+
         // Flush any partial block
         if !self.block.is_empty() {
             self.flush_block().await?;
@@ -293,6 +353,7 @@ impl CircuitWriterV5a {
 
         // Build header bytes (explicit LE encoding, no unsafe)
         let header_bytes = encode_header_v5a_le(
+            0,
             &checksum,
             self.xor_gates_written,
             self.and_gates_written,
@@ -360,7 +421,8 @@ impl CircuitWriterV5a {
 }
 
 /// Encode v5a header to bytes (104 bytes total, LE).
-fn encode_header_v5a_le(
+pub(super) fn encode_header_v5a_le(
+    flags: u8,
     checksum: &[u8; 32],
     xor_gates: u64,
     and_gates: u64,
@@ -373,7 +435,8 @@ fn encode_header_v5a_le(
     h[0..4].copy_from_slice(&MAGIC);
     h[4] = VERSION;
     h[5] = FORMAT_TYPE_A;
-    // h[6..8] reserved zeros
+    // h[6] flags, h[7] reserved zero
+    h[6] = flags;
     h[8..40].copy_from_slice(&memo);
     // Checksum
     h[40..72].copy_from_slice(checksum);
@@ -386,7 +449,7 @@ fn encode_header_v5a_le(
 }
 
 /// Encode outputs to 5-byte little-endian entries (lower 34 bits used, upper 6 must be zero).
-fn encode_outputs_le34(outputs: &[u64]) -> Result<Vec<u8>> {
+pub(super) fn encode_outputs_le34(outputs: &[u64]) -> Result<Vec<u8>> {
     let capacity = outputs
         .len()
         .checked_mul(5)
@@ -648,6 +711,134 @@ mod tests {
         }
     }
 
+    #[monoio::test]
+    async fn test_streamed_and_batched_writes_match() {
+        let dir = tempdir().unwrap();
+        let gates: Vec<GateV5a> = (0..GATES_PER_BLOCK as u64 * 5 + 17)
+            .map(|i| GateV5a {
+                in1: 2 + i % 7,
+                in2: 3 + i % 11,
+                out: 20 + i,
+                credits: (i % 3) as u32,
+                gate_type: if i % 4 == 0 {
+                    GateType::AND
+                } else {
+                    GateType::XOR
+                },
+            })
+            .collect();
+        let outputs = vec![20, 21];
+
+        let batched_path = dir.path().join("batched.v5a");
+        let mut writer = CircuitWriterV5a::new(&batched_path, 16, outputs.clone(), [7u8; 32])
+            .await
+            .unwrap();
+        writer.write_gates(&gates).await.unwrap();
+        let batched = writer.finalize().await.unwrap();
+
+        // Stream one gate at a time through a minimal I/O buffer so full
+        // blocks go to disk as they fill
+        let streamed_path = dir.path().join("streamed.v5a");
+        let mut writer = CircuitWriterV5a::new(&streamed_path, 16, outputs, [7u8; 32])
+            .await
+            .unwrap();
+        writer.set_io_buffer_capacity(0);
+        for &gate in &gates {
+            writer.write_gate(gate).await.unwrap();
+        }
+        let streamed = writer.finalize().await.unwrap();
+
+        assert_eq!(streamed.checksum, batched.checksum);
+        assert_eq!(streamed.total_gates, batched.total_gates);
+        assert_eq!(
+            std::fs::read(&streamed_path).unwrap(),
+            std::fs::read(&batched_path).unwrap()
+        );
+        assert!(verify_file_checksum(&streamed_path).unwrap());
+    }
+
+    // Wire 4 is read once by the AND gate but claims two credits
+    fn gates_with_wrong_credits() -> Vec<GateV5a> {
+        vec![
+            GateV5a {
+                in1: 2,
+                in2: 3,
+                out: 4,
+                credits: 2,
+                gate_type: GateType::XOR,
+            },
+            GateV5a {
+                in1: 2,
+                in2: 4,
+                out: 5,
+                credits: 0,
+                gate_type: GateType::AND,
+            },
+        ]
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[monoio::test]
+    async fn test_external_credits_trusted_in_release() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("external.v5a");
+
+        let mut writer = CircuitWriterV5a::new(&path, 2, vec![5], [0u8; 32])
+            .await
+            .unwrap();
+        writer
+            .write_gates_with_credits(&gates_with_wrong_credits())
+            .await
+            .unwrap();
+        let stats = writer.finalize().await.unwrap();
+
+        assert_eq!(stats.total_gates, 2);
+        assert!(verify_file_checksum(&path).unwrap());
+    }
+
+    #[cfg(debug_assertions)]
+    #[monoio::test]
+    #[should_panic(expected = "wire 4 with 2 credits but 1 reads")]
+    async fn test_external_credits_checked_in_debug() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("external.v5a");
+
+        let mut writer = CircuitWriterV5a::new(&path, 2, vec![5], [0u8; 32])
+            .await
+            .unwrap();
+        writer
+            .write_gates_with_credits(&gates_with_wrong_credits())
+            .await
+            .unwrap();
+        let _ = writer.finalize().await;
+    }
+
+    #[monoio::test]
+    async fn test_external_credits_match_plain_writes() {
+        let dir = tempdir().unwrap();
+        let mut gates = gates_with_wrong_credits();
+        gates[0].credits = 1;
+
+        let plain_path = dir.path().join("plain.v5a");
+        let mut writer = CircuitWriterV5a::new(&plain_path, 2, vec![5], [0u8; 32])
+            .await
+            .unwrap();
+        writer.write_gates(&gates).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        let external_path = dir.path().join("external.v5a");
+        let mut writer = CircuitWriterV5a::new(&external_path, 2, vec![5], [0u8; 32])
+            .await
+            .unwrap();
+        writer.write_gates_with_credits(&gates).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        assert_eq!(
+            std::fs::read(&external_path).unwrap(),
+            std::fs::read(&plain_path).unwrap()
+        );
+    }
+
     #[monoio::test]
     async fn test_writer_rejects_bad_output_id() {
         let dir = tempdir().unwrap();
@@ -737,4 +928,31 @@ mod tests {
 
         Ok(hasher.finalize().as_bytes() == file_checksum)
     }
+
+    #[test]
+    fn test_encode_header_little_endian_layout() {
+        let bytes = encode_header_v5a_le(
+            0,
+            &[0xCD; 32],
+            0x0102_0304_0506_0708,
+            0x10,
+            0x100,
+            0xFFFF_FFFF,
+            [0xAB; 32],
+        );
+
+        assert_eq!(
+            &bytes[0..8],
+            &[b'Z', b'k', b'2', b'u', 0x06, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(&bytes[8..40], &[0xAB; 32]);
+        assert_eq!(&bytes[40..72], &[0xCD; 32]);
+        assert_eq!(
+            &bytes[72..80],
+            &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(&bytes[80..88], &[0x10, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[88..96], &[0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[96..104], &[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0]);
+    }
 }
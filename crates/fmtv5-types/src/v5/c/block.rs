@@ -6,7 +6,8 @@ use super::gate::GateV5c;
 /// A 256 KiB block containing gates and their types
 ///
 /// This struct is #[repr(C)] with exact layout matching the file format.
-/// It can be safely cast from aligned byte buffers.
+/// It can be safely cast from aligned byte buffers. Files with another block
+/// size are read through [`BlockRef`] instead.
 #[repr(C, align(8))]
 pub struct Block {
     /// Gates in execution order (21,620 gates × 12 bytes = 259,440 bytes)
@@ -24,8 +25,89 @@ impl Block {
     /// Get the type of a specific gate in this block.
     #[inline]
     pub fn gate_type(&self, index: usize) -> GateType {
-        debug_assert!(index < GATES_PER_BLOCK);
-        get_gate_type(&self.types, index)
+        BlockRef::from(self).gate_type(index)
+    }
+
+    /// Iterate over the first `num_valid` gates together with their types, in
+    /// execution order.
+    ///
+    /// Pass the block's gate count from [`get_block_num_gates`] so the
+    /// padding in a partial last block is skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_valid` exceeds [`GATES_PER_BLOCK`].
+    #[inline]
+    pub fn iter_gates(&self, num_valid: usize) -> impl ExactSizeIterator<Item = GateWithType> + '_ {
+        BlockRef::from(self).iter_gates(num_valid)
+    }
+}
+
+/// A block of any size, borrowed from a file or buffer
+///
+/// The gates and types sections hold [`BlockLayout::gates_per_block`]
+/// entries of the file's layout.
+#[derive(Clone, Copy)]
+pub struct BlockRef<'b> {
+    /// Gates in execution order
+    pub gates: &'b [GateV5c],
+
+    /// Gate types bit-packed
+    /// Bit N corresponds to gates\[N\]: 0 = XOR, 1 = AND
+    pub types: &'b [u8],
+}
+
+impl<'b> BlockRef<'b> {
+    /// Get the type of a specific gate in this block.
+    #[inline]
+    pub fn gate_type(&self, index: usize) -> GateType {
+        debug_assert!(index < self.gates.len());
+        get_gate_type(self.types, index)
+    }
+
+    /// Iterate over the first `num_valid` gates together with their types, in
+    /// execution order.
+    ///
+    /// Pass the block's gate count from [`BlockLayout::block_num_gates`] so
+    /// the padding in a partial last block is skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_valid` exceeds the number of gates in the block.
+    #[inline]
+    pub fn iter_gates(self, num_valid: usize) -> impl ExactSizeIterator<Item = GateWithType> + 'b {
+        let types = self.types;
+        self.gates[..num_valid]
+            .iter()
+            .enumerate()
+            .map(move |(i, &gate)| GateWithType {
+                gate,
+                gate_type: get_gate_type(types, i),
+            })
+    }
+}
+
+impl<'b> From<&'b Block> for BlockRef<'b> {
+    fn from(block: &'b Block) -> Self {
+        Self {
+            gates: &block.gates,
+            types: &block.types,
+        }
+    }
+}
+
+/// A gate paired with its type, as yielded by [`Block::iter_gates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GateWithType {
+    /// Gate addresses
+    pub gate: GateV5c,
+    /// XOR or AND
+    pub gate_type: GateType,
+}
+
+impl From<GateWithType> for (GateV5c, GateType) {
+    fn from(g: GateWithType) -> Self {
+        (g.gate, g.gate_type)
     }
 }
 
@@ -50,15 +132,118 @@ pub fn set_gate_type(types: &mut [u8], gate_index: usize, gate_type: GateType) {
     }
 }
 
-/// Get the number of valid gates in this block
+/// Get the number of valid gates in this block, for the default block size
 ///
 /// For full blocks this is GATES_PER_BLOCK (21,620).
 /// For the last block it may be less.
 #[inline]
 pub fn get_block_num_gates(total_gates: u64, block_index: usize) -> usize {
-    let gates_before = block_index * GATES_PER_BLOCK;
-    let remaining = total_gates.saturating_sub(gates_before as u64) as usize;
-    remaining.min(GATES_PER_BLOCK)
+    BlockLayout::DEFAULT.block_num_gates(total_gates, block_index)
+}
+
+/// Size and gate capacity of the blocks in a v5c file
+///
+/// Files default to [`BLOCK_SIZE`] blocks, sized for a 1 MB L2 cache. The
+/// header may record another power of two between [`MIN_BLOCK_SIZE`] and
+/// [`MAX_BLOCK_SIZE`] to suit a smaller or larger cache; the bounds keep
+/// blocks tiling the 4 MiB disk buffers exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockLayout {
+    block_size: usize,
+    gates_per_block: usize,
+}
+
+impl BlockLayout {
+    /// 256 KiB blocks of 21,620 gates
+    pub const DEFAULT: Self = Self {
+        block_size: BLOCK_SIZE,
+        gates_per_block: GATES_PER_BLOCK,
+    };
+
+    /// Layout for blocks of `block_size` bytes, or `None` if that size isn't
+    /// allowed
+    ///
+    /// A block holds as many gates as fit alongside their type bits, rounded
+    /// down to an even count so the types section starts 8-byte aligned.
+    pub const fn new(block_size: usize) -> Option<Self> {
+        if !block_size.is_power_of_two()
+            || block_size < MIN_BLOCK_SIZE
+            || block_size > MAX_BLOCK_SIZE
+        {
+            return None;
+        }
+
+        // 12 bytes of addresses plus one type bit per gate
+        let mut gates = (block_size * 8 / (GATE_SIZE * 8 + 1)) & !1;
+        while gates * GATE_SIZE + gates.div_ceil(8) > block_size {
+            gates -= 2;
+        }
+        Some(Self {
+            block_size,
+            gates_per_block: gates,
+        })
+    }
+
+    /// Bytes per block, padding included
+    pub const fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Maximum gates per block
+    pub const fn gates_per_block(&self) -> usize {
+        self.gates_per_block
+    }
+
+    /// Size of the gates section, which starts the block
+    pub const fn gates_size(&self) -> usize {
+        self.gates_per_block * GATE_SIZE
+    }
+
+    /// Offset of the types section within a block
+    pub const fn types_offset(&self) -> usize {
+        self.gates_size()
+    }
+
+    /// Size of the bit-packed types section
+    pub const fn types_size(&self) -> usize {
+        self.gates_per_block.div_ceil(8)
+    }
+
+    /// Number of valid gates in block `block_index` of a circuit with
+    /// `total_gates` gates
+    #[inline]
+    pub fn block_num_gates(&self, total_gates: u64, block_index: usize) -> usize {
+        let gates_before = (block_index as u64).saturating_mul(self.gates_per_block as u64);
+        let remaining = total_gates.saturating_sub(gates_before);
+        remaining.min(self.gates_per_block as u64) as usize
+    }
+
+    /// Number of blocks, including a partial last one, holding `total_gates`
+    pub fn num_blocks(&self, total_gates: u64) -> u64 {
+        total_gates.div_ceil(self.gates_per_block as u64)
+    }
+
+    /// Size of a file with `total_gates` gates and `num_outputs` outputs, or
+    /// `None` if it doesn't fit in a `u64`
+    pub fn file_size(&self, total_gates: u64, num_outputs: u64) -> Option<u64> {
+        let header_padded = ALIGNMENT as u64;
+        let outputs_padded = num_outputs
+            .checked_mul(OUTPUT_ENTRY_SIZE as u64)?
+            .checked_next_multiple_of(ALIGNMENT as u64)?;
+        let blocks_size = self
+            .num_blocks(total_gates)
+            .checked_mul(self.block_size as u64)?;
+
+        header_padded
+            .checked_add(outputs_padded)?
+            .checked_add(blocks_size)
+    }
+}
+
+impl Default for BlockLayout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
 
 /// Calculate padded size for alignment to 256 KiB boundaries
@@ -66,21 +251,18 @@ pub fn padded_size(size: usize) -> usize {
     size.div_ceil(ALIGNMENT) * ALIGNMENT
 }
 
-/// Calculate the file size for a circuit
-pub fn calculate_file_size(total_gates: u64, num_outputs: u64) -> u64 {
-    let header_padded = ALIGNMENT as u64;
-    let outputs_size = num_outputs * OUTPUT_ENTRY_SIZE as u64;
-    let outputs_padded = padded_size(outputs_size as usize) as u64;
-    let num_blocks = total_gates.div_ceil(GATES_PER_BLOCK as u64);
-    let blocks_size = num_blocks * BLOCK_SIZE as u64;
-
-    header_padded + outputs_padded + blocks_size
+/// Calculate the file size for a circuit with the default block size, or
+/// `None` if it doesn't fit in a `u64`
+pub fn calculate_file_size(total_gates: u64, num_outputs: u64) -> Option<u64> {
+    BlockLayout::DEFAULT.file_size(total_gates, num_outputs)
 }
 
 #[cfg(test)]
 mod tests {
+    use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
     use super::*;
-    use crate::v5::c::HeaderV5c;
+    use crate::v5::c::{HeaderV5c, estimate_garbling};
 
     #[test]
     fn test_constants() {
@@ -108,20 +290,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_block_layouts() {
+        assert_eq!(BlockLayout::new(BLOCK_SIZE), Some(BlockLayout::DEFAULT));
+        let default = BlockLayout::DEFAULT;
+        assert_eq!(default.gates_size(), GATES_SIZE);
+        assert_eq!(default.types_offset(), TYPES_OFFSET);
+        assert_eq!(default.types_size(), TYPES_SIZE);
+
+        // What the constant asserts check for the default, for every size
+        let mut block_size = MIN_BLOCK_SIZE;
+        while block_size <= MAX_BLOCK_SIZE {
+            let layout = BlockLayout::new(block_size).unwrap();
+            assert!(layout.gates_size() + layout.types_size() <= block_size);
+            assert!(layout.types_offset().is_multiple_of(8));
+            // Not a gate pair short of what fits
+            let more = layout.gates_per_block() + 2;
+            assert!(more * GATE_SIZE + more.div_ceil(8) > block_size);
+            assert!(DISK_BUFFER_SIZE.is_multiple_of(block_size));
+            block_size *= 2;
+        }
+        assert_eq!(
+            BlockLayout::new(128 * 1024).unwrap().gates_per_block(),
+            10_810
+        );
+
+        for bad in [0, 1, MIN_BLOCK_SIZE / 2, 3 * 64 * 1024, 2 * MAX_BLOCK_SIZE] {
+            assert_eq!(BlockLayout::new(bad), None, "{}", bad);
+        }
+    }
+
     #[test]
     fn test_header_size() {
-        assert_eq!(std::mem::size_of::<HeaderV5c>(), HEADER_SIZE);
+        assert_eq!(core::mem::size_of::<HeaderV5c>(), HEADER_SIZE);
     }
 
     #[test]
     fn test_gate_size() {
-        assert_eq!(std::mem::size_of::<GateV5c>(), GATE_SIZE);
-        assert_eq!(std::mem::align_of::<GateV5c>(), 4);
+        assert_eq!(core::mem::size_of::<GateV5c>(), GATE_SIZE);
+        assert_eq!(core::mem::align_of::<GateV5c>(), 4);
     }
 
     #[test]
     fn test_block_size() {
-        assert_eq!(std::mem::size_of::<Block>(), BLOCK_SIZE);
+        assert_eq!(core::mem::size_of::<Block>(), BLOCK_SIZE);
     }
 
     #[test]
@@ -146,14 +358,14 @@ mod tests {
 
         // Test scratch space overflow (MUST use write_unaligned for packed struct)
         unsafe {
-            std::ptr::write_unaligned(
-                std::ptr::addr_of_mut!(header.scratch_space),
+            core::ptr::write_unaligned(
+                core::ptr::addr_of_mut!(header.scratch_space),
                 MAX_MEMORY_ADDRESS + 1,
             );
         }
         assert!(header.validate().is_err());
         unsafe {
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.scratch_space), 1000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.scratch_space), 1000);
         }
 
         assert!(header.validate().is_ok());
@@ -166,6 +378,26 @@ mod tests {
         assert!(gate.validate(250).is_err()); // out exceeds
     }
 
+    #[test]
+    fn test_gate_from_wide_addresses() {
+        let max = MAX_MEMORY_ADDRESS - 1;
+        assert_eq!(
+            GateV5c::try_from_addresses(1, 2, max).unwrap(),
+            GateV5c::new(1, 2, max as u32)
+        );
+
+        // 2^32 must be rejected, not wrapped to address 0
+        for (addrs, name) in [
+            ([MAX_MEMORY_ADDRESS, 0, 0], "in1"),
+            ([0, MAX_MEMORY_ADDRESS, 0], "in2"),
+            ([0, 0, MAX_MEMORY_ADDRESS], "out"),
+        ] {
+            let err = GateV5c::try_from_addresses(addrs[0], addrs[1], addrs[2]).unwrap_err();
+            assert_eq!(err.kind(), crate::io::ErrorKind::InvalidInput);
+            assert!(err.to_string().starts_with(name), "{}", err);
+        }
+    }
+
     #[test]
     fn test_gate_serialization() {
         let gate = GateV5c::new(0x12345678, 0xABCDEF01, 0xDEADBEEF);
@@ -195,13 +427,44 @@ mod tests {
         assert_eq!(get_gate_type(&types, 21619), GateType::AND);
     }
 
+    #[test]
+    fn test_iter_gates_pairs_gates_with_types() {
+        use crate::GateType;
+
+        // Zeroed gates and types are valid, so build the block on the heap
+        let mut block: Box<Block> = unsafe { Box::new_zeroed().assume_init() };
+        for i in 0..20 {
+            block.gates[i] = GateV5c::new(i as u32, i as u32 + 1, i as u32 + 2);
+            // AND on every third gate, XOR otherwise
+            let gate_type = if i % 3 == 0 {
+                GateType::AND
+            } else {
+                GateType::XOR
+            };
+            set_gate_type(&mut block.types, i, gate_type);
+        }
+
+        // Gate 12 is an AND past num_valid, so it must not be yielded
+        let gates: Vec<GateWithType> = block.iter_gates(10).collect();
+        assert_eq!(gates.len(), 10);
+        for (i, g) in gates.iter().enumerate() {
+            assert_eq!(g.gate, GateV5c::new(i as u32, i as u32 + 1, i as u32 + 2));
+            assert_eq!(g.gate_type == GateType::AND, i % 3 == 0, "gate {}", i);
+            assert_eq!(g.gate_type, block.gate_type(i));
+        }
+
+        let (gate, gate_type) = block.iter_gates(1).next().unwrap().into();
+        assert_eq!((gate, gate_type), (GateV5c::new(0, 1, 2), GateType::AND));
+        assert_eq!(block.iter_gates(GATES_PER_BLOCK).len(), GATES_PER_BLOCK);
+    }
+
     #[test]
     fn test_header_total_gates() {
         let mut header = HeaderV5c::new();
         // MUST use write_unaligned for packed struct fields
         unsafe {
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.xor_gates), 1000);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.and_gates), 2000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.xor_gates), 1000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.and_gates), 2000);
         }
         assert_eq!(header.total_gates(), 3000);
     }
@@ -211,8 +474,8 @@ mod tests {
         let mut header = HeaderV5c::new();
         // MUST use write_unaligned for packed struct fields
         unsafe {
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.xor_gates), 50000);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.and_gates), 50000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.xor_gates), 50000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.and_gates), 50000);
         }
 
         let total = header.total_gates(); // 100,000
@@ -222,6 +485,26 @@ mod tests {
         assert_eq!(header.total_blocks(), 5);
     }
 
+    #[test]
+    fn test_estimate_garbling_counts_only_and_gates() {
+        let mut header = HeaderV5c::new();
+        // MUST use write_unaligned for packed struct fields
+        unsafe {
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.xor_gates), 50000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.and_gates), 50000);
+        }
+
+        let estimate = estimate_garbling(&header);
+        assert_eq!(estimate.and_gates, 50000);
+        assert_eq!(estimate.ciphertext_bytes, 800_000); // 16 bytes per AND gate
+        assert_eq!(estimate.blocks, 5);
+
+        unsafe {
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.and_gates), 0);
+        }
+        assert_eq!(estimate_garbling(&header).ciphertext_bytes, 0);
+    }
+
     #[test]
     fn test_padded_size() {
         assert_eq!(padded_size(88), ALIGNMENT); // Header
@@ -237,21 +520,26 @@ mod tests {
         let expected = ALIGNMENT as u64 + // header
             ALIGNMENT as u64 + // outputs
             BLOCK_SIZE as u64; // 1 block
-        assert_eq!(size, expected);
+        assert_eq!(size, Some(expected));
 
         // Exact block boundary: 21,620 gates
         let size = calculate_file_size(21620, 10);
         let expected = ALIGNMENT as u64 + // header
             ALIGNMENT as u64 + // outputs
             BLOCK_SIZE as u64; // 1 block (exact)
-        assert_eq!(size, expected);
+        assert_eq!(size, Some(expected));
 
         // Two blocks: 21,621 gates
         let size = calculate_file_size(21621, 10);
         let expected = ALIGNMENT as u64 + // header
             ALIGNMENT as u64 + // outputs
             2 * BLOCK_SIZE as u64; // 2 blocks
-        assert_eq!(size, expected);
+        assert_eq!(size, Some(expected));
+
+        // Sizes past u64::MAX
+        assert_eq!(calculate_file_size(0, (1 << 62) + 1), None);
+        assert_eq!(calculate_file_size(0, u64::MAX / 4 - 1), None);
+        assert_eq!(calculate_file_size(u64::MAX, 0), None);
     }
 
     #[test]
@@ -259,11 +547,11 @@ mod tests {
         let mut header = HeaderV5c::new();
         // MUST use write_unaligned for packed struct fields
         unsafe {
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.xor_gates), 1234567);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.and_gates), 7654321);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.primary_inputs), 1000);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.scratch_space), 10000000);
-            std::ptr::write_unaligned(std::ptr::addr_of_mut!(header.num_outputs), 100);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.xor_gates), 1234567);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.and_gates), 7654321);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.primary_inputs), 1000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.scratch_space), 10000000);
+            core::ptr::write_unaligned(core::ptr::addr_of_mut!(header.num_outputs), 100);
         }
         header.checksum = [0x42; 32]; // Arrays are always aligned
 
@@ -277,24 +565,24 @@ mod tests {
         // Use read_unaligned for packed struct fields
         unsafe {
             assert_eq!(
-                std::ptr::read_unaligned(std::ptr::addr_of!(header.xor_gates)),
-                std::ptr::read_unaligned(std::ptr::addr_of!(header2.xor_gates))
+                core::ptr::read_unaligned(core::ptr::addr_of!(header.xor_gates)),
+                core::ptr::read_unaligned(core::ptr::addr_of!(header2.xor_gates))
             );
             assert_eq!(
-                std::ptr::read_unaligned(std::ptr::addr_of!(header.and_gates)),
-                std::ptr::read_unaligned(std::ptr::addr_of!(header2.and_gates))
+                core::ptr::read_unaligned(core::ptr::addr_of!(header.and_gates)),
+                core::ptr::read_unaligned(core::ptr::addr_of!(header2.and_gates))
             );
             assert_eq!(
-                std::ptr::read_unaligned(std::ptr::addr_of!(header.primary_inputs)),
-                std::ptr::read_unaligned(std::ptr::addr_of!(header2.primary_inputs))
+                core::ptr::read_unaligned(core::ptr::addr_of!(header.primary_inputs)),
+                core::ptr::read_unaligned(core::ptr::addr_of!(header2.primary_inputs))
             );
             assert_eq!(
-                std::ptr::read_unaligned(std::ptr::addr_of!(header.scratch_space)),
-                std::ptr::read_unaligned(std::ptr::addr_of!(header2.scratch_space))
+                core::ptr::read_unaligned(core::ptr::addr_of!(header.scratch_space)),
+                core::ptr::read_unaligned(core::ptr::addr_of!(header2.scratch_space))
             );
             assert_eq!(
-                std::ptr::read_unaligned(std::ptr::addr_of!(header.num_outputs)),
-                std::ptr::read_unaligned(std::ptr::addr_of!(header2.num_outputs))
+                core::ptr::read_unaligned(core::ptr::addr_of!(header.num_outputs)),
+                core::ptr::read_unaligned(core::ptr::addr_of!(header2.num_outputs))
             );
         }
         assert_eq!(header.checksum, header2.checksum);
@@ -0,0 +1,236 @@
+//! Checked zero-copy casts from byte buffers to v5c structures.
+//!
+//! Casting a `&[u8]` to `&[GateV5c]` or `&[Block]` is only sound when the
+//! buffer start satisfies the target alignment and the length is an exact
+//! multiple of the element size. Buffers coming from the triple-buffer reader
+//! are always aligned, but slices handed in from elsewhere (e.g. a `Vec<u8>`
+//! offset by a header) need not be, so we check rather than assume.
+
+use core::fmt;
+use core::mem::{align_of, size_of};
+
+use super::block::{Block, BlockLayout, BlockRef};
+use super::gate::GateV5c;
+
+/// Error returned when a byte buffer can't be reinterpreted as a slice of `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentError {
+    /// Buffer start address isn't aligned to the target type.
+    Misaligned {
+        /// Address of the first byte of the buffer.
+        addr: usize,
+        /// Alignment required by the target type.
+        required: usize,
+    },
+
+    /// Buffer length isn't a multiple of the target type's size.
+    BadLength {
+        /// Length of the buffer in bytes.
+        len: usize,
+        /// Size of a single element of the target type.
+        elem_size: usize,
+    },
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::Misaligned { addr, required } => {
+                write!(f, "buffer at {addr:#x} is not aligned to {required} bytes")
+            }
+            AlignmentError::BadLength { len, elem_size } => write!(
+                f,
+                "buffer length {len} is not a multiple of element size {elem_size}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for AlignmentError {}
+
+impl From<AlignmentError> for crate::io::Error {
+    fn from(e: AlignmentError) -> Self {
+        crate::io::Error::new(crate::io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Checks that `bytes` can be viewed as a `[T]` and returns the element count.
+fn check_cast<T>(bytes: &[u8]) -> Result<usize, AlignmentError> {
+    let elem_size = size_of::<T>();
+    let required = align_of::<T>();
+
+    let addr = bytes.as_ptr() as usize;
+    if !addr.is_multiple_of(required) {
+        return Err(AlignmentError::Misaligned { addr, required });
+    }
+
+    if !bytes.len().is_multiple_of(elem_size) {
+        return Err(AlignmentError::BadLength {
+            len: bytes.len(),
+            elem_size,
+        });
+    }
+
+    Ok(bytes.len() / elem_size)
+}
+
+/// Reinterprets a byte buffer as a slice of gates without copying.
+///
+/// Fails if the buffer isn't 4-byte aligned or its length isn't a multiple
+/// of [`GATE_SIZE`](super::GATE_SIZE).
+pub fn as_gates(bytes: &[u8]) -> Result<&[GateV5c], AlignmentError> {
+    let len = check_cast::<GateV5c>(bytes)?;
+    // SAFETY: `GateV5c` is `repr(C)` with only `u32` fields, so every bit
+    // pattern is valid, and we just checked alignment and length.
+    Ok(unsafe { core::slice::from_raw_parts(bytes.as_ptr() as *const GateV5c, len) })
+}
+
+/// Reinterprets a byte buffer as a slice of blocks without copying.
+///
+/// Fails if the buffer isn't 8-byte aligned or its length isn't a multiple
+/// of [`BLOCK_SIZE`](super::BLOCK_SIZE).
+pub fn as_blocks(bytes: &[u8]) -> Result<&[Block], AlignmentError> {
+    let len = check_cast::<Block>(bytes)?;
+    // SAFETY: `Block` is `repr(C)` made up of gates and plain bytes, so every
+    // bit pattern is valid, and we just checked alignment and length.
+    Ok(unsafe { core::slice::from_raw_parts(bytes.as_ptr() as *const Block, len) })
+}
+
+/// Views a byte buffer as a single block of the given layout without
+/// copying.
+///
+/// Fails if the buffer isn't 8-byte aligned, like [`as_blocks`], or isn't
+/// exactly one block long.
+pub fn as_block_ref(bytes: &[u8], layout: BlockLayout) -> Result<BlockRef<'_>, AlignmentError> {
+    if bytes.len() != layout.block_size() {
+        return Err(AlignmentError::BadLength {
+            len: bytes.len(),
+            elem_size: layout.block_size(),
+        });
+    }
+    let addr = bytes.as_ptr() as usize;
+    let required = align_of::<Block>();
+    if !addr.is_multiple_of(required) {
+        return Err(AlignmentError::Misaligned { addr, required });
+    }
+
+    let types_end = layout.types_offset() + layout.types_size();
+    Ok(BlockRef {
+        gates: as_gates(&bytes[..layout.gates_size()])?,
+        types: &bytes[layout.types_offset()..types_end],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::v5::c::{BLOCK_SIZE, GATE_SIZE};
+
+    /// Returns a u64-backed buffer so the start is at least 8-byte aligned.
+    fn aligned_buf(len: usize) -> Vec<u64> {
+        vec![0u64; len.div_ceil(8) + 1]
+    }
+
+    fn as_bytes(buf: &[u64]) -> &[u8] {
+        // SAFETY: u8 has no alignment requirement and any bit pattern is valid.
+        unsafe { core::slice::from_raw_parts(buf.as_ptr() as *const u8, buf.len() * 8) }
+    }
+
+    #[test]
+    fn test_as_gates_aligned() {
+        let mut buf = aligned_buf(GATE_SIZE * 3);
+        let gate_bytes = [
+            GateV5c::new(1, 2, 3).to_bytes(),
+            GateV5c::new(0, 0, 0).to_bytes(),
+            GateV5c::new(7, 8, 9).to_bytes(),
+        ]
+        .concat();
+        // SAFETY: the write stays within the allocation of `buf`.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                gate_bytes.as_ptr(),
+                buf.as_mut_ptr() as *mut u8,
+                gate_bytes.len(),
+            );
+        }
+
+        let gates = as_gates(&as_bytes(&buf)[..GATE_SIZE * 3]).unwrap();
+        assert_eq!(gates.len(), 3);
+        assert_eq!(gates[0], GateV5c::new(1, 2, 3));
+        assert_eq!(gates[1], GateV5c::new(0, 0, 0));
+        assert_eq!(gates[2], GateV5c::new(7, 8, 9));
+    }
+
+    #[test]
+    fn test_as_gates_misaligned() {
+        let buf = aligned_buf(GATE_SIZE * 2 + 1);
+        let bytes = &as_bytes(&buf)[1..1 + GATE_SIZE * 2];
+
+        let err = as_gates(bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            AlignmentError::Misaligned { required: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_as_gates_bad_length() {
+        let buf = aligned_buf(GATE_SIZE * 2);
+        let bytes = &as_bytes(&buf)[..GATE_SIZE + 4];
+
+        assert_eq!(
+            as_gates(bytes).unwrap_err(),
+            AlignmentError::BadLength {
+                len: GATE_SIZE + 4,
+                elem_size: GATE_SIZE,
+            }
+        );
+    }
+
+    #[test]
+    fn test_as_blocks() {
+        let buf = aligned_buf(BLOCK_SIZE + 8);
+        let bytes = as_bytes(&buf);
+
+        assert_eq!(as_blocks(&bytes[..BLOCK_SIZE]).unwrap().len(), 1);
+        assert_eq!(as_blocks(&bytes[..0]).unwrap().len(), 0);
+        assert!(matches!(
+            as_blocks(&bytes[4..4 + BLOCK_SIZE]).err(),
+            Some(AlignmentError::Misaligned { required: 8, .. })
+        ));
+        assert!(matches!(
+            as_blocks(&bytes[..BLOCK_SIZE - 8]).err(),
+            Some(AlignmentError::BadLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_as_block_ref() {
+        let layout = BlockLayout::new(64 * 1024).unwrap();
+        let buf = aligned_buf(layout.block_size() + 8);
+        let bytes = as_bytes(&buf);
+
+        let block = as_block_ref(&bytes[..layout.block_size()], layout).unwrap();
+        assert_eq!(block.gates.len(), layout.gates_per_block());
+        assert_eq!(block.types.len(), layout.types_size());
+        assert!(matches!(
+            as_block_ref(&bytes[8..8 + layout.block_size() - 8], layout).err(),
+            Some(AlignmentError::BadLength { .. })
+        ));
+        assert!(matches!(
+            as_block_ref(&bytes[4..4 + layout.block_size()], layout).err(),
+            Some(AlignmentError::Misaligned { required: 8, .. })
+        ));
+
+        // The default layout views the same bytes as `Block`
+        let buf = aligned_buf(BLOCK_SIZE);
+        let bytes = &as_bytes(&buf)[..BLOCK_SIZE];
+        let block = as_block_ref(bytes, BlockLayout::DEFAULT).unwrap();
+        let typed = BlockRef::from(&as_blocks(bytes).unwrap()[0]);
+        assert_eq!(block.gates.as_ptr(), typed.gates.as_ptr());
+        assert_eq!(block.types.as_ptr(), typed.types.as_ptr());
+        assert_eq!(block.types.len(), typed.types.len());
+    }
+}
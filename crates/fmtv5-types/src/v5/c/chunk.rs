@@ -1,32 +1,60 @@
 //! Chunk of blocks that the reader returns.
 
-use super::Block;
+use super::block::{BlockLayout, BlockRef};
+use super::cast::{AlignmentError, as_block_ref};
 
 /// A chunk of blocks borrowed from the reader.
 #[derive(Clone)]
 #[expect(missing_debug_implementations, reason = "it's garbage data in there")]
 pub struct Chunk<'b> {
-    buf: &'b [u8],
-    num_blocks: usize,
+    bytes: &'b [u8],
+    layout: BlockLayout,
 }
 
 impl<'b> Chunk<'b> {
-    /// Constructs a new instance.
+    /// Constructs a new instance over the first `num_blocks` blocks of `buf`.
     ///
-    /// # Safety
-    ///
-    /// The `num_blocks` value must correspond to the amount of space available
-    /// in the buffer passed, with respect to the size of a [`Block`]
-    pub(crate) unsafe fn from_blocks_buf(buf: &'b [u8], num_blocks: usize) -> Self {
-        Self { buf, num_blocks }
+    /// Fails if the buffer is too short for `num_blocks` blocks or isn't
+    /// aligned for [`Block`](super::Block).
+    pub(crate) fn from_blocks_buf(
+        buf: &'b [u8],
+        num_blocks: usize,
+        layout: BlockLayout,
+    ) -> Result<Self, AlignmentError> {
+        let len = num_blocks * layout.block_size();
+        let bytes = buf.get(..len).ok_or(AlignmentError::BadLength {
+            len: buf.len(),
+            elem_size: layout.block_size(),
+        })?;
+        if let Some(first) = bytes.get(..layout.block_size()) {
+            // Blocks are a multiple of 8 bytes, so the rest are aligned too
+            as_block_ref(first, layout)?;
+        }
+        Ok(Self { bytes, layout })
+    }
+
+    /// Number of blocks in the chunk.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.layout.block_size()
+    }
+
+    /// Whether the chunk holds no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns block `index` of the chunk, if there is one.
+    pub fn get(&self, index: usize) -> Option<BlockRef<'b>> {
+        let size = self.layout.block_size();
+        let bytes = self.bytes.get(index * size..(index + 1) * size)?;
+        Some(as_block_ref(bytes, self.layout).expect("chunk checked on construction"))
     }
 
     /// Returns an iterator over the blocks in the chunk buffer.
-    pub fn blocks_iter(&self) -> impl Iterator<Item = &'b Block> {
-        // SAFETY: we get the number of blocks sized properly from this always
-        unsafe {
-            let blocks = &*(self.buf.as_ptr() as *const [Block; 16]);
-            blocks.iter().take(self.num_blocks)
-        }
+    pub fn blocks_iter(&self) -> impl Iterator<Item = BlockRef<'b>> {
+        let layout = self.layout;
+        self.bytes
+            .chunks_exact(layout.block_size())
+            .map(move |b| as_block_ref(b, layout).expect("chunk checked on construction"))
     }
 }
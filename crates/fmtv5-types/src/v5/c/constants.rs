@@ -44,11 +44,23 @@ pub const DISK_BUFFER_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
 /// Number of processing blocks per disk buffer
 pub const BLOCKS_PER_DISK_BUFFER: usize = DISK_BUFFER_SIZE / BLOCK_SIZE; // 16
 
+/// Smallest block size a header may record (4 KiB)
+pub const MIN_BLOCK_SIZE: usize = 4 * 1024;
+
+/// Largest block size a header may record (one disk buffer)
+pub const MAX_BLOCK_SIZE: usize = DISK_BUFFER_SIZE;
+
+/// Size of a single garbled ciphertext
+pub const CIPHERTEXT_SIZE: usize = 16;
+
+/// Ciphertexts emitted per AND gate (half-gates; XOR gates are free)
+pub const CIPHERTEXTS_PER_AND_GATE: usize = 1;
+
 /// Expected ciphertext vector capacity per block (for garbling)
 pub const CIPHERTEXTS_PER_BLOCK: usize = GATES_PER_BLOCK;
 
 /// Expected ciphertext vector size in bytes (16 bytes per ciphertext)
-pub const CIPHERTEXT_VEC_SIZE: usize = CIPHERTEXTS_PER_BLOCK * 16; // 346,320 bytes
+pub const CIPHERTEXT_VEC_SIZE: usize = CIPHERTEXTS_PER_BLOCK * CIPHERTEXT_SIZE; // 346,320 bytes
 
 // Compile-time assertions
 const _: () = assert!(GATES_SIZE + TYPES_SIZE + BLOCK_PADDING == BLOCK_SIZE);
@@ -1,3 +1,8 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::io::{Error, ErrorKind, Result};
+
 use super::constants::*;
 
 /// A single gate in v5c format (Array-of-Structures layout)
@@ -18,8 +23,33 @@ impl GateV5c {
         GateV5c { in1, in2, out }
     }
 
+    /// Create a gate from wider addresses, such as slab indices.
+    ///
+    /// Fails with `InvalidInput` if any address is at or above
+    /// [`MAX_MEMORY_ADDRESS`], rather than truncating it to 32 bits.
+    pub fn try_from_addresses(in1: u64, in2: u64, out: u64) -> Result<Self> {
+        let narrow = |name: &str, addr: u64| {
+            u32::try_from(addr).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} address {} exceeds maximum {}",
+                        name,
+                        addr,
+                        MAX_MEMORY_ADDRESS - 1
+                    ),
+                )
+            })
+        };
+        Ok(GateV5c {
+            in1: narrow("in1", in1)?,
+            in2: narrow("in2", in2)?,
+            out: narrow("out", out)?,
+        })
+    }
+
     /// Validate gate addresses against scratch space
-    pub fn validate(&self, scratch_space: u64) -> Result<(), String> {
+    pub fn validate(&self, scratch_space: u64) -> core::result::Result<(), String> {
         if self.in1 as u64 >= scratch_space {
             return Err(format!(
                 "in1 address {} exceeds scratch_space {}",
@@ -1,6 +1,11 @@
-use std::io::{self, Error, ErrorKind};
+use alloc::format;
+use alloc::string::{String, ToString};
 
+use crate::io::{self, Error, ErrorKind};
+
+use super::block::BlockLayout;
 use super::constants::*;
+use crate::v5::GateCounts;
 
 /// Header structure for v5c format (120 bytes)
 ///
@@ -23,7 +28,8 @@ pub struct HeaderV5c {
     pub primary_inputs: u64, // Number of primary inputs
     pub scratch_space: u64,  // Maximum scratch space entries needed
     pub num_outputs: u64,    // Number of outputs
-    pub reserved2: [u8; 6],  // Reserved for future use
+    pub block_size: u32,     // Bytes per block, 0 for the default 256 KiB
+    pub reserved2: [u8; 2],  // Reserved for future use
 }
 
 impl HeaderV5c {
@@ -41,7 +47,8 @@ impl HeaderV5c {
             primary_inputs: 0,
             scratch_space: 0,
             num_outputs: 0,
-            reserved2: [0; 6],
+            block_size: 0,
+            reserved2: [0; 2],
         }
     }
 
@@ -52,19 +59,50 @@ impl HeaderV5c {
             .expect("total gate overflow")
     }
 
+    /// Get XOR, AND and total gate counts
+    pub fn gate_counts(&self) -> GateCounts {
+        GateCounts::new(self.xor_gates, self.and_gates)
+    }
+
+    /// Get the block layout recorded in the header
+    ///
+    /// # Panics
+    ///
+    /// If `block_size` is neither 0 nor a size [`BlockLayout::new`] accepts,
+    /// which [`HeaderV5c::validate`] rules out.
+    pub fn block_layout(&self) -> BlockLayout {
+        if self.block_size == 0 {
+            return BlockLayout::DEFAULT;
+        }
+        BlockLayout::new(self.block_size as usize).expect("invalid block size")
+    }
+
+    /// Get the number of gates in block `block_index`
+    pub fn block_num_gates(&self, block_index: usize) -> usize {
+        self.block_layout()
+            .block_num_gates(self.total_gates(), block_index)
+    }
+
     /// Get the number of full blocks
     pub fn num_full_blocks(&self) -> u64 {
-        self.total_gates() / GATES_PER_BLOCK as u64
+        self.total_gates() / self.block_layout().gates_per_block() as u64
     }
 
     /// Get the number of gates in the partial block (0 if none)
     pub fn partial_block_gates(&self) -> usize {
-        (self.total_gates() % GATES_PER_BLOCK as u64) as usize
+        (self.total_gates() % self.block_layout().gates_per_block() as u64) as usize
     }
 
     /// Get total number of blocks (including partial)
     pub fn total_blocks(&self) -> u64 {
-        self.total_gates().div_ceil(GATES_PER_BLOCK as u64)
+        self.block_layout().num_blocks(self.total_gates())
+    }
+
+    /// Get the size of a file holding the padded header, outputs and blocks,
+    /// or `None` if it doesn't fit in a `u64`
+    pub fn expected_file_size(&self) -> Option<u64> {
+        self.block_layout()
+            .file_size(self.total_gates(), self.num_outputs)
     }
 
     /// Validate the header
@@ -101,8 +139,16 @@ impl HeaderV5c {
             ));
         }
 
+        // Check block size, 0 meaning the default
+        if self.block_size != 0 && BlockLayout::new(self.block_size as usize).is_none() {
+            return Err(format!(
+                "Invalid block size {}: must be a power of two from {} to {}",
+                self.block_size, MIN_BLOCK_SIZE, MAX_BLOCK_SIZE
+            ));
+        }
+
         // Check reserved2 field
-        if self.reserved2 != [0; 6] {
+        if self.reserved2 != [0; 2] {
             return Err(format!(
                 "Reserved2 field must be zero, got {:?}",
                 self.reserved2
@@ -110,13 +156,22 @@ impl HeaderV5c {
         }
 
         // Check for gate count overflow
-        if self.total_gates() == u64::MAX {
+        if self
+            .xor_gates
+            .checked_add(self.and_gates)
+            .is_none_or(|total| total == u64::MAX)
+        {
             return Err("Total gate count would overflow".to_string());
         }
 
-        // Check number of outputs
-        if self.num_outputs > self.total_gates() + self.primary_inputs {
-            return Err("Too many outputs for gate and input count".to_string());
+        // Outputs may repeat an address or name a constant, so their count
+        // isn't bounded by the wire count, only by the file size
+        if self.expected_file_size().is_none() {
+            return Err(format!(
+                "Too many outputs: {} outputs and {} blocks overflow the file size",
+                self.num_outputs,
+                self.total_blocks()
+            ));
         }
 
         // Validate scratch space
@@ -179,8 +234,12 @@ impl HeaderV5c {
         bytes[offset..offset + 8].copy_from_slice(&self.num_outputs.to_le_bytes());
         offset += 8;
 
-        // reserved2 (6 bytes)
-        bytes[offset..offset + 6].copy_from_slice(&self.reserved2);
+        // block_size (4 bytes, little-endian)
+        bytes[offset..offset + 4].copy_from_slice(&self.block_size.to_le_bytes());
+        offset += 4;
+
+        // reserved2 (2 bytes)
+        bytes[offset..offset + 2].copy_from_slice(&self.reserved2);
 
         bytes
     }
@@ -289,9 +348,18 @@ impl HeaderV5c {
         ]);
         offset += 8;
 
-        // reserved2 (6 bytes)
-        let mut reserved2 = [0u8; 6];
-        reserved2.copy_from_slice(&bytes[offset..offset + 6]);
+        // block_size (4 bytes, little-endian)
+        let block_size = u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+
+        // reserved2 (2 bytes)
+        let mut reserved2 = [0u8; 2];
+        reserved2.copy_from_slice(&bytes[offset..offset + 2]);
 
         let header = Self {
             magic,
@@ -305,6 +373,7 @@ impl HeaderV5c {
             primary_inputs,
             scratch_space,
             num_outputs,
+            block_size,
             reserved2,
         };
 
@@ -321,3 +390,91 @@ impl Default for HeaderV5c {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hand-written little-endian v5c header, independent of host byte order.
+    fn le_header_bytes() -> [u8; HEADER_SIZE] {
+        let mut b = [0u8; HEADER_SIZE];
+        b[0..4].copy_from_slice(b"Zk2u");
+        b[4] = 0x06;
+        b[5] = 0x02;
+        b[6..10].copy_from_slice(b"nkas");
+        b[10..42].fill(0xAB); // memo
+        b[42..74].fill(0xCD); // checksum
+        b[74..82].copy_from_slice(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        b[82..90].copy_from_slice(&[0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        b[90..98].copy_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        b[98..106].copy_from_slice(&[0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0x00, 0x00]);
+        b[106..114].copy_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        b[114..118].copy_from_slice(&[0x00, 0x00, 0x02, 0x00]);
+        // reserved2 at 118..120 left zero
+        b
+    }
+
+    #[test]
+    fn test_from_bytes_little_endian_layout() {
+        let header = HeaderV5c::from_bytes(&le_header_bytes()).unwrap();
+        assert_eq!(header.magic, MAGIC);
+        assert_eq!(header.version, VERSION);
+        assert_eq!(header.format_type, FORMAT_TYPE);
+        assert_eq!(header.nkas, NKAS);
+        assert_eq!(header.memo, [0xAB; 32]);
+        assert_eq!(header.checksum, [0xCD; 32]);
+        assert_eq!(header.xor_gates, 0x0102_0304_0506_0708);
+        assert_eq!(header.and_gates, 0x10);
+        assert_eq!(header.primary_inputs, 0x100);
+        assert_eq!(header.scratch_space, 0x1234_5678);
+        assert_eq!(header.num_outputs, 2);
+        assert_eq!(header.block_size, 128 * 1024);
+        assert_eq!(header.reserved2, [0; 2]);
+    }
+
+    #[test]
+    fn test_to_bytes_little_endian_layout() {
+        let bytes = le_header_bytes();
+        let header = HeaderV5c::from_bytes(&bytes).unwrap();
+        assert_eq!(header.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_block_size_defaults_and_validation() {
+        let mut header = HeaderV5c::new();
+        assert_eq!(header.block_layout(), BlockLayout::DEFAULT);
+
+        header.block_size = 512 * 1024;
+        assert!(header.validate().is_ok());
+        assert_eq!(header.block_layout().block_size(), 512 * 1024);
+
+        for bad in [1000, 1024, 8 * 1024 * 1024] {
+            header.block_size = bad;
+            let err = HeaderV5c::from_bytes(&header.to_bytes()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_num_outputs_bounded_by_file_size() {
+        // More outputs than gates and inputs is fine
+        let mut header = HeaderV5c::new();
+        header.primary_inputs = 2;
+        header.num_outputs = 5;
+        assert!(HeaderV5c::from_bytes(&header.to_bytes()).is_ok());
+
+        // But not so many that the outputs section overflows the file size
+        for num_outputs in [(1 << 62) + 1, u64::MAX] {
+            header.num_outputs = num_outputs;
+            let err = HeaderV5c::from_bytes(&header.to_bytes()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with("Too many outputs"));
+        }
+
+        header.num_outputs = 0;
+        header.xor_gates = u64::MAX;
+        header.and_gates = 1;
+        let err = HeaderV5c::from_bytes(&header.to_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "Total gate count would overflow");
+    }
+}
@@ -48,7 +48,7 @@ async fn test_round_trip_small_circuit() {
     assert_eq!(num_blocks, 1); // Only 1 block of gates
 
     // Verify gates in block (cast buffer to blocks)
-    let blocks = unsafe { &*(buffer.as_ptr() as *const [Block; 16]) };
+    let blocks = as_blocks(&buffer[..num_blocks * BLOCK_SIZE]).unwrap();
     let block = &blocks[0];
     assert_eq!(block.gates[0].in1, 2);
     assert_eq!(block.gates[0].in2, 3);
@@ -117,7 +117,7 @@ async fn test_round_trip_multiple_blocks() {
     assert_eq!(num_blocks, 3);
 
     // Verify first block has correct gates
-    let blocks = unsafe { &*(buffer.as_ptr() as *const [Block; 16]) };
+    let blocks = as_blocks(&buffer[..num_blocks * BLOCK_SIZE]).unwrap();
     let first_block = &blocks[0];
     assert_eq!(first_block.gates[0].in1, 100);
     assert_eq!(first_block.gates[0].out, 102);
@@ -129,6 +129,47 @@ async fn test_round_trip_multiple_blocks() {
     std::fs::remove_file(path).unwrap();
 }
 
+#[monoio::test]
+async fn test_reader_dropped_mid_stream_leaves_heap_intact() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("early_drop.v5c");
+
+    // More blocks than the reader buffers, so the IO thread is still reading
+    // when each reader is dropped
+    let total_gates = GATES_PER_BLOCK * 3 * BLOCKS_PER_DISK_BUFFER;
+    let mut writer = WriterV5c::new(&path, 2, 1, [0u8; 32]).await.unwrap();
+    for i in 0..total_gates {
+        let gate = GateV5c::new(2, 3, 4 + (i % 1000) as u32);
+        writer.write_gate(gate, GateType::XOR).await.unwrap();
+    }
+    writer.finalize(1004, vec![4]).await.unwrap();
+
+    let mut buffer = vec![0u8; BLOCK_SIZE * BLOCKS_PER_DISK_BUFFER];
+    for round in 0..40 {
+        let mut reader = ReaderV5c::open(&path).unwrap();
+        if round % 2 == 1 {
+            assert!(reader.read_blocks(&mut buffer).await.unwrap() > 0);
+        }
+        drop(reader);
+        let fresh = vec![round as u8; DISK_BUFFER_SIZE];
+        assert!(fresh.iter().all(|&b| b == round as u8));
+    }
+
+    let mut reader = ReaderV5c::open(&path).unwrap();
+    let mut blocks = 0;
+    loop {
+        let n = reader.read_blocks(&mut buffer).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        for block in as_blocks(&buffer[..n * BLOCK_SIZE]).unwrap() {
+            assert_eq!((block.gates[0].in1, block.gates[0].in2), (2, 3));
+        }
+        blocks += n;
+    }
+    assert_eq!(blocks, 3 * BLOCKS_PER_DISK_BUFFER);
+}
+
 #[monoio::test]
 async fn test_arc_sharing_pattern() {
     let path = "/tmp/test_v5c_arc_sharing.ckt";
@@ -154,7 +195,7 @@ async fn test_arc_sharing_pattern() {
     let shared = Arc::new(buffer);
 
     // Simulate 4 workers processing their assigned blocks
-    let blocks = unsafe { &*(shared.as_ptr() as *const [Block; 16]) };
+    let blocks = as_blocks(&shared[..num_blocks * BLOCK_SIZE]).unwrap();
     let mut total_processed = 0;
     for _block in blocks.iter().take(num_blocks) {
         // Each worker would process gates from this block
@@ -232,6 +273,101 @@ async fn test_checksum_verification() {
     std::fs::remove_file(path).unwrap();
 }
 
+#[monoio::test]
+async fn test_one_pass_counts_match_streaming_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("counts.v5c");
+
+    // Irregular type pattern over a full block and a partial one whose
+    // last type byte is only partly used
+    let total_gates = GATES_PER_BLOCK + 13;
+    let mut writer = WriterV5c::new(&path, 2, 1, [0u8; 32]).await.unwrap();
+    for i in 0..total_gates {
+        let gate_type = if i % 7 < 3 {
+            GateType::AND
+        } else {
+            GateType::XOR
+        };
+        writer
+            .write_gate(GateV5c::new(2, 3, 4 + (i % 8) as u32), gate_type)
+            .await
+            .unwrap();
+    }
+    writer.finalize(12, vec![4]).await.unwrap();
+
+    let (valid, counts) = crate::v5::c::reader::verify_v5c_checksum_and_count(&path)
+        .await
+        .unwrap();
+    assert!(valid);
+
+    let mut reader = ReaderV5c::open(&path).unwrap();
+    let header = *reader.header();
+    let (mut seen, mut and) = (0u64, 0u64);
+    while let Some(chunk) = reader.next_blocks_chunk().await.unwrap() {
+        for block in chunk.blocks_iter() {
+            let n = get_block_num_gates(
+                header.total_gates(),
+                (seen / GATES_PER_BLOCK as u64) as usize,
+            );
+            and += block
+                .iter_gates(n)
+                .filter(|g| g.gate_type == GateType::AND)
+                .count() as u64;
+            seen += n as u64;
+        }
+    }
+    assert_eq!(counts, crate::v5::GateCounts::new(seen - and, and));
+    assert_eq!(counts, header.gate_counts());
+}
+
+#[monoio::test]
+async fn test_repair_stale_checksum() {
+    let path = "/tmp/test_v5c_repair.ckt";
+
+    let mut writer = WriterV5c::new(path, 5, 2, [0u8; 32]).await.unwrap();
+    for i in 0..100 {
+        writer
+            .write_gate(GateV5c::new(5, 6, 100 + i), GateType::AND)
+            .await
+            .unwrap();
+    }
+    writer.finalize(1000, vec![100, 199]).await.unwrap();
+    assert!(
+        !crate::v5::c::reader::repair_v5c_checksum(path)
+            .await
+            .unwrap()
+    );
+
+    // Corrupt only the stored checksum
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes[42..74].fill(0xEE);
+    std::fs::write(path, &bytes).unwrap();
+    let valid = crate::v5::c::reader::verify_v5c_checksum(path)
+        .await
+        .unwrap();
+    assert!(!valid);
+
+    assert!(
+        crate::v5::c::reader::repair_v5c_checksum(path)
+            .await
+            .unwrap()
+    );
+    let valid = crate::v5::c::reader::verify_v5c_checksum(path)
+        .await
+        .unwrap();
+    assert!(valid);
+
+    // A truncated file is not repaired
+    let bytes = std::fs::read(path).unwrap();
+    std::fs::write(path, &bytes[..bytes.len() - 1]).unwrap();
+    let err = crate::v5::c::reader::repair_v5c_checksum(path)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    std::fs::remove_file(path).unwrap();
+}
+
 #[monoio::test]
 async fn test_partial_last_block() {
     let path = "/tmp/test_v5c_partial_last.ckt";
@@ -258,7 +394,7 @@ async fn test_partial_last_block() {
     assert_eq!(num_blocks, 2); // 2 blocks
 
     // Verify first block has full GATES_PER_BLOCK gates
-    let blocks = unsafe { &*(buffer.as_ptr() as *const [Block; 16]) };
+    let blocks = as_blocks(&buffer[..num_blocks * BLOCK_SIZE]).unwrap();
     let _block1 = &blocks[0];
     let gates_in_block1 = get_block_num_gates(header.total_gates(), 0);
     assert_eq!(gates_in_block1, GATES_PER_BLOCK);
@@ -271,6 +407,111 @@ async fn test_partial_last_block() {
     std::fs::remove_file(path).unwrap();
 }
 
+#[monoio::test]
+async fn test_checksum_covers_exactly_the_bytes_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("on_disk.v5c");
+
+    // Partial last block, with stale bytes in the writer's buffer from the
+    // full block before it, so its tail only hashes right if it's zeroed
+    let mut writer = WriterV5c::new(&path, 2, 3, [7u8; 32]).await.unwrap();
+    for i in 0..GATES_PER_BLOCK + 5 {
+        writer
+            .write_gate(GateV5c::new(2, 3, 4 + i as u32), GateType::AND)
+            .await
+            .unwrap();
+    }
+    let stats = writer.finalize(100_000, vec![4, 5, 6]).await.unwrap();
+
+    // Rehash the file in spec order, skipping only the checksum field
+    let bytes = std::fs::read(&path).unwrap();
+    let header = HeaderV5c::from_bytes(&bytes[..HEADER_SIZE]).unwrap();
+    let blocks_start = ALIGNMENT + padded_size(3 * 4);
+    assert_eq!(bytes.len(), blocks_start + 2 * BLOCK_SIZE);
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&bytes[blocks_start..]);
+    hasher.update(&bytes[ALIGNMENT..blocks_start]);
+    hasher.update(&bytes[..42]);
+    hasher.update(&bytes[74..ALIGNMENT]);
+    assert_eq!(*hasher.finalize().as_bytes(), header.checksum);
+    assert_eq!(header.checksum, stats.checksum);
+
+    let last_block = &bytes[blocks_start + BLOCK_SIZE..];
+    assert!(
+        last_block[5 * GATE_SIZE..TYPES_OFFSET]
+            .iter()
+            .all(|&b| b == 0)
+    );
+    assert!(
+        crate::v5::c::reader::verify_v5c_checksum(&path)
+            .await
+            .unwrap()
+    );
+}
+
+#[monoio::test]
+async fn test_truncated_file_rejected_at_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("truncated.v5c");
+
+    let mut writer = WriterV5c::new(&path, 10, 1, [0u8; 32]).await.unwrap();
+    for i in 0..GATES_PER_BLOCK + 100 {
+        writer
+            .write_gate(GateV5c::new(10, 11, 100 + i as u32), GateType::XOR)
+            .await
+            .unwrap();
+    }
+    writer.finalize(100000, vec![100]).await.unwrap();
+    let header = *ReaderV5c::open(&path).unwrap().header();
+    let expected_size = header.expected_file_size().unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), expected_size);
+
+    // Cut the file off halfway through the second block
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.truncate(bytes.len() - BLOCK_SIZE / 2);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let err = ReaderV5c::open(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "file is {} bytes but header describes {} bytes (2 blocks, 1 outputs)",
+            bytes.len(),
+            expected_size
+        )
+    );
+}
+
+#[monoio::test]
+async fn test_overflowing_output_count_rejected_at_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("outputs.v5c");
+
+    let mut writer = WriterV5c::new(&path, 2, 1, [0u8; 32]).await.unwrap();
+    writer
+        .write_gate(GateV5c::new(2, 3, 4), GateType::AND)
+        .await
+        .unwrap();
+    writer.finalize(5, vec![4]).await.unwrap();
+
+    // Claim enough outputs that the outputs section overflows a u64
+    let mut bytes = std::fs::read(&path).unwrap();
+    let mut header = HeaderV5c::from_bytes(&bytes[..HEADER_SIZE]).unwrap();
+    header.num_outputs = (1 << 62) + 1;
+    bytes[..HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+
+    let err = ReaderV5c::open(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = ReaderV5c::open_mmap(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = crate::v5::c::reader::verify_v5c_checksum(&path)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
 #[monoio::test]
 async fn test_empty_circuit_rejected() {
     let path = "/tmp/test_v5c_empty.ckt";
@@ -288,6 +529,34 @@ async fn test_empty_circuit_rejected() {
     std::fs::remove_file(path).unwrap();
 }
 
+#[monoio::test]
+async fn test_passthrough_circuit_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("passthrough.v5c");
+
+    // No gates, every output is a primary input or a constant
+    let writer = WriterV5c::new(&path, 3, 4, [0u8; 32]).await.unwrap();
+    let stats = writer.finalize(5, vec![4, 2, 3, 1]).await.unwrap();
+    assert_eq!(stats.total_gates, 0);
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.len(), 2 * ALIGNMENT);
+    assert!(
+        crate::v5::c::reader::verify_v5c_checksum(&path)
+            .await
+            .unwrap()
+    );
+
+    let mut reader = ReaderV5c::open(&path).unwrap();
+    assert_eq!(reader.header().total_blocks(), 0);
+    assert_eq!(reader.outputs(), &[4, 2, 3, 1]);
+    assert!(reader.next_blocks_chunk().await.unwrap().is_none());
+
+    let mut mmap = ReaderV5c::open_mmap(&path).unwrap();
+    assert!(mmap.blocks().is_empty());
+    assert!(mmap.next_block().is_none());
+}
+
 #[monoio::test]
 async fn test_writer_validates_addresses() {
     let path = "/tmp/test_v5c_validate_addr.ckt";
@@ -306,3 +575,284 @@ async fn test_writer_validates_addresses() {
 
     std::fs::remove_file(path).unwrap();
 }
+
+#[monoio::test]
+async fn test_for_each_block_par_sums_gates() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let path = "/tmp/test_v5c_for_each_block_par.ckt";
+
+    // More than one 16-block chunk, ending in a partial block
+    let total_gates = GATES_PER_BLOCK * 17 + 100;
+    let mut writer = WriterV5c::new(path, 2, 1, [0u8; 32]).await.unwrap();
+    for i in 0..total_gates {
+        let gate_type = if i % 2 == 0 {
+            GateType::XOR
+        } else {
+            GateType::AND
+        };
+        writer
+            .write_gate(GateV5c::new(0, 1, i as u32), gate_type)
+            .await
+            .unwrap();
+    }
+    writer
+        .finalize(total_gates as u64, vec![total_gates as u32 - 1])
+        .await
+        .unwrap();
+
+    let mut reader = ReaderV5c::open(path).unwrap();
+    let header = *reader.header();
+    let total = AtomicU64::new(0);
+    let visited = AtomicU64::new(0);
+
+    reader
+        .for_each_block_par(4, |block, idx| {
+            // Each block's first gate output encodes its position
+            assert_eq!(block.gates[0].out as usize, idx * GATES_PER_BLOCK);
+            let n = get_block_num_gates(header.total_gates(), idx);
+            total.fetch_add(n as u64, Ordering::Relaxed);
+            visited.fetch_add(1, Ordering::Relaxed);
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(total.load(Ordering::Relaxed), header.total_gates());
+    assert_eq!(visited.load(Ordering::Relaxed), header.total_blocks());
+
+    std::fs::remove_file(path).ok();
+}
+
+#[monoio::test]
+async fn test_for_each_block_par_balances_slow_blocks() {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("skewed.v5c");
+
+    // One full chunk of mostly AND gates
+    let total_gates = GATES_PER_BLOCK * 16;
+    let mut writer = WriterV5c::new(&path, 2, 1, [0u8; 32]).await.unwrap();
+    for i in 0..total_gates {
+        let gate_type = if i % 8 == 0 {
+            GateType::XOR
+        } else {
+            GateType::AND
+        };
+        writer
+            .write_gate(GateV5c::new(0, 1, i as u32), gate_type)
+            .await
+            .unwrap();
+    }
+    writer
+        .finalize(total_gates as u64, vec![total_gates as u32 - 1])
+        .await
+        .unwrap();
+
+    // Block 0 is far slower than the others, so whichever worker takes it
+    // should be left with nothing else once it's done
+    let mut reader = ReaderV5c::open(&path).unwrap();
+    let taken_by = Mutex::new(vec![None; 16]);
+    reader
+        .for_each_block_par(2, |block, idx| {
+            assert_eq!(block.gates[0].out as usize, idx * GATES_PER_BLOCK);
+            let ands = block
+                .iter_gates(GATES_PER_BLOCK)
+                .filter(|g| g.gate_type == GateType::AND)
+                .count();
+            let start = idx * GATES_PER_BLOCK;
+            let expected = (start..start + GATES_PER_BLOCK)
+                .filter(|i| i % 8 != 0)
+                .count();
+            assert_eq!(ands, expected);
+            if idx == 0 {
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            taken_by.lock().unwrap()[idx] = Some(std::thread::current().id());
+        })
+        .await
+        .unwrap();
+
+    let taken_by = taken_by.into_inner().unwrap();
+    assert!(taken_by.iter().all(Option::is_some));
+    let slow_worker = taken_by[0];
+    assert_eq!(taken_by.iter().filter(|&&t| t == slow_worker).count(), 1);
+}
+
+#[monoio::test]
+async fn test_gate_counts_match_across_formats() {
+    use crate::v5::GateCounts;
+    use crate::v5::a::reader::CircuitReaderV5a;
+    use crate::v5::a::writer::CircuitWriterV5a;
+    use crate::v5::a::{CREDITS_OUTPUT, GateV5a};
+
+    let path_a = "/tmp/test_gate_counts_v5a.ckt";
+    let path_c = "/tmp/test_gate_counts_v5c.ckt";
+
+    // Same gate types in both formats, spanning more than one v5c block
+    let num_gates = GATES_PER_BLOCK + 10;
+    let gate_type = |i: usize| {
+        if i.is_multiple_of(3) {
+            GateType::AND
+        } else {
+            GateType::XOR
+        }
+    };
+
+    let mut writer_a = CircuitWriterV5a::new(path_a, 2, vec![], [0u8; 32])
+        .await
+        .unwrap();
+    let mut writer_c = WriterV5c::new(path_c, 2, 0, [0u8; 32]).await.unwrap();
+    for i in 0..num_gates {
+        writer_a
+            .write_gate(GateV5a {
+                in1: 2,
+                in2: 3,
+                out: 4 + i as u64,
+                credits: CREDITS_OUTPUT,
+                gate_type: gate_type(i),
+            })
+            .await
+            .unwrap();
+        writer_c
+            .write_gate(GateV5c::new(2, 3, 4 + i as u32), gate_type(i))
+            .await
+            .unwrap();
+    }
+    writer_a.finalize().await.unwrap();
+    writer_c
+        .finalize(num_gates as u64 + 4, vec![])
+        .await
+        .unwrap();
+
+    let reader_a = CircuitReaderV5a::open(path_a).unwrap();
+    let reader_c = ReaderV5c::open(path_c).unwrap();
+
+    let and = num_gates.div_ceil(3) as u64;
+    let expected = GateCounts {
+        xor: num_gates as u64 - and,
+        and,
+        total: num_gates as u64,
+    };
+    assert_eq!(reader_a.gate_counts(), expected);
+    assert_eq!(reader_c.gate_counts(), expected);
+
+    std::fs::remove_file(path_a).unwrap();
+    std::fs::remove_file(path_c).unwrap();
+}
+
+#[monoio::test]
+async fn test_mmap_blocks_match_buffered_reader() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mmap.v5c");
+
+    let num_gates = GATES_PER_BLOCK * 2 + 100;
+    let mut writer = WriterV5c::new(&path, 10, 2, [0u8; 32]).await.unwrap();
+    for i in 0..num_gates as u32 {
+        let gate_type = if i % 3 == 0 {
+            GateType::AND
+        } else {
+            GateType::XOR
+        };
+        writer
+            .write_gate(
+                GateV5c::new(2 + i % 10, 3 + i % 7, 100 + i % 5000),
+                gate_type,
+            )
+            .await
+            .unwrap();
+    }
+    writer.finalize(10000, vec![100, 101]).await.unwrap();
+
+    let mut mapped = ReaderV5c::open_mmap(&path).unwrap();
+    let mut buffered = ReaderV5c::open(&path).unwrap();
+    assert_eq!(mapped.header().to_bytes(), buffered.header().to_bytes());
+    assert_eq!(mapped.outputs(), buffered.outputs());
+    assert_eq!(mapped.blocks().len(), 3);
+
+    let mut seen = 0;
+    while let Some(chunk) = buffered.next_blocks_chunk().await.unwrap() {
+        for block in chunk.blocks_iter() {
+            let (mapped_block, n) = mapped.next_block().unwrap();
+            assert_eq!(n, get_block_num_gates(num_gates as u64, seen));
+            let gates = |b: BlockRef<'_>| b.iter_gates(n).collect::<Vec<_>>();
+            assert_eq!(gates(mapped_block), gates(block), "block {}", seen);
+            seen += 1;
+        }
+    }
+    assert_eq!(seen, 3);
+    assert!(mapped.next_block().is_none());
+}
+
+#[monoio::test]
+async fn test_round_trip_at_other_block_sizes() {
+    use crate::v5::c::reader::verify_v5c_checksum_and_count;
+
+    let dir = tempfile::tempdir().unwrap();
+    let num_gates = 60_000u32;
+    let gate = |i: u32| GateV5c::new(2 + i % 10, 3 + i % 7, 100 + i % 5000);
+    let gate_type = |i: u32| {
+        if i.is_multiple_of(3) {
+            GateType::AND
+        } else {
+            GateType::XOR
+        }
+    };
+
+    // Smaller and larger than the default, the larger one spanning a
+    // whole disk buffer so neither reader can assume 16 blocks per buffer
+    for block_size in [64 * 1024, 4 * 1024 * 1024] {
+        let layout = BlockLayout::new(block_size).unwrap();
+        let path = dir.path().join(format!("{}.v5c", block_size));
+        let mut writer = WriterV5c::with_block_size(&path, 10, 1, [0u8; 32], block_size)
+            .await
+            .unwrap();
+        for i in 0..num_gates {
+            writer.write_gate(gate(i), gate_type(i)).await.unwrap();
+        }
+        writer.finalize(10_000, vec![100]).await.unwrap();
+
+        let expected_blocks = (num_gates as u64).div_ceil(layout.gates_per_block() as u64);
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            2 * ALIGNMENT as u64 + expected_blocks * block_size as u64
+        );
+        let (valid, counts) = verify_v5c_checksum_and_count(&path).await.unwrap();
+        assert!(valid);
+        assert_eq!(counts.and, (num_gates as u64).div_ceil(3));
+
+        let mut reader = ReaderV5c::open(&path).unwrap();
+        let header = *reader.header();
+        assert_eq!(header.block_size as usize, block_size);
+        assert_eq!(header.block_layout(), layout);
+        assert_eq!(header.total_blocks(), expected_blocks);
+
+        let mut read = Vec::new();
+        let mut block_index = 0;
+        while let Some(chunk) = reader.next_blocks_chunk().await.unwrap() {
+            for block in chunk.blocks_iter() {
+                assert_eq!(block.gates.len(), layout.gates_per_block());
+                read.extend(block.iter_gates(header.block_num_gates(block_index)));
+                block_index += 1;
+            }
+        }
+        assert_eq!(block_index as u64, expected_blocks);
+        assert_eq!(read.len(), num_gates as usize);
+        for (i, g) in read.iter().enumerate() {
+            assert_eq!((g.gate, g.gate_type), (gate(i as u32), gate_type(i as u32)));
+        }
+
+        let mut mapped = ReaderV5c::open_mmap(&path).unwrap();
+        assert_eq!(mapped.blocks().len() as u64, expected_blocks);
+        let (first, n) = mapped.next_block().unwrap();
+        assert_eq!(n, layout.gates_per_block().min(num_gates as usize));
+        assert!(first.iter_gates(n).eq(read[..n].iter().copied()));
+    }
+
+    let err = WriterV5c::with_block_size(dir.path().join("bad.v5c"), 10, 1, [0u8; 32], 100_000)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,146 @@
+//! Memory-mapped v5c reader.
+//!
+//! Blocks are stored with exactly their in-memory layout, so a mapped file
+//! can be viewed as a [`Chunk`] of blocks without copying. This is sound
+//! because:
+//!
+//! - the gate region starts at a multiple of [`ALIGNMENT`](super::ALIGNMENT) from the start of
+//!   the file, and the mapping itself is page aligned, so every block is at
+//!   least as aligned as [`Block`](super::Block) requires;
+//! - the file size must match the header exactly, so the gate region is a
+//!   whole number of blocks ending at the end of the file.
+//!
+//! Both are checked on open, when the chunk is first built. The
+//! mapping is private and read-only, but like any mmap it reflects the file
+//! as it is on disk, so the file must not be truncated or modified while the
+//! reader is alive.
+
+use std::io::{Error, Result};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use super::reader::{expected_file_size, read_header_and_outputs};
+use crate::v5::GateCounts;
+use crate::v5::c::{BlockRef, Chunk, HeaderV5c, ReaderV5c};
+
+/// v5c reader over a read-only memory mapping of the whole file.
+///
+/// Created by [`ReaderV5c::open_mmap`]. Blocks are borrowed straight from the
+/// mapping, so reading is a pointer bump rather than a copy.
+#[derive(Debug)]
+pub struct MmapReaderV5c {
+    header: HeaderV5c,
+    outputs: Vec<u32>,
+
+    // The mapping, unmapped on drop
+    ptr: NonNull<u8>,
+    len: usize,
+    gate_region_start: usize,
+
+    next_block: usize,
+}
+
+// SAFETY: the mapping is read-only and owned by the reader, so sharing or
+// moving it across threads is no different from doing so with a `&[u8]`
+unsafe impl Send for MmapReaderV5c {}
+unsafe impl Sync for MmapReaderV5c {}
+
+impl ReaderV5c {
+    /// Open a v5c file by memory-mapping it.
+    ///
+    /// Validates the header, outputs and file size like [`ReaderV5c::open`],
+    /// then checks that the gate region can be viewed as blocks.
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<MmapReaderV5c> {
+        let mut f = std::fs::OpenOptions::new().read(true).open(path.as_ref())?;
+        let (header, outputs, gate_region_start) = read_header_and_outputs(&mut f)?;
+        let len = expected_file_size(&header)? as usize;
+
+        // SAFETY: a fresh private read-only mapping of a file we hold open
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                f.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::last_os_error());
+        }
+
+        let reader = MmapReaderV5c {
+            header,
+            outputs,
+            ptr: NonNull::new(ptr.cast()).expect("mmap returned null"),
+            len,
+            gate_region_start: gate_region_start as usize,
+            next_block: 0,
+        };
+        reader.try_blocks()?;
+        Ok(reader)
+    }
+}
+
+impl MmapReaderV5c {
+    /// Get the header
+    pub fn header(&self) -> &HeaderV5c {
+        &self.header
+    }
+
+    /// Get XOR, AND and total gate counts
+    pub fn gate_counts(&self) -> GateCounts {
+        self.header.gate_counts()
+    }
+
+    /// Get outputs as a slice
+    pub fn outputs(&self) -> &[u32] {
+        &self.outputs
+    }
+
+    /// All blocks of the circuit, borrowed from the mapping.
+    ///
+    /// The last block may be partial; [`HeaderV5c::block_num_gates`] gives
+    /// the number of valid gates in each.
+    pub fn blocks(&self) -> Chunk<'_> {
+        self.try_blocks().expect("gate region checked on open")
+    }
+
+    /// The next block and its number of valid gates, or `None` at the end.
+    pub fn next_block(&mut self) -> Option<(BlockRef<'_>, usize)> {
+        let index = self.next_block;
+        if index as u64 >= self.header.total_blocks() {
+            return None;
+        }
+        self.next_block += 1;
+        let num_gates = self.header.block_num_gates(index);
+        Some((self.blocks().get(index)?, num_gates))
+    }
+
+    fn try_blocks(&self) -> Result<Chunk<'_>> {
+        let num_blocks = self.header.total_blocks() as usize;
+        let layout = self.header.block_layout();
+        Ok(Chunk::from_blocks_buf(
+            self.gate_region(),
+            num_blocks,
+            layout,
+        )?)
+    }
+
+    fn gate_region(&self) -> &[u8] {
+        // SAFETY: the mapping is `len` bytes long and lives as long as `self`
+        let file = unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) };
+        &file[self.gate_region_start..]
+    }
+}
+
+impl Drop for MmapReaderV5c {
+    fn drop(&mut self) {
+        // SAFETY: unmapping exactly the region mapped in `open_mmap`
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
@@ -3,7 +3,7 @@
 //! This module implements the v5c format specification with:
 //! - 32-bit memory addresses (not wire IDs)
 //! - Flat array organization (no level structure)
-//! - 256 KiB blocks optimized for 1 MB L2 cache
+//! - 256 KiB blocks optimized for 1 MB L2 cache, or a size set in the header
 //! - Bit-compacted gate types (1 bit per gate)
 //! - Gates stored in execution order
 //!
@@ -12,23 +12,35 @@
 //! for distributing blocks to multiple worker threads.
 
 mod block;
+mod cast;
+#[cfg(feature = "std")]
 mod chunk;
 mod constants;
 mod gate;
 mod header;
+#[cfg(feature = "std")]
+mod mmap;
 mod stats;
 
+#[cfg(feature = "std")]
 mod reader;
+#[cfg(feature = "std")]
 mod writer;
 
 pub use block::*;
+pub use cast::*;
+#[cfg(feature = "std")]
 pub use chunk::*;
 pub use constants::*;
 pub use gate::*;
 pub use header::*;
+#[cfg(feature = "std")]
+pub use mmap::*;
+#[cfg(feature = "std")]
 pub use reader::*;
 pub use stats::*;
+#[cfg(feature = "std")]
 pub use writer::*;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod integration;
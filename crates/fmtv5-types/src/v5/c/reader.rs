@@ -3,9 +3,10 @@
 //! Provides zero-copy block distribution for parallel garbling workloads.
 //! Uses proven v5b triple-buffer architecture for 6.5+ GB/s throughput.
 
-use std::io::{Error, ErrorKind, Read, Result};
+use std::io::{Error, ErrorKind, Read, Result, Seek};
 use std::path::{Path, PathBuf};
 use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
 
 use blake3::Hasher;
@@ -16,7 +17,8 @@ use kanal::{AsyncReceiver, AsyncSender, bounded_async};
 use monoio::{FusionDriver, select};
 
 use super::chunk::Chunk;
-use crate::v5::c::{ALIGNMENT, BLOCK_SIZE, GATES_PER_BLOCK, HEADER_SIZE, HeaderV5c, padded_size};
+use crate::v5::GateCounts;
+use crate::v5::c::{ALIGNMENT, BlockLayout, BlockRef, HEADER_SIZE, HeaderV5c, padded_size};
 
 /// Reader for v5c format files with triple-buffered io_uring
 pub struct ReaderV5c {
@@ -29,8 +31,9 @@ pub struct ReaderV5c {
     io_jh: Option<thread::JoinHandle<()>>,
 
     // Current state
+    layout: BlockLayout,
     cur_buf: Option<AlignedBuffer>,
-    bytes_remaining: u64, // Always multiple of BLOCK_SIZE
+    bytes_remaining: u64, // Always multiple of the block size
 }
 
 impl ReaderV5c {
@@ -38,67 +41,11 @@ impl ReaderV5c {
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
         let mut f = std::fs::OpenOptions::new().read(true).open(path.as_ref())?;
 
-        // Read and parse header (120 bytes)
-        let mut hdr_bytes = [0u8; HEADER_SIZE];
-        f.read_exact(&mut hdr_bytes)?;
-        let header = HeaderV5c::from_bytes(&hdr_bytes)?;
-        header
-            .validate()
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
-
-        // Calculate outputs section offset (header padded to 256 KiB)
-        let outputs_offset = ALIGNMENT;
-        let outputs_bytes_len = (header.num_outputs as usize)
-            .checked_mul(4)
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "outputs length overflow"))?;
-
-        // Read outputs
-        use std::io::Seek;
-        f.seek(std::io::SeekFrom::Start(outputs_offset as u64))?;
-        let mut outputs_raw = vec![0u8; outputs_bytes_len];
-        if outputs_bytes_len > 0 {
-            f.read_exact(&mut outputs_raw)?;
-        }
-        let outputs = decode_outputs_le32(&outputs_raw)?;
-
-        // Validate outputs against scratch space
-        let scratch_space = header.scratch_space;
-        for (i, &addr) in outputs.iter().enumerate() {
-            if addr as u64 >= scratch_space {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    format!(
-                        "Output {} address {} exceeds scratch_space {}",
-                        i, addr, scratch_space
-                    ),
-                ));
-            }
-        }
-
-        // Calculate gate region
-        let file_metadata = f.metadata()?;
-        let file_size = file_metadata.len();
-
-        let outputs_padded_size = padded_size(outputs_bytes_len);
-        let gate_region_start = ALIGNMENT as u64 + outputs_padded_size as u64;
-        let gate_region_end = file_size;
-        let gate_region_bytes =
-            gate_region_end
-                .checked_sub(gate_region_start)
-                .ok_or_else(|| {
-                    Error::new(
-                        ErrorKind::InvalidData,
-                        "file truncated before outputs boundary",
-                    )
-                })?;
-
-        // Validate that gate region is multiple of BLOCK_SIZE
-        if !gate_region_bytes.is_multiple_of(BLOCK_SIZE as u64) {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "gate region size is not a multiple of 256 KiB",
-            ));
-        }
+        let (header, outputs, gate_region_start) = read_header_and_outputs(&mut f)?;
+        let gate_region_end = expected_file_size(&header)?;
+        let gate_region_bytes = gate_region_end
+            .checked_sub(gate_region_start)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "gate region starts past the end"))?;
 
         // O_DIRECT alignment
         let aligned_mask = (BUFFER_ALIGN as u64) - 1;
@@ -131,6 +78,7 @@ impl ReaderV5c {
             reader,
             stop_tx: Some(stop_tx),
             io_jh: Some(jh),
+            layout: header.block_layout(),
             cur_buf: None,
             bytes_remaining: gate_region_bytes,
         })
@@ -138,30 +86,71 @@ impl ReaderV5c {
 
     /// Returns the next chunk of blocks that we can iterate over.
     pub async fn next_blocks_chunk(&mut self) -> Result<Option<Chunk<'_>>> {
+        let layout = self.layout;
         let Some((buf, num_blocks)) = self.next_blocks_ref().await? else {
             return Ok(None);
         };
 
-        // SAFETY: `next_blocks_ref` returns values constructed properly.
-        let chunk = unsafe { Chunk::from_blocks_buf(buf, num_blocks) };
+        let chunk = Chunk::from_blocks_buf(buf, num_blocks, layout)?;
         Ok(Some(chunk))
     }
 
+    /// Reads every remaining block and hands each one to a pool of worker threads.
+    ///
+    /// `f` is called with the block and its index from the start of the gate
+    /// region. While workers process a chunk the IO thread is already filling
+    /// the next buffer. Blocks within a chunk run concurrently and in no
+    /// particular order, but every block of a chunk completes before the next
+    /// chunk is dispatched, so side effects only need ordering by block index
+    /// if the caller relies on it. Workers take the next unclaimed block as
+    /// they finish one, so a slow block doesn't hold up the rest.
+    pub async fn for_each_block_par<F>(&mut self, workers: usize, f: F) -> Result<()>
+    where
+        F: Fn(BlockRef<'_>, usize) + Sync,
+    {
+        let workers = workers.max(1);
+        let mut next_index = 0;
+
+        while let Some(chunk) = self.next_blocks_chunk().await? {
+            let blocks: Vec<BlockRef<'_>> = chunk.blocks_iter().collect();
+            let claimed = AtomicUsize::new(0);
+            let (f, blocks, claimed) = (&f, &blocks, &claimed);
+
+            thread::scope(|s| {
+                for _ in 0..workers.min(blocks.len()) {
+                    s.spawn(move || {
+                        loop {
+                            let i = claimed.fetch_add(1, Ordering::Relaxed);
+                            let Some(&block) = blocks.get(i) else {
+                                break;
+                            };
+                            f(block, next_index + i);
+                        }
+                    });
+                }
+            });
+
+            next_index += blocks.len();
+        }
+
+        Ok(())
+    }
+
     /// Get a zero-copy reference to the next 4 MiB buffer from the triple-buffer reader
     ///
     /// Returns a reference to the internal aligned buffer along with the number of valid blocks.
     /// The buffer remains valid until the next call to `next_blocks_ref()` or `read_blocks()`.
     ///
     /// # Returns
-    /// * `Ok(Some((&[u8], usize)))` - Buffer reference and number of valid blocks (1-16)
+    /// * `Ok(Some((&[u8], usize)))` - Buffer reference and number of valid blocks
     /// * `Ok(None)` - No more data
     /// * `Err(_)` - I/O error
     ///
     /// # Example
     /// ```ignore
     /// while let Some((buffer, num_blocks)) = reader.next_blocks_ref().await? {
-    ///     let blocks = unsafe { &*(buffer.as_ptr() as *const [Block; 16]) };
-    ///     // Process blocks[0..num_blocks]
+    ///     let chunk = Chunk::from_blocks_buf(buffer, num_blocks, layout)?;
+    ///     // Process blocks
     /// }
     /// ```
     pub(crate) async fn next_blocks_ref(&mut self) -> Result<Option<(&[u8], usize)>> {
@@ -174,16 +163,14 @@ impl ReaderV5c {
         let aligned_buf = self.reader.next(prev_buf).await;
 
         // Calculate number of valid blocks based on bytes_remaining
-        let blocks_in_buffer = 16;
+        let block_size = self.layout.block_size() as u64;
         let buffer_size = aligned_buf.len() as u64;
         let valid_bytes = self.bytes_remaining.min(buffer_size);
-        let valid_blocks = (valid_bytes / BLOCK_SIZE as u64) as usize;
-
-        let num_blocks = valid_blocks.min(blocks_in_buffer);
+        let num_blocks = (valid_bytes / block_size) as usize;
 
         // Update bytes_remaining
         let bytes_consumed = (num_blocks as u64)
-            .checked_mul(BLOCK_SIZE as u64)
+            .checked_mul(block_size)
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "bytes consumed overflow"))?;
         self.bytes_remaining = self
             .bytes_remaining
@@ -205,7 +192,7 @@ impl ReaderV5c {
     /// Read up to 4 MiB of blocks into the provided buffer
     ///
     /// Returns the number of valid blocks read (0 if no more data).
-    /// Buffer must be at least 4 MiB (16 × 256 KiB blocks).
+    /// Buffer must be at least 4 MiB (one disk buffer).
     ///
     /// # Arguments
     /// * `buffer` - Mutable slice to fill with block data (must be >= 4 MiB)
@@ -220,7 +207,7 @@ impl ReaderV5c {
         }
 
         // Buffer should be at least 4 MiB for optimal performance
-        if buffer.len() < BLOCK_SIZE * 16 {
+        if buffer.len() < crate::v5::c::DISK_BUFFER_SIZE {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
                 "buffer must be at least 4 MiB (16 blocks)",
@@ -232,12 +219,10 @@ impl ReaderV5c {
         let aligned_buf = self.reader.next(prev_buf).await;
 
         // Calculate number of valid blocks based on bytes_remaining
-        let blocks_in_buffer = 16;
+        let block_size = self.layout.block_size() as u64;
         let buffer_size = aligned_buf.len() as u64;
         let valid_bytes = self.bytes_remaining.min(buffer_size);
-        let valid_blocks = (valid_bytes / BLOCK_SIZE as u64) as usize;
-
-        let num_blocks = valid_blocks.min(blocks_in_buffer);
+        let num_blocks = (valid_bytes / block_size) as usize;
 
         // Copy data into provided buffer
         let copy_len = aligned_buf.len().min(buffer.len());
@@ -248,7 +233,7 @@ impl ReaderV5c {
 
         // Update bytes_remaining
         let bytes_consumed = (num_blocks as u64)
-            .checked_mul(BLOCK_SIZE as u64)
+            .checked_mul(block_size)
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "bytes consumed overflow"))?;
         self.bytes_remaining = self
             .bytes_remaining
@@ -268,6 +253,11 @@ impl ReaderV5c {
         &self.header
     }
 
+    /// Get XOR, AND and total gate counts
+    pub fn gate_counts(&self) -> GateCounts {
+        self.header.gate_counts()
+    }
+
     /// Get outputs as a slice
     pub fn outputs(&self) -> &[u32] {
         &self.outputs
@@ -285,6 +275,84 @@ impl Drop for ReaderV5c {
     }
 }
 
+/// Read and validate the header and outputs of a v5c file, returning them
+/// with the offset of the gate region.
+///
+/// The file size must match the header exactly, so the gate region is a
+/// whole number of blocks ending at the end of the file.
+pub(super) fn read_header_and_outputs(f: &mut std::fs::File) -> Result<(HeaderV5c, Vec<u32>, u64)> {
+    // Read and parse header (120 bytes)
+    let mut hdr_bytes = [0u8; HEADER_SIZE];
+    f.read_exact(&mut hdr_bytes)?;
+    let header = HeaderV5c::from_bytes(&hdr_bytes)?;
+    header
+        .validate()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    // Catch truncation here rather than as a short read mid-circuit
+    let file_size = f.metadata()?.len();
+    let expected_size = expected_file_size(&header)?;
+    if file_size != expected_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "file is {} bytes but header describes {} bytes ({} blocks, {} outputs)",
+                file_size,
+                expected_size,
+                header.total_blocks(),
+                header.num_outputs
+            ),
+        ));
+    }
+
+    // Calculate outputs section offset (header padded to 256 KiB)
+    let outputs_offset = ALIGNMENT;
+    let outputs_bytes_len = (header.num_outputs as usize)
+        .checked_mul(4)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "outputs length overflow"))?;
+
+    // Read outputs
+    f.seek(std::io::SeekFrom::Start(outputs_offset as u64))?;
+    let mut outputs_raw = vec![0u8; outputs_bytes_len];
+    if outputs_bytes_len > 0 {
+        f.read_exact(&mut outputs_raw)?;
+    }
+    let outputs = decode_outputs_le32(&outputs_raw)?;
+
+    // Validate outputs against scratch space
+    let scratch_space = header.scratch_space;
+    for (i, &addr) in outputs.iter().enumerate() {
+        if addr as u64 >= scratch_space {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Output {} address {} exceeds scratch_space {}",
+                    i, addr, scratch_space
+                ),
+            ));
+        }
+    }
+
+    // The gate region starts after the padded outputs and, given the
+    // file size check, runs to the end of the file
+    let gate_region_start = ALIGNMENT as u64 + padded_size(outputs_bytes_len) as u64;
+    Ok((header, outputs, gate_region_start))
+}
+
+/// The file size the header describes, failing if it overflows a `u64`
+pub(super) fn expected_file_size(header: &HeaderV5c) -> Result<u64> {
+    header.expected_file_size().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "header describes more than u64::MAX bytes ({} blocks, {} outputs)",
+                header.total_blocks(),
+                header.num_outputs
+            ),
+        )
+    })
+}
+
 /// Decode outputs from 4-byte little-endian u32 entries
 fn decode_outputs_le32(bytes: &[u8]) -> Result<Vec<u32>> {
     if !bytes.len().is_multiple_of(4) {
@@ -317,6 +385,74 @@ pub async fn verify_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
     opts.read(true);
     let file = opts.open(path.as_ref()).await?;
 
+    let (stored, computed) = compute_v5c_checksum(&file, None, |_, _, _| {}).await?;
+    Ok(computed == stored)
+}
+
+/// Verify the checksum of a v5c file and recount its gate types in the
+/// same pass
+///
+/// The counts come from the type bits of every block rather than the
+/// header, so comparing them with [`HeaderV5c::gate_counts`] catches a
+/// header whose counts disagree with its gates.
+pub async fn verify_v5c_checksum_and_count(path: impl AsRef<Path>) -> Result<(bool, GateCounts)> {
+    let mut opts = monoio::fs::OpenOptions::new();
+    opts.read(true);
+    let file = opts.open(path.as_ref()).await?;
+
+    let (mut total, mut and) = (0u64, 0u64);
+    let (stored, computed) = compute_v5c_checksum(&file, None, |block, layout, num_gates| {
+        total += num_gates as u64;
+        and += count_and_gates(block, layout.types_offset(), num_gates);
+    })
+    .await?;
+    Ok((computed == stored, GateCounts::new(total - and, and)))
+}
+
+/// Number of set type bits, i.e. AND gates, among a block's first
+/// `num_gates` gates
+fn count_and_gates(block: &[u8], types_offset: usize, num_gates: usize) -> u64 {
+    let types = &block[types_offset..types_offset + num_gates.div_ceil(8)];
+    let (full, partial) = types.split_at(num_gates / 8);
+    let mut count: u64 = full.iter().map(|b| b.count_ones() as u64).sum();
+    if let Some(last) = partial.first() {
+        count += (last & ((1u8 << (num_gates % 8)) - 1)).count_ones() as u64;
+    }
+    count
+}
+
+/// Recompute the checksum of a v5c file and rewrite it in the header
+///
+/// The header must validate and the file length must match
+/// [`HeaderV5c::expected_file_size`]; only the 32 checksum bytes are written.
+/// Returns whether the stored checksum was stale.
+pub async fn repair_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
+    let mut opts = monoio::fs::OpenOptions::new();
+    opts.read(true).write(true);
+    let file = opts.open(path.as_ref()).await?;
+
+    let file_len = std::fs::metadata(path.as_ref())?.len();
+    let (stored, computed) = compute_v5c_checksum(&file, Some(file_len), |_, _, _| {}).await?;
+    if computed == stored {
+        return Ok(false);
+    }
+
+    let (res, _) = file.write_all_at(computed.to_vec(), 42).await;
+    res?;
+    file.sync_all().await?;
+    Ok(true)
+}
+
+/// Returns the (stored, computed) checksums, first checking the file is
+/// exactly `file_len` bytes if given
+///
+/// `on_block` sees each block's bytes, the file's block layout and the
+/// block's number of valid gates as it is hashed.
+async fn compute_v5c_checksum(
+    file: &monoio::fs::File,
+    file_len: Option<u64>,
+    mut on_block: impl FnMut(&[u8], BlockLayout, usize),
+) -> Result<([u8; 32], [u8; 32])> {
     // Read header
     let (res, header_vec) = file.read_exact_at(vec![0u8; HEADER_SIZE], 0).await;
     res?;
@@ -328,7 +464,21 @@ pub async fn verify_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
     hdr.validate()
         .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
-    let file_checksum = &header_arr[42..74];
+    let mut stored = [0u8; 32];
+    stored.copy_from_slice(&header_arr[42..74]);
+
+    if let Some(len) = file_len {
+        let expected_len = expected_file_size(&hdr)?;
+        if len != expected_len {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "file is {} bytes but header describes {} bytes",
+                    len, expected_len
+                ),
+            ));
+        }
+    }
 
     let outputs_len = (hdr.num_outputs as usize)
         .checked_mul(4)
@@ -336,10 +486,11 @@ pub async fn verify_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
 
     let mut hasher = Hasher::new();
 
-    // 1. Hash all gate blocks (full 256 KiB blocks including all padding)
-    let total_gates = hdr.total_gates();
-    let num_full_blocks = total_gates / GATES_PER_BLOCK as u64;
-    let partial_gates = (total_gates % GATES_PER_BLOCK as u64) as usize;
+    // 1. Hash all gate blocks (full blocks including all padding)
+    let layout = hdr.block_layout();
+    let block_size = layout.block_size();
+    let num_full_blocks = hdr.num_full_blocks();
+    let partial_gates = hdr.partial_block_gates();
 
     let outputs_padded = padded_size(outputs_len);
     let mut block_offset = (ALIGNMENT + outputs_padded) as u64;
@@ -347,25 +498,27 @@ pub async fn verify_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
     // Hash full blocks
     for _ in 0..num_full_blocks {
         let (res, block_bytes) = file
-            .read_exact_at(vec![0u8; BLOCK_SIZE], block_offset)
+            .read_exact_at(vec![0u8; block_size], block_offset)
             .await;
         res?;
 
-        // Hash full 256 KiB block (including padding)
+        // Hash full block (including padding)
         hasher.update(&block_bytes);
+        on_block(&block_bytes, layout, layout.gates_per_block());
 
-        block_offset += BLOCK_SIZE as u64;
+        block_offset += block_size as u64;
     }
 
-    // Hash partial block if present (full 256 KiB including padding)
+    // Hash partial block if present (whole block including padding)
     if partial_gates > 0 {
         let (res, block_bytes) = file
-            .read_exact_at(vec![0u8; BLOCK_SIZE], block_offset)
+            .read_exact_at(vec![0u8; block_size], block_offset)
             .await;
         res?;
 
         // Hash full block including padding
         hasher.update(&block_bytes);
+        on_block(&block_bytes, layout, partial_gates);
     }
 
     // 2. Hash outputs section (WITH padding to 256 KiB boundary)
@@ -386,8 +539,7 @@ pub async fn verify_v5c_checksum(path: impl AsRef<Path>) -> Result<bool> {
     let header_padding = vec![0u8; ALIGNMENT - HEADER_SIZE];
     hasher.update(&header_padding);
 
-    let computed = hasher.finalize();
-    Ok(computed.as_bytes() == file_checksum)
+    Ok((stored, *hasher.finalize().as_bytes()))
 }
 
 /// I/O thread for triple-buffered reading (reused from v5b pattern)
@@ -434,11 +586,9 @@ fn io_thread_run(
                 let mut off = aligned_start;
                 let mut buf = write_buf;
                 while off < aligned_end {
-                    let read_fut = file.read_at(buf, off);
-                    let (res, b) = select! {
-                        _ = stop_rx.recv() => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    // Never race a read with stop: dropping the op mid-flight frees
+                    // `buf` while the kernel may still be writing into it
+                    let (res, b) = file.read_at(buf, off).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -455,11 +605,7 @@ fn io_thread_run(
                     let mut opts = monoio::fs::OpenOptions::new();
                     opts.read(true);
                     let tail = opts.open(&path).await?;
-                    let read_fut = tail.read_at(buf, aligned_end);
-                    let (res, b) = select! {
-                        _ = stop_rx.recv() => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    let (res, b) = tail.read_at(buf, aligned_end).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -477,11 +623,7 @@ fn io_thread_run(
                 let end = aligned_end + tail_len as u64;
                 let mut buf = write_buf;
                 while off < end {
-                    let read_fut = file.read_at(buf, off);
-                    let (res, b) = select! {
-                        _ = stop_rx.recv() => return Ok(()),
-                        out = read_fut => out,
-                    };
+                    let (res, b) = file.read_at(buf, off).await;
                     buf = b;
                     let n = res?;
                     if n == 0 {
@@ -1,3 +1,6 @@
+use super::constants::{CIPHERTEXT_SIZE, CIPHERTEXTS_PER_AND_GATE};
+use super::header::HeaderV5c;
+
 /// Statistics about a written circuit
 #[derive(Debug, Clone)]
 pub struct CircuitStats {
@@ -9,3 +12,23 @@ pub struct CircuitStats {
     pub num_outputs: u64,
     pub checksum: [u8; 32],
 }
+
+/// Estimated size of a circuit's garbled output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GarbleEstimate {
+    pub ciphertext_bytes: u64,
+    pub and_gates: u64,
+    pub blocks: u64,
+}
+
+/// Estimate garbled output size from a header alone
+///
+/// Only AND gates emit ciphertexts, so XOR gates don't count towards the size.
+pub fn estimate_garbling(header: &HeaderV5c) -> GarbleEstimate {
+    let and_gates = header.and_gates;
+    GarbleEstimate {
+        ciphertext_bytes: and_gates * (CIPHERTEXTS_PER_AND_GATE * CIPHERTEXT_SIZE) as u64,
+        and_gates,
+        blocks: header.total_blocks(),
+    }
+}
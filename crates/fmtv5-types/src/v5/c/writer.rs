@@ -4,12 +4,16 @@
 //! - new() → write_gate()* → finalize()
 //!
 //! Behavior:
-//! - Buffers gates in 256 KiB blocks (21,620 gates per block)
+//! - Buffers gates in 256 KiB blocks (21,620 gates per block) by default, or
+//!   blocks of the size given to with_block_size()
 //! - Auto-flushes when block is full
 //! - Gates stored in execution order with bit-packed types
 //! - Writes header placeholder + zeroed output placeholders, streams blocks,
 //!   later overwrites outputs and backpatches the header with checksum
-//! - Computes checksum in order: blocks || outputs || header_tail
+//! - Computes checksum in order: blocks || outputs || header_tail, hashing
+//!   each block as it's flushed, so nothing is read back or kept around
+//! - A partial last block is zero-filled and written whole, so the hashed
+//!   bytes are exactly the bytes on disk
 //! - Uses monoio for async I/O
 
 use blake3::Hasher;
@@ -19,8 +23,8 @@ use std::path::Path;
 
 use crate::GateType;
 use crate::v5::c::{
-    ALIGNMENT, BLOCK_SIZE, CircuitStats, GATE_SIZE, GATES_PER_BLOCK, GateV5c, HEADER_SIZE,
-    HeaderV5c, MAX_MEMORY_ADDRESS, TYPES_OFFSET, padded_size, set_gate_type,
+    ALIGNMENT, BLOCK_SIZE, BlockLayout, CircuitStats, GATE_SIZE, GateV5c, HEADER_SIZE, HeaderV5c,
+    MAX_BLOCK_SIZE, MAX_MEMORY_ADDRESS, MIN_BLOCK_SIZE, padded_size, set_gate_type,
 };
 
 /// Default I/O aggregation buffer capacity (tunable)
@@ -39,8 +43,9 @@ pub struct WriterV5c {
     outputs_offset: u64, // Start of outputs section
     next_offset: u64,    // Current write position
 
-    // Current block buffer (exactly 256 KiB)
-    block_buffer: Box<[u8; BLOCK_SIZE]>,
+    // Current block buffer (exactly one block)
+    layout: BlockLayout,
+    block_buffer: Box<[u8]>,
     gates_in_block: usize,
 
     // I/O aggregation buffer
@@ -64,6 +69,31 @@ impl WriterV5c {
         num_outputs: u64,
         memo: [u8; 32],
     ) -> Result<Self> {
+        Self::with_block_size(path, primary_inputs, num_outputs, memo, BLOCK_SIZE).await
+    }
+
+    /// Create a new writer emitting blocks of `block_size` bytes.
+    ///
+    /// The size must be a power of two from [`MIN_BLOCK_SIZE`] to
+    /// [`MAX_BLOCK_SIZE`]. It is recorded in the header unless it is the
+    /// default [`BLOCK_SIZE`], so default files are unchanged.
+    pub async fn with_block_size(
+        path: impl AsRef<Path>,
+        primary_inputs: u64,
+        num_outputs: u64,
+        memo: [u8; 32],
+        block_size: usize,
+    ) -> Result<Self> {
+        let layout = BlockLayout::new(block_size).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "block size {} is not a power of two from {} to {}",
+                    block_size, MIN_BLOCK_SIZE, MAX_BLOCK_SIZE
+                ),
+            )
+        })?;
+
         // Open/truncate file
         let mut opts = OpenOptions::new();
         opts.create(true).write(true).truncate(true);
@@ -100,7 +130,8 @@ impl WriterV5c {
             memo,
             outputs_offset,
             next_offset: blocks_start_offset,
-            block_buffer: Box::new([0u8; BLOCK_SIZE]),
+            layout,
+            block_buffer: vec![0u8; block_size].into_boxed_slice(),
             gates_in_block: 0,
             io_buf: Vec::with_capacity(DEFAULT_IO_BUFFER_CAP),
             io_buf_cap: DEFAULT_IO_BUFFER_CAP,
@@ -113,7 +144,7 @@ impl WriterV5c {
 
     /// Optionally tune the I/O aggregation buffer capacity (bytes)
     pub fn set_io_buffer_capacity(&mut self, cap: usize) {
-        self.io_buf_cap = cap.max(BLOCK_SIZE);
+        self.io_buf_cap = cap.max(self.layout.block_size());
         if self.io_buf.capacity() < self.io_buf_cap {
             self.io_buf
                 .reserve(self.io_buf_cap - self.io_buf.capacity());
@@ -143,7 +174,7 @@ impl WriterV5c {
         }
 
         // Flush block if full
-        if self.gates_in_block >= GATES_PER_BLOCK {
+        if self.gates_in_block >= self.layout.gates_per_block() {
             self.flush_block().await?;
         }
 
@@ -157,7 +188,7 @@ impl WriterV5c {
 
         // Set gate type bit in types section
         set_gate_type(
-            &mut self.block_buffer[TYPES_OFFSET..],
+            &mut self.block_buffer[self.layout.types_offset()..],
             self.gates_in_block,
             gate_type,
         );
@@ -179,10 +210,10 @@ impl WriterV5c {
             return Ok(());
         }
 
-        // Hash FULL 256 KiB block (including all padding)
+        // Hash FULL block (including all padding)
         self.hasher.update(&self.block_buffer[..]);
 
-        // Write FULL 256 KiB block to disk
+        // Write FULL block to disk
         let block_copy = self.block_buffer.to_vec();
         self.enqueue_bytes(&block_copy).await?;
 
@@ -255,6 +286,7 @@ impl WriterV5c {
         temp_header.primary_inputs = self.primary_inputs;
         temp_header.scratch_space = scratch_space;
         temp_header.num_outputs = self.num_outputs;
+        temp_header.block_size = self.header_block_size();
         // checksum field stays zero for now
 
         let temp_header_bytes = temp_header.to_bytes();
@@ -279,6 +311,7 @@ impl WriterV5c {
         header.primary_inputs = self.primary_inputs;
         header.scratch_space = scratch_space;
         header.num_outputs = self.num_outputs;
+        header.block_size = self.header_block_size();
         header.checksum = checksum;
 
         // Write header at offset 0 (only 120 bytes, not padding)
@@ -303,6 +336,15 @@ impl WriterV5c {
         })
     }
 
+    /// Block size as recorded in the header, 0 for the default
+    fn header_block_size(&self) -> u32 {
+        if self.layout == BlockLayout::DEFAULT {
+            0
+        } else {
+            self.layout.block_size() as u32
+        }
+    }
+
     /// Append bytes to I/O aggregation buffer, flushing if needed
     async fn enqueue_bytes(&mut self, data: &[u8]) -> Result<()> {
         if self.io_buf.len() + data.len() > self.io_buf_cap {
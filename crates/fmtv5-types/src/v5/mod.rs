@@ -7,12 +7,14 @@
 //! All formats use fixed-width encoding for optimal performance with
 //! AVX-512 SIMD processing and io_uring I/O.
 
+#[cfg(feature = "std")]
 pub mod a;
 pub mod c;
+#[cfg(feature = "std")]
 pub mod scalar;
 
 // Decoder module is private - callers use dispatch functions
-#[cfg(target_arch = "x86_64")]
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
 mod avx512;
 
 /// Magic bytes for v5 format: "Zk2u" in ASCII
@@ -47,6 +49,25 @@ impl FormatType {
     }
 }
 
+/// Gate counts of a circuit, uniform across format variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GateCounts {
+    pub xor: u64,
+    pub and: u64,
+    pub total: u64,
+}
+
+impl GateCounts {
+    /// Build counts from XOR and AND totals.
+    pub fn new(xor: u64, and: u64) -> Self {
+        GateCounts {
+            xor,
+            and,
+            total: xor.checked_add(and).expect("total gate overflow"),
+        }
+    }
+}
+
 /// Statistics about a written circuit
 #[derive(Debug, Clone)]
 pub struct CircuitStats {
@@ -59,6 +80,7 @@ pub struct CircuitStats {
     pub checksum: [u8; 32],
 }
 
+#[cfg(feature = "std")]
 impl CircuitStats {
     /// Calculate the average gates per block
     pub fn avg_gates_per_block(&self) -> f64 {
@@ -94,6 +116,7 @@ impl CircuitStats {
 /// - Otherwise: uses portable scalar code
 ///
 /// The caller doesn't need to know which path is taken or manage any scratch space.
+#[cfg(feature = "std")]
 #[inline]
 pub fn decode_block_v5a(
     block_bytes: &[u8],
@@ -200,6 +223,7 @@ mod tests {
         assert_eq!(MAGIC[3] as char, 'u');
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_circuit_stats() {
         let stats = CircuitStats {
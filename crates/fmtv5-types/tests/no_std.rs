@@ -0,0 +1,62 @@
+//! The format types as seen from a `#![no_std]` crate, as in an embedded
+//! verifier.
+//!
+//! With default features this only checks that nothing here needs the `std`
+//! prelude. To check the library itself builds without `std`, run
+//! `cargo test -p ckt-fmtv5-types --no-default-features --features v5 --test no_std`.
+
+#![cfg(feature = "v5")]
+#![no_std]
+#![allow(unused_crate_dependencies)]
+
+// The test harness still needs std
+extern crate std;
+
+use ckt_fmtv5_types::GateType;
+use ckt_fmtv5_types::io::ErrorKind;
+use ckt_fmtv5_types::v5::c::{
+    GATE_SIZE, GateV5c, HEADER_SIZE, HeaderV5c, TYPES_SIZE, calculate_file_size, estimate_garbling,
+    get_gate_type, set_gate_type,
+};
+
+#[test]
+fn test_header_round_trips() {
+    let mut header = HeaderV5c::new();
+    header.xor_gates = 30_000;
+    header.and_gates = 12_000;
+    header.primary_inputs = 64;
+    header.scratch_space = 1 << 20;
+    header.num_outputs = 33;
+
+    let bytes = header.to_bytes();
+    assert_eq!(bytes.len(), HEADER_SIZE);
+    let decoded = HeaderV5c::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.total_gates(), 42_000);
+    assert_eq!(decoded.total_blocks(), 2);
+    assert_eq!(
+        decoded.expected_file_size(),
+        calculate_file_size(42_000, 33)
+    );
+    assert_eq!(estimate_garbling(&decoded).and_gates, 12_000);
+
+    let err = HeaderV5c::from_bytes(&bytes[..HEADER_SIZE - 1]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_gates_and_types_round_trip() {
+    let gate = GateV5c::new(2, 3, 4);
+    let bytes: [u8; GATE_SIZE] = gate.to_bytes();
+    assert_eq!(GateV5c::from_bytes(&bytes), gate);
+    assert_eq!(
+        GateV5c::try_from_addresses(2, 3, 1 << 32)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::InvalidInput
+    );
+
+    let mut types = [0u8; TYPES_SIZE];
+    set_gate_type(&mut types, 9, GateType::AND);
+    assert_eq!(get_gate_type(&types, 9), GateType::AND);
+    assert_eq!(get_gate_type(&types, 8), GateType::XOR);
+}
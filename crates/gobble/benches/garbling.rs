@@ -13,7 +13,7 @@ type Inner = std::arch::aarch64::uint8x16_t;
 
 use bitvec::vec::BitVec;
 use ckt_gobble::{
-    Engine,
+    Engine, HashScheme,
     traits::{
         EvaluationInstance, EvaluationInstanceConfig, GarblingInstance, GarblingInstanceConfig,
         GobbleEngine,
@@ -22,10 +22,10 @@ use ckt_gobble::{
 use criterion::{Criterion, criterion_group, criterion_main};
 
 #[cfg(target_arch = "aarch64")]
-use ckt_gobble::aarch64::{Ciphertext, get_permute_bit, index_to_tweak, xor128};
+use ckt_gobble::aarch64::{Ciphertext, get_permute_bit, index_to_tweak, with_permute_bit, xor128};
 
 #[cfg(target_arch = "x86_64")]
-use ckt_gobble::x86_64::{Ciphertext, get_permute_bit, index_to_tweak, xor128};
+use ckt_gobble::x86_64::{Ciphertext, get_permute_bit, index_to_tweak, with_permute_bit, xor128};
 
 fn bench_garble_xor_gate(c: &mut Criterion) {
     c.bench_function("garble_xor_gate", |b| {
@@ -45,6 +45,7 @@ fn bench_garble_xor_gate(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -76,6 +77,7 @@ fn bench_garble_and_gate(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -107,6 +109,7 @@ fn bench_garble_mixed_gates(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -134,6 +137,93 @@ fn bench_garble_mixed_gates(c: &mut Criterion) {
     });
 }
 
+fn bench_garble_and_level(c: &mut Criterion) {
+    // One level of 1024 independent AND gates, reading the first half of
+    // working space and writing the second
+    let gates: Vec<(usize, usize, usize)> = (0..1024)
+        .map(|i| (i, (i * 7 + 1) % 1024, 1024 + i))
+        .collect();
+
+    let mut group = c.benchmark_group("garble_and_level_1024");
+    for (name, batched) in [("sequential", false), ("batched", true)] {
+        group.bench_function(name, |b| {
+            let primary_input_false_labels = vec![];
+            let config = GarblingInstanceConfig {
+                scratch_space: 2048,
+                delta: [0xFFu8; 16],
+                primary_input_false_labels: &primary_input_false_labels,
+                aes128_key: [0x2Bu8; 16],
+                public_s: [0xDEu8; 16],
+                constant_zero_label: [0x09u8; 16],
+                constant_one_label: [0x08u8; 16],
+                hash_scheme: HashScheme::Ccrnd,
+            };
+
+            let engine = Engine::new();
+            let mut instance = engine.new_garbling_instance(config);
+
+            b.iter(|| {
+                let ciphertexts = if batched {
+                    instance.feed_and_gates(black_box(&gates))
+                } else {
+                    black_box(&gates)
+                        .iter()
+                        .map(|&(in1, in2, out)| instance.feed_and_gate(in1, in2, out))
+                        .collect()
+                };
+                black_box(ciphertexts);
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_garble_scattered_prefetch(c: &mut Criterion) {
+    // Working space far larger than the LLC, with input addresses in no
+    // particular order, so label loads miss unless prefetched
+    const SCRATCH_SPACE: usize = 1 << 22;
+    const NUM_GATES: usize = 4096;
+    let gates: Vec<(usize, usize, usize)> = (0..NUM_GATES)
+        .map(|i| {
+            let mix = |x: usize| x.wrapping_mul(0x9E37_79B9_7F4A_7C15) % SCRATCH_SPACE;
+            (mix(3 * i + 1), mix(3 * i + 2), mix(3 * i + 3))
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("garble_scattered_4096_and_gates");
+    for distance in [0usize, 4, 8, 16] {
+        group.bench_function(format!("prefetch_{}", distance), |b| {
+            let primary_input_false_labels = vec![];
+            let config = GarblingInstanceConfig {
+                scratch_space: SCRATCH_SPACE as u32,
+                delta: [0xFFu8; 16],
+                primary_input_false_labels: &primary_input_false_labels,
+                aes128_key: [0x2Bu8; 16],
+                public_s: [0xDEu8; 16],
+                constant_zero_label: [0x09u8; 16],
+                constant_one_label: [0x08u8; 16],
+                hash_scheme: HashScheme::Ccrnd,
+            };
+
+            let engine = Engine::new();
+            let mut instance = engine.new_garbling_instance(config);
+
+            b.iter(|| {
+                for (i, &(in1, in2, out)) in gates.iter().enumerate() {
+                    if distance > 0
+                        && let Some(&(next1, next2, _)) = gates.get(i + distance)
+                    {
+                        instance.prefetch_wire(next1);
+                        instance.prefetch_wire(next2);
+                    }
+                    black_box(instance.feed_and_gate(in1, in2, out));
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
 fn bench_eval_xor_gate(c: &mut Criterion) {
     c.bench_function("eval_xor_gate", |b| {
         // Setup once: create instance with dummy labels
@@ -152,6 +242,7 @@ fn bench_eval_xor_gate(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -183,6 +274,7 @@ fn bench_eval_and_gate(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -223,6 +315,7 @@ fn bench_eval_mixed_gates(c: &mut Criterion) {
             public_s,
             constant_zero_label,
             constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
         };
 
         let engine = Engine::new();
@@ -251,6 +344,53 @@ fn bench_eval_mixed_gates(c: &mut Criterion) {
     });
 }
 
+fn bench_eval_scattered_gates(c: &mut Criterion) {
+    // Spread over a working space far larger than L2, so each gate's cost is
+    // dominated by the memory traffic of reading inputs and writing the output
+    const SCRATCH_SPACE: usize = 1 << 20;
+    const STRIDE: usize = 4099;
+
+    c.bench_function("eval_scattered_1000_gates", |b| {
+        let selected_primary_input_labels = vec![];
+        let selected_primary_input_values = BitVec::repeat(false, 0);
+        let aes128_key = [0x2Bu8; 16]; // Arbitrary key for benchmarking
+        let public_s = [0xDEu8; 16]; // Arbitrary S for benchmarking
+        let constant_zero_label = [0x09u8; 16];
+        let constant_one_label = [0x08u8; 16];
+
+        let config = EvaluationInstanceConfig {
+            scratch_space: SCRATCH_SPACE as u32,
+            selected_primary_input_labels: &selected_primary_input_labels,
+            selected_primary_input_values: &selected_primary_input_values,
+            aes128_key,
+            public_s,
+            constant_zero_label,
+            constant_one_label,
+            hash_scheme: HashScheme::Ccrnd,
+        };
+
+        let engine = Engine::new();
+        let mut instance = engine.new_evaluation_instance(config);
+
+        let ct_bytes = [0xAAu8; 16];
+        let ciphertext = Ciphertext::from(ct_bytes);
+
+        // Measure: 500 XOR + 500 AND gates at scattered addresses
+        b.iter(|| {
+            for i in 0..500 {
+                let in1 = black_box((i * STRIDE) % SCRATCH_SPACE);
+                let in2 = black_box((i * STRIDE + SCRATCH_SPACE / 3) % SCRATCH_SPACE);
+                let out = black_box((i * STRIDE + 2 * SCRATCH_SPACE / 3) % SCRATCH_SPACE);
+
+                instance.feed_xor_gate(in1, in2, out);
+                instance.feed_and_gate(in2, out, in1, black_box(ciphertext));
+            }
+
+            black_box(&instance);
+        });
+    });
+}
+
 fn bench_xor128(c: &mut Criterion) {
     c.bench_function("xor128", |bencher| unsafe {
         let a_bytes = [0x42u8; 16];
@@ -277,6 +417,18 @@ fn bench_get_permute_bit(c: &mut Criterion) {
     });
 }
 
+fn bench_with_permute_bit(c: &mut Criterion) {
+    c.bench_function("with_permute_bit", |b| unsafe {
+        let label_bytes = [0x42u8; 16];
+        let label = transmute::<[u8; 16], Inner>(label_bytes);
+
+        b.iter(|| {
+            let result = with_permute_bit(black_box(label), black_box(true));
+            black_box(result)
+        });
+    });
+}
+
 fn bench_index_to_tweak(c: &mut Criterion) {
     c.bench_function("index_to_tweak", |b| {
         b.iter(|| unsafe {
@@ -291,11 +443,15 @@ criterion_group!(
     bench_garble_xor_gate,
     bench_garble_and_gate,
     bench_garble_mixed_gates,
+    bench_garble_and_level,
+    bench_garble_scattered_prefetch,
     bench_eval_xor_gate,
     bench_eval_and_gate,
     bench_eval_mixed_gates,
+    bench_eval_scattered_gates,
     bench_xor128,
     bench_get_permute_bit,
+    bench_with_permute_bit,
     bench_index_to_tweak
 );
 
@@ -39,6 +39,22 @@ pub unsafe fn get_permute_bit(label: uint8x16_t) -> bool {
     (bytes[0] & 1) == 1
 }
 
+/// Return `label` with its point-and-permute bit (LSB) set to `bit`.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - The CPU supports the `neon` target feature.
+/// - The `label` parameter contains initialized data (not uninitialized memory).
+#[inline]
+pub unsafe fn with_permute_bit(label: uint8x16_t, bit: bool) -> uint8x16_t {
+    unsafe {
+        let mask = vsetq_lane_u8::<0>(0xFE, vdupq_n_u8(0xFF));
+        let lsb = vsetq_lane_u8::<0>(bit as u8, vdupq_n_u8(0));
+        vorrq_u8(vandq_u8(label, mask), lsb)
+    }
+}
+
 /// XOR two 128-bit values.
 ///
 /// # Safety
@@ -51,6 +67,22 @@ pub unsafe fn xor128(a: uint8x16_t, b: uint8x16_t) -> uint8x16_t {
     unsafe { veorq_u8(a, b) }
 }
 
+/// Hint that the cache line holding `ptr` will be read soon.
+///
+/// # Safety
+///
+/// Prefetches never fault, so `ptr` need not be valid.
+#[inline]
+pub unsafe fn prefetch_read<T>(ptr: *const T) {
+    unsafe {
+        std::arch::asm!(
+            "prfm pldl1keep, [{0}]",
+            in(reg) ptr,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+}
+
 /// Convert gate index to tweak value.
 ///
 /// # Safety
@@ -139,6 +171,34 @@ pub unsafe fn ccrnd_with_round_keys(
     }
 }
 
+/// TMMO hash function using caller-provided round keys.
+///
+/// This is the tweakable MMO construction `π(π(x) ⊕ t) ⊕ π(x)` from
+/// <https://eprint.iacr.org/2019/074.pdf>, using two AES calls.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - The CPU supports the `aes` and `neon` target features.
+/// - `x` and `tweak` are valid 128-bit values.
+/// - The `round_keys` are a valid AES-128 key schedule.
+#[inline]
+#[target_feature(enable = "aes")]
+#[target_feature(enable = "neon")]
+pub unsafe fn tmmo_with_round_keys(
+    x: uint8x16_t,
+    tweak: uint8x16_t,
+    round_keys: &Aes128RoundKeys,
+) -> uint8x16_t {
+    let pi_x = unsafe { aes_encrypt_with_round_keys(x, round_keys) };
+    unsafe {
+        xor128(
+            aes_encrypt_with_round_keys(xor128(pi_x, tweak), round_keys),
+            pi_x,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,6 +232,94 @@ mod tests {
         }
     }
 
+    /// Byte-level σ: the low 8 bytes become `lo ⊕ hi` and the high 8 bytes
+    /// become `lo`, matching `L || R -> (L ⊕ R) || L` with `L` the low half.
+    fn sigma_reference(x: [u8; 16]) -> [u8; 16] {
+        let mut out = [0u8; 16];
+        for i in 0..8 {
+            out[i] = x[i] ^ x[i + 8];
+            out[i + 8] = x[i];
+        }
+        out
+    }
+
+    #[test]
+    fn test_sigma_matches_scalar_reference() {
+        let mut rng = rand::rng();
+        let mut inputs = vec![[0u8; 16], [0xFF; 16], std::array::from_fn(|i| i as u8)];
+        for _ in 0..1000 {
+            let mut x = [0u8; 16];
+            rng.fill_bytes(&mut x);
+            inputs.push(x);
+        }
+
+        for x in inputs {
+            let result: [u8; 16] =
+                unsafe { transmute(sigma(transmute::<[u8; 16], uint8x16_t>(x))) };
+            assert_eq!(result, sigma_reference(x), "input {:02x?}", x);
+        }
+    }
+
+    #[test]
+    fn test_sigma_known_vector() {
+        let x = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20,
+            0x40, 0x80,
+        ];
+        let expected = [
+            0x01, 0x13, 0x26, 0x3b, 0x54, 0x75, 0x26, 0xf7, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55,
+            0x66, 0x77,
+        ];
+        let result: [u8; 16] = unsafe { transmute(sigma(transmute::<[u8; 16], uint8x16_t>(x))) };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_sigma_is_invertible() {
+        // σ⁻¹: A || B -> B || (A ⊕ B), again with the low half first
+        let sigma_inverse = |y: [u8; 16]| -> [u8; 16] {
+            std::array::from_fn(|i| if i < 8 { y[i + 8] } else { y[i - 8] ^ y[i] })
+        };
+
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let mut x = [0u8; 16];
+            rng.fill_bytes(&mut x);
+            let y: [u8; 16] = unsafe { transmute(sigma(transmute::<[u8; 16], uint8x16_t>(x))) };
+            assert_eq!(sigma_inverse(y), x, "input {:02x?}", x);
+        }
+    }
+
+    #[test]
+    fn test_ccrnd_matches_scalar_reference() {
+        let mut rng = rand::rng();
+        for i in 0..1000 {
+            let [mut key, mut x, mut tweak, mut s] = [[0u8; 16]; 4];
+            for bytes in [&mut key, &mut x, &mut tweak, &mut s] {
+                rng.fill_bytes(bytes);
+            }
+
+            let round_keys = unsafe { expand_aes128_key(&key) };
+            let result: [u8; 16] = unsafe {
+                transmute(ccrnd_with_round_keys(
+                    transmute::<[u8; 16], uint8x16_t>(x),
+                    transmute::<[u8; 16], uint8x16_t>(tweak),
+                    &round_keys,
+                    transmute::<[u8; 16], uint8x16_t>(s),
+                ))
+            };
+
+            // H(x) = π(σ(x ⊕ S ⊕ t)) ⊕ σ(x ⊕ S ⊕ t)
+            let input: [u8; 16] = std::array::from_fn(|j| x[j] ^ s[j] ^ tweak[j]);
+            let sigma_input = sigma_reference(input);
+            let mut block = sigma_input.into();
+            Aes128::new(&key.into()).encrypt_block(&mut block);
+            let expected: [u8; 16] = std::array::from_fn(|j| block[j] ^ sigma_input[j]);
+
+            assert_eq!(result, expected, "failed at test {}", i);
+        }
+    }
+
     #[test]
     fn test_ccrnd_output_bytes() {
         // Test vectors: fixed inputs to ensure reproducible outputs
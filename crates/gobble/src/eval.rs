@@ -5,70 +5,79 @@
 
 use std::mem::transmute;
 
-use bitvec::vec::BitVec;
-
+use crate::hash::{AesBackend, HashScheme, RoundKeys, hash_with_round_keys};
 use crate::traits::{EvaluationInstance, EvaluationInstanceConfig};
 use crate::types::{Ciphertext, Inner, Label};
 
 // Conditional imports for architecture-specific intrinsics
 #[cfg(target_arch = "aarch64")]
-use crate::aarch64::{ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, xor128};
+use crate::aarch64::{get_permute_bit, index_to_tweak, with_permute_bit, xor128};
 
 #[cfg(target_arch = "x86_64")]
-use crate::x86_64::{ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, xor128};
+use crate::x86_64::{get_permute_bit, index_to_tweak, with_permute_bit, xor128};
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+use crate::scalar::{get_permute_bit, index_to_tweak, with_permute_bit, xor128};
 
 /// Evaluation instance for evaluating a garbled circuit.
+///
+/// Each wire's value is its label's point-and-permute bit (LSB), so there is
+/// no separate value store to read and write per gate.
 #[derive(Debug)]
 pub struct EvaluationInstanceImpl {
     /// Counter for the number of gates evaluated.
     gate_ctr: u64,
     /// Counter for the number of AND gates evaluated.
     and_ctr: u64,
-    /// Working/scratch space for wire labels.
+    /// Working/scratch space for wire labels, with values in their LSBs.
     working_space: Vec<Label>,
-    /// Working/scratch space for wire values.
-    working_space_bits: BitVec,
-    round_keys: [Inner; 11],
+    round_keys: RoundKeys,
     public_s: Inner,
+    hash_scheme: HashScheme,
 }
 
 impl EvaluationInstanceImpl {
-    /// Initialize a new evaluation instance with the given configuration.
-    ///
-    /// # Safety
-    ///
-    /// The caller must ensure the CPU supports the required target features (AES and NEON/SSE2).
+    /// Initialize a new evaluation instance with the given configuration,
+    /// hashing with hardware AES if the CPU has it and in software otherwise.
     pub fn new(config: EvaluationInstanceConfig<'_>) -> Self {
+        Self::with_backend(config, AesBackend::detect())
+    }
+
+    /// Initialize a new evaluation instance hashing with `backend`.
+    pub(crate) fn with_backend(config: EvaluationInstanceConfig<'_>, backend: AesBackend) -> Self {
         let empty_label = Label::default();
         let mut working_space = vec![empty_label; config.scratch_space as usize];
 
-        // Wire 0 is constant false label, wire 1 is constant true label
-        working_space[0] = Label::from(config.constant_zero_label);
-        working_space[1] = Label::from(config.constant_one_label);
-
-        // Set primary input labels starting at position 2
-        for (label, i) in config.selected_primary_input_labels.iter().zip(2..) {
-            working_space[i] = Label(unsafe { transmute::<[u8; 16], Inner>(*label) });
-        }
+        let label_with_value = |label: [u8; 16], value| {
+            Label(unsafe { with_permute_bit(Label::from(label).0, value) })
+        };
 
-        // Initialize working space bits
-        let mut working_space_bits = BitVec::repeat(false, config.scratch_space as usize);
-        working_space_bits.set(0, false);
-        working_space_bits.set(1, true);
-        for (value, i) in config.selected_primary_input_values.iter().zip(2..) {
-            working_space_bits.set(i, *value);
+        // Wire 0 is constant false label, wire 1 is constant true label
+        working_space[0] = label_with_value(config.constant_zero_label, false);
+        working_space[1] = label_with_value(config.constant_one_label, true);
+
+        // Set primary input labels starting at position 2. The garbler already
+        // encodes each value in the label's LSB; setting it again keeps the
+        // configured values authoritative.
+        for ((label, value), i) in config
+            .selected_primary_input_labels
+            .iter()
+            .zip(config.selected_primary_input_values.iter())
+            .zip(2..)
+        {
+            working_space[i] = label_with_value(*label, *value);
         }
 
-        let round_keys = unsafe { expand_aes128_key(&config.aes128_key) };
+        let round_keys = RoundKeys::new(backend, &config.aes128_key);
         let public_s = unsafe { transmute::<[u8; 16], Inner>(config.public_s) };
 
         EvaluationInstanceImpl {
             gate_ctr: 0,
             and_ctr: 0,
             working_space,
-            working_space_bits,
             round_keys,
             public_s,
+            hash_scheme: config.hash_scheme,
         }
     }
 }
@@ -82,8 +91,6 @@ impl EvaluationInstance for EvaluationInstanceImpl {
         let in1 = self.working_space[in1_addr];
         let in2 = self.working_space[in2_addr];
         self.working_space[out_addr] = Label(unsafe { xor128(in1.0, in2.0) });
-        let value = self.working_space_bits[in1_addr] ^ self.working_space_bits[in2_addr];
-        self.working_space_bits.set(out_addr, value);
         self.gate_ctr += 1;
     }
 
@@ -100,18 +107,21 @@ impl EvaluationInstance for EvaluationInstanceImpl {
 
         // PFHG evaluation: out = H(in1, t) ⊕ permute_bit * (in2 ⊕ ciphertext)
         let t = unsafe { index_to_tweak(self.gate_ctr) };
-        let permute_bit = self.working_space_bits[in1_addr];
-
-        let mut out_label =
-            unsafe { ccrnd_with_round_keys(in1.0, t, &self.round_keys, self.public_s) };
+        let permute_bit = unsafe { get_permute_bit(in1.0) };
+
+        // The garbler clears the hash's LSB so the output's LSB is its value
+        let mut out_label = unsafe {
+            with_permute_bit(
+                hash_with_round_keys(self.hash_scheme, in1.0, t, &self.round_keys, self.public_s),
+                false,
+            )
+        };
         if permute_bit {
             out_label = unsafe { xor128(out_label, xor128(ciphertext.0, in2.0)) };
         }
 
         // Write output label to working space
         self.working_space[out_addr] = Label(out_label);
-        let value = self.working_space_bits[in1_addr] & self.working_space_bits[in2_addr];
-        self.working_space_bits.set(out_addr, value);
 
         // Increment gate counter to enforce uniqueness
         self.gate_ctr += 1;
@@ -127,7 +137,34 @@ impl EvaluationInstance for EvaluationInstanceImpl {
 
     fn get_values(&self, wires: &[u64], values: &mut [bool]) {
         for (i, wire_id) in wires.iter().enumerate() {
-            values[i] = self.working_space_bits[*wire_id as usize];
+            values[i] = unsafe { get_permute_bit(self.working_space[*wire_id as usize].0) };
+        }
+    }
+
+    fn apply_translation(&mut self, output_wires: &[u64], ciphertexts: &[Ciphertext]) {
+        assert_eq!(
+            output_wires.len(),
+            ciphertexts.len(),
+            "apply_translation: wire count does not match ciphertext count"
+        );
+
+        for (&wire_id, ciphertext) in output_wires.iter().zip(ciphertexts) {
+            let wire_id = wire_id as usize;
+            let label = self.working_space[wire_id];
+
+            // label = H(label, t) ⊕ ciphertext, which is the target when false
+            let t = unsafe { index_to_tweak(self.gate_ctr) };
+            let h = unsafe {
+                hash_with_round_keys(
+                    self.hash_scheme,
+                    label.0,
+                    t,
+                    &self.round_keys,
+                    self.public_s,
+                )
+            };
+            self.working_space[wire_id] = Label(unsafe { xor128(h, ciphertext.0) });
+            self.gate_ctr += 1;
         }
     }
 }
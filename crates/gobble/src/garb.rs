@@ -6,16 +6,23 @@
 use std::{fmt, mem::transmute};
 
 use bitvec::vec::BitVec;
+use thiserror::Error;
 
+use crate::hash::{
+    AesBackend, HashScheme, RoundKeys, hash_many_with_round_keys, hash_with_round_keys,
+};
 use crate::traits::{GarblingInstance, GarblingInstanceConfig};
 use crate::types::{Ciphertext, Inner, Label};
 
 // Conditional imports for architecture-specific intrinsics
 #[cfg(target_arch = "aarch64")]
-use crate::aarch64::{ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, xor128};
+use crate::aarch64::{index_to_tweak, prefetch_read, with_permute_bit, xor128};
 
 #[cfg(target_arch = "x86_64")]
-use crate::x86_64::{ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, xor128};
+use crate::x86_64::{index_to_tweak, prefetch_read, with_permute_bit, xor128};
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+use crate::scalar::{index_to_tweak, prefetch_read, with_permute_bit, xor128};
 
 /// Garbling instance that produces a garbled circuit.
 pub struct GarblingInstanceImpl {
@@ -23,8 +30,11 @@ pub struct GarblingInstanceImpl {
     and_ctr: u64,
     working_space: Vec<Label>,
     delta: Inner,
-    round_keys: [Inner; 11],
+    round_keys: RoundKeys,
     public_s: Inner,
+    hash_scheme: HashScheme,
+    ciphertext_hasher: Option<blake3::Hasher>,
+    primary_inputs: usize,
 }
 
 impl fmt::Debug for GarblingInstanceImpl {
@@ -34,45 +44,386 @@ impl fmt::Debug for GarblingInstanceImpl {
 }
 
 impl GarblingInstanceImpl {
-    /// Creates a new garbling instance with the given configuration.
-    ///
-    /// # Safety
-    ///
-    /// The caller must ensure the CPU supports the required target features (AES and NEON/SSE2).
+    /// Creates a new garbling instance with the given configuration, hashing
+    /// with hardware AES if the CPU has it and in software otherwise.
     pub fn new(config: GarblingInstanceConfig<'_>) -> Self {
+        Self::with_backend(config, AesBackend::detect())
+    }
+
+    /// Creates a new garbling instance hashing with `backend`.
+    pub(crate) fn with_backend(config: GarblingInstanceConfig<'_>, backend: AesBackend) -> Self {
         let empty_label = Label::default();
         let mut working_space = vec![empty_label; config.scratch_space as usize];
+        let delta = delta_from_config(&config);
+
+        // The evaluator reads each wire's value from its label's LSB, so every
+        // false label has LSB 0 and the delta has LSB 1.
+        let false_label =
+            |label: [u8; 16]| Label(unsafe { with_permute_bit(Label::from(label).0, false) });
 
         // Wire 0 is constant false label, wire 1 is constant true label
         // The garbler stores the false label for each wire internally (FreeXOR invariant)
         // For wire 1, false_label = constant_one_label XOR delta (so true_label = constant_one_label)
-        working_space[0] = Label::from(config.constant_zero_label);
+        working_space[0] = false_label(config.constant_zero_label);
         working_space[1] = Label(unsafe {
             xor128(
-                transmute::<[u8; 16], Inner>(config.constant_one_label),
-                transmute::<[u8; 16], Inner>(config.delta),
+                with_permute_bit(Label::from(config.constant_one_label).0, true),
+                delta,
             )
         });
 
         // Set primary input labels starting at position 2
         for (label, i) in config.primary_input_false_labels.iter().zip(2..) {
-            working_space[i] = Label(unsafe { transmute::<[u8; 16], Inner>(*label) });
+            working_space[i] = false_label(*label);
         }
 
-        let round_keys = unsafe { expand_aes128_key(&config.aes128_key) };
+        let round_keys = RoundKeys::new(backend, &config.aes128_key);
         let public_s = unsafe { transmute::<[u8; 16], Inner>(config.public_s) };
 
         GarblingInstanceImpl {
             gate_ctr: 0,
             working_space,
-            delta: unsafe { transmute::<[u8; 16], Inner>(config.delta) },
+            delta,
             and_ctr: 0,
             round_keys,
             public_s,
+            hash_scheme: config.hash_scheme,
+            ciphertext_hasher: None,
+            primary_inputs: config.primary_input_false_labels.len(),
         }
     }
 }
 
+impl GarblingInstanceImpl {
+    /// Returns the number of gates garbled so far.
+    pub fn gates_garbled(&self) -> u64 {
+        self.gate_ctr
+    }
+
+    /// Hints that the label at `addr` will be read soon.
+    ///
+    /// Garbling loops call this for a gate some distance ahead, so its
+    /// input labels are in cache by the time it is fed. Out-of-range
+    /// addresses are ignored.
+    #[inline]
+    pub fn prefetch_wire(&self, addr: usize) {
+        if let Some(label) = self.working_space.get(addr) {
+            unsafe { prefetch_read(label) };
+        }
+    }
+
+    /// Returns the AND gate hash scheme, which the evaluator must be told.
+    pub fn hash_scheme(&self) -> HashScheme {
+        self.hash_scheme
+    }
+
+    /// Snapshots the garbling progress so it can be resumed later.
+    ///
+    /// The checkpoint holds the false label of every wire in working space,
+    /// which together with the delta reveals all labels. Keep it as secret as
+    /// the delta itself.
+    pub fn checkpoint(&self) -> GarblingCheckpoint {
+        GarblingCheckpoint {
+            gate_ctr: self.gate_ctr,
+            and_ctr: self.and_ctr,
+            working_space: self.working_space.clone(),
+        }
+    }
+
+    /// Resumes garbling from a checkpoint.
+    ///
+    /// `config` must be the same one used to create the checkpointed instance;
+    /// only the delta, AES key, public S and hash scheme are taken from it, the labels come
+    /// from the checkpoint. The caller then skips the first
+    /// [`GarblingCheckpoint::gates_garbled`] gates of the circuit and feeds
+    /// the rest as usual.
+    pub fn resume(
+        config: GarblingInstanceConfig<'_>,
+        checkpoint: GarblingCheckpoint,
+    ) -> Result<Self, CheckpointError> {
+        if checkpoint.working_space.len() != config.scratch_space as usize {
+            return Err(CheckpointError::ScratchSpaceMismatch {
+                expected: config.scratch_space as usize,
+                actual: checkpoint.working_space.len(),
+            });
+        }
+
+        let round_keys = RoundKeys::new(AesBackend::detect(), &config.aes128_key);
+        let public_s = unsafe { transmute::<[u8; 16], Inner>(config.public_s) };
+
+        Ok(GarblingInstanceImpl {
+            gate_ctr: checkpoint.gate_ctr,
+            and_ctr: checkpoint.and_ctr,
+            working_space: checkpoint.working_space,
+            delta: delta_from_config(&config),
+            round_keys,
+            public_s,
+            hash_scheme: config.hash_scheme,
+            ciphertext_hasher: None,
+            primary_inputs: config.primary_input_false_labels.len(),
+        })
+    }
+
+    /// Commits to the false and true label of every primary input, in wire
+    /// order starting at wire 2.
+    ///
+    /// Each commitment is a BLAKE3 hash of the wire ID and its label pair,
+    /// checked with [`open_commitment`]. Call this before feeding any gate,
+    /// since later gates may overwrite input addresses. Opening a commitment
+    /// reveals both labels and so the delta, as it should for a circuit
+    /// opened in cut-and-choose.
+    pub fn input_label_commitments(&self) -> Vec<[u8; 32]> {
+        (2..2 + self.primary_inputs)
+            .map(|wire| {
+                let false_label = self.working_space[wire];
+                let true_label = Label(unsafe { xor128(false_label.0, self.delta) });
+                commit_label_pair(wire as u64, [false_label.to_bytes(), true_label.to_bytes()])
+            })
+            .collect()
+    }
+
+    /// Hashes every ciphertext this instance produces, in order, with BLAKE3.
+    ///
+    /// Lets callers commit to a garbled circuit too large to buffer. The
+    /// hash is not part of a [`GarblingCheckpoint`], so a resumed instance
+    /// only hashes the ciphertexts produced after resuming.
+    pub fn with_ciphertext_hash(mut self) -> Self {
+        self.ciphertext_hasher = Some(blake3::Hasher::new());
+        self
+    }
+
+    /// Consumes the instance, returning the false label of every wire in
+    /// working space and the hash of all ciphertexts produced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the instance was not created with
+    /// [`GarblingInstanceImpl::with_ciphertext_hash`].
+    pub fn finish_with_hash(self) -> (Vec<Label>, [u8; 32]) {
+        let hasher = self
+            .ciphertext_hasher
+            .expect("finish_with_hash: ciphertext hashing was not enabled");
+        (self.working_space, *hasher.finalize().as_bytes())
+    }
+}
+
+impl GarblingInstanceImpl {
+    #[inline]
+    unsafe fn hash(&self, x: Inner, tweak: Inner) -> Inner {
+        unsafe { hash_with_round_keys(self.hash_scheme, x, tweak, &self.round_keys, self.public_s) }
+    }
+
+    #[inline]
+    fn emit(&mut self, ciphertext: Inner) -> Ciphertext {
+        let ciphertext = Ciphertext(ciphertext);
+        if let Some(hasher) = &mut self.ciphertext_hasher {
+            hasher.update(&<[u8; 16]>::from(ciphertext));
+        }
+        ciphertext
+    }
+}
+
+/// AND gates garbled together by [`GarblingInstanceImpl::feed_and_gates`],
+/// each needing two hashes.
+const AND_BATCH: usize = 4;
+
+impl GarblingInstanceImpl {
+    /// Garbles a batch of independent AND gates, given as
+    /// `(in1_addr, in2_addr, out_addr)`, returning one ciphertext per gate.
+    ///
+    /// The result is the same as feeding each gate to
+    /// [`GarblingInstance::feed_and_gate`] in order, but the hashes of
+    /// several gates are computed together so that their AES calls overlap.
+    /// No gate may read another gate's output from the same batch, which
+    /// holds for gates on the same level.
+    pub fn feed_and_gates(&mut self, gates: &[(usize, usize, usize)]) -> Vec<Ciphertext> {
+        let mut ciphertexts = Vec::with_capacity(gates.len());
+        let mut chunks = gates.chunks_exact(AND_BATCH);
+        for chunk in &mut chunks {
+            debug_assert!(
+                chunk.iter().enumerate().all(|(i, &(in1, in2, _))| {
+                    chunk[..i]
+                        .iter()
+                        .all(|&(_, _, out)| out != in1 && out != in2)
+                }),
+                "feed_and_gates: gates in a batch must be independent"
+            );
+
+            // Both hashes of every gate, as [H(in1, t), H(in1 ⊕ delta, t)]
+            let in1s: [Inner; AND_BATCH] =
+                std::array::from_fn(|i| self.working_space[chunk[i].0].0);
+            let xs: [Inner; 2 * AND_BATCH] = std::array::from_fn(|j| {
+                let in1 = in1s[j / 2];
+                if j % 2 == 0 {
+                    in1
+                } else {
+                    unsafe { xor128(in1, self.delta) }
+                }
+            });
+            let tweaks: [Inner; 2 * AND_BATCH] =
+                std::array::from_fn(|j| unsafe { index_to_tweak(self.gate_ctr + (j / 2) as u64) });
+            let hashes = unsafe {
+                hash_many_with_round_keys(
+                    self.hash_scheme,
+                    xs,
+                    tweaks,
+                    &self.round_keys,
+                    self.public_s,
+                )
+            };
+
+            for (i, &(_, in2_addr, out_addr)) in chunk.iter().enumerate() {
+                let h_in1_t = unsafe { with_permute_bit(hashes[2 * i], false) };
+                let h_in1_delta_t = unsafe { with_permute_bit(hashes[2 * i + 1], false) };
+                let in2 = self.working_space[in2_addr];
+                let ciphertext = unsafe { xor128(xor128(h_in1_t, h_in1_delta_t), in2.0) };
+
+                self.working_space[out_addr] = Label(h_in1_t);
+                self.gate_ctr += 1;
+                self.and_ctr += 1;
+                ciphertexts.push(self.emit(ciphertext));
+            }
+        }
+
+        for &(in1_addr, in2_addr, out_addr) in chunks.remainder() {
+            ciphertexts.push(self.feed_and_gate(in1_addr, in2_addr, out_addr));
+        }
+        ciphertexts
+    }
+}
+
+const COMMITMENT_CONTEXT: &str = "ckt-gobble 2026-01 input label commitment";
+
+fn commit_label_pair(wire: u64, labels: [[u8; 16]; 2]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_derive_key(COMMITMENT_CONTEXT);
+    hasher.update(&wire.to_le_bytes());
+    hasher.update(&labels[0]);
+    hasher.update(&labels[1]);
+    *hasher.finalize().as_bytes()
+}
+
+/// Checks that `commitment`, from
+/// [`GarblingInstanceImpl::input_label_commitments`], commits wire `wire` to
+/// the given false and true labels.
+pub fn open_commitment(commitment: &[u8; 32], wire: u64, labels: [[u8; 16]; 2]) -> bool {
+    // blake3::Hash compares in constant time
+    blake3::Hash::from(*commitment) == blake3::Hash::from(commit_label_pair(wire, labels))
+}
+
+/// The configured delta with its LSB forced to 1, so that a wire's true and
+/// false labels always differ in their point-and-permute bit.
+fn delta_from_config(config: &GarblingInstanceConfig<'_>) -> Inner {
+    unsafe { with_permute_bit(Label::from(config.delta).0, true) }
+}
+
+/// Errors that can occur when restoring a garbling checkpoint.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The checkpoint's working space doesn't match the config's scratch space.
+    #[error("checkpoint has {actual} wires but scratch space is {expected}")]
+    ScratchSpaceMismatch {
+        /// Scratch space from the config.
+        expected: usize,
+        /// Number of wires held in the checkpoint.
+        actual: usize,
+    },
+
+    /// The serialized checkpoint is shorter or longer than its header implies.
+    #[error("checkpoint is {actual} bytes but {expected} were expected")]
+    BadLength {
+        /// Length implied by the checkpoint header.
+        expected: usize,
+        /// Length of the buffer provided.
+        actual: usize,
+    },
+}
+
+/// Saved progress of a [`GarblingInstanceImpl`].
+///
+/// Serialized as `gate_ctr || and_ctr || num_wires` (all u64 LE) followed
+/// by 16 bytes per wire label.
+#[derive(Clone)]
+pub struct GarblingCheckpoint {
+    gate_ctr: u64,
+    and_ctr: u64,
+    working_space: Vec<Label>,
+}
+
+impl fmt::Debug for GarblingCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GarblingCheckpoint")
+            .field("gate_ctr", &self.gate_ctr)
+            .field("and_ctr", &self.and_ctr)
+            .field("working_space", &"<redacted>")
+            .finish()
+    }
+}
+
+impl GarblingCheckpoint {
+    const HEADER_SIZE: usize = 24;
+
+    /// Returns the number of gates garbled before the checkpoint was taken.
+    pub fn gates_garbled(&self) -> u64 {
+        self.gate_ctr
+    }
+
+    /// Returns the number of AND gates (ciphertexts) produced before the
+    /// checkpoint was taken.
+    pub fn and_gates_garbled(&self) -> u64 {
+        self.and_ctr
+    }
+
+    /// Serializes the checkpoint to bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_SIZE + self.working_space.len() * 16);
+        bytes.extend_from_slice(&self.gate_ctr.to_le_bytes());
+        bytes.extend_from_slice(&self.and_ctr.to_le_bytes());
+        bytes.extend_from_slice(&(self.working_space.len() as u64).to_le_bytes());
+        for label in &self.working_space {
+            let label_bytes: [u8; 16] = (*label).into();
+            bytes.extend_from_slice(&label_bytes);
+        }
+        bytes
+    }
+
+    /// Deserializes a checkpoint produced by [`GarblingCheckpoint::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        let read_u64 = |i: usize| u64::from_le_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap());
+
+        if bytes.len() < Self::HEADER_SIZE {
+            return Err(CheckpointError::BadLength {
+                expected: Self::HEADER_SIZE,
+                actual: bytes.len(),
+            });
+        }
+        let gate_ctr = read_u64(0);
+        let and_ctr = read_u64(1);
+        let num_wires = read_u64(2) as usize;
+
+        let expected = num_wires
+            .checked_mul(16)
+            .and_then(|n| n.checked_add(Self::HEADER_SIZE))
+            .unwrap_or(usize::MAX);
+        if bytes.len() != expected {
+            return Err(CheckpointError::BadLength {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let working_space = bytes[Self::HEADER_SIZE..]
+            .chunks_exact(16)
+            .map(|chunk| Label::from(<[u8; 16]>::try_from(chunk).unwrap()))
+            .collect();
+
+        Ok(GarblingCheckpoint {
+            gate_ctr,
+            and_ctr,
+            working_space,
+        })
+    }
+}
+
 /// Implements the GarblingInstance trait using the privacy-free half-gates
 /// construction from ZRE15 <https://eprint.iacr.org/2014/756>.
 impl GarblingInstance for GarblingInstanceImpl {
@@ -95,13 +446,13 @@ impl GarblingInstance for GarblingInstanceImpl {
         let in1 = self.working_space[in1_addr];
         let in2 = self.working_space[in2_addr];
 
-        // PFHG garbling: ciphertext = H(in1, t) ⊕ H(in1 ⊕ delta, t) ⊕ in2
+        // PFHG garbling: ciphertext = H(in1, t) ⊕ H(in1 ⊕ delta, t) ⊕ in2, with
+        // both hashes' LSBs cleared so the false output label keeps LSB 0
         let t = unsafe { index_to_tweak(self.gate_ctr) };
         let xor_in1_delta = unsafe { xor128(in1.0, self.delta) };
 
-        let h_in1_t = unsafe { ccrnd_with_round_keys(in1.0, t, &self.round_keys, self.public_s) };
-        let h_in1_delta_t =
-            unsafe { ccrnd_with_round_keys(xor_in1_delta, t, &self.round_keys, self.public_s) };
+        let h_in1_t = unsafe { with_permute_bit(self.hash(in1.0, t), false) };
+        let h_in1_delta_t = unsafe { with_permute_bit(self.hash(xor_in1_delta, t), false) };
 
         let ciphertext = unsafe { xor128(xor128(h_in1_t, h_in1_delta_t), in2.0) };
 
@@ -111,7 +462,7 @@ impl GarblingInstance for GarblingInstanceImpl {
         // Increment gate counter to enforce uniqueness
         self.gate_ctr += 1;
         self.and_ctr += 1;
-        Ciphertext(ciphertext)
+        self.emit(ciphertext)
     }
 
     fn get_selected_labels(&self, wires: &[u64], values: &BitVec, labels: &mut [[u8; 16]]) {
@@ -131,4 +482,269 @@ impl GarblingInstance for GarblingInstanceImpl {
             labels[i] = unsafe { transmute::<Inner, [u8; 16]>(selected_label.0) };
         }
     }
+
+    fn translate_outputs(
+        &mut self,
+        output_wires: &[u64],
+        targets: &[[u8; 16]],
+    ) -> Vec<Self::Ciphertext> {
+        assert_eq!(
+            output_wires.len(),
+            targets.len(),
+            "translate_outputs: wire count does not match target count"
+        );
+
+        output_wires
+            .iter()
+            .zip(targets)
+            .map(|(&wire_id, target)| {
+                let wire_id = wire_id as usize;
+                let false_label = self.working_space[wire_id];
+                let target = Label::from(*target);
+
+                // ciphertext = H(false_label, t) ⊕ target. Hashing rather than
+                // XORing the label keeps delta hidden once the target is revealed.
+                let t = unsafe { index_to_tweak(self.gate_ctr) };
+                let ciphertext = unsafe { xor128(self.hash(false_label.0, t), target.0) };
+
+                self.working_space[wire_id] = target;
+                self.gate_ctr += 1;
+                self.emit(ciphertext)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{SCRATCH_SPACE, garbling_config, test_gates};
+
+    fn feed(
+        instance: &mut GarblingInstanceImpl,
+        gates: &[(usize, usize, usize, bool)],
+        hasher: &mut blake3::Hasher,
+    ) {
+        for &(in1, in2, out, is_and) in gates {
+            if is_and {
+                let ct: [u8; 16] = instance.feed_and_gate(in1, in2, out).into();
+                hasher.update(&ct);
+            } else {
+                instance.feed_xor_gate(in1, in2, out);
+            }
+        }
+    }
+
+    fn output_labels(instance: &GarblingInstanceImpl) -> Vec<[u8; 16]> {
+        let wires: Vec<u64> = (0..SCRATCH_SPACE as u64).collect();
+        let values = BitVec::repeat(false, wires.len());
+        let mut labels = vec![[0u8; 16]; wires.len()];
+        instance.get_selected_labels(&wires, &values, &mut labels);
+        labels
+    }
+
+    #[test]
+    fn test_checkpoint_resume_matches_uninterrupted() {
+        let input_labels = [[0xAA; 16], [0xBB; 16], [0xCC; 16]];
+        let gates = test_gates();
+
+        // Uninterrupted run
+        let mut full = GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd));
+        let mut full_hasher = blake3::Hasher::new();
+        feed(&mut full, &gates, &mut full_hasher);
+
+        // Garble half, checkpoint through bytes, resume and skip garbled gates
+        let mut first =
+            GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd));
+        let mut resumed_hasher = blake3::Hasher::new();
+        feed(&mut first, &gates[..gates.len() / 2], &mut resumed_hasher);
+        let saved = first.checkpoint().to_bytes();
+        drop(first);
+
+        let checkpoint = GarblingCheckpoint::from_bytes(&saved).unwrap();
+        let skip = checkpoint.gates_garbled() as usize;
+        assert_eq!(skip, gates.len() / 2);
+
+        let mut resumed = GarblingInstanceImpl::resume(
+            garbling_config(&input_labels, HashScheme::Ccrnd),
+            checkpoint,
+        )
+        .unwrap();
+        feed(&mut resumed, &gates[skip..], &mut resumed_hasher);
+
+        assert_eq!(resumed.gates_garbled(), full.gates_garbled());
+        assert_eq!(full_hasher.finalize(), resumed_hasher.finalize());
+        assert_eq!(output_labels(&full), output_labels(&resumed));
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_bad_input() {
+        let input_labels = [[0xAA; 16]];
+        let instance = GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd));
+        let bytes = instance.checkpoint().to_bytes();
+
+        assert!(matches!(
+            GarblingCheckpoint::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CheckpointError::BadLength { .. })
+        ));
+
+        let mut small = garbling_config(&input_labels, HashScheme::Ccrnd);
+        small.scratch_space = SCRATCH_SPACE - 1;
+        assert!(matches!(
+            GarblingInstanceImpl::resume(small, instance.checkpoint()),
+            Err(CheckpointError::ScratchSpaceMismatch {
+                expected: 15,
+                actual: 16
+            })
+        ));
+    }
+
+    #[test]
+    fn test_labels_reloaded_from_bytes_garble_identically() {
+        let (labels, delta) = crate::types::expand_seed([9; 32], 4);
+        let label = labels[0];
+        assert_eq!(
+            Label::from_bytes(label.to_bytes()).to_bytes(),
+            label.to_bytes()
+        );
+
+        // Export delta and the false labels as one buffer, as if sending them
+        // to another process, then rebuild them from the bytes alone
+        let exported: Vec<u8> = std::iter::once(delta)
+            .chain(labels.iter().copied())
+            .flat_map(Label::to_bytes)
+            .collect();
+        let imported: Vec<Label> = exported
+            .chunks_exact(16)
+            .map(|chunk| Label::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        let seeded: Vec<[u8; 16]> = labels.iter().map(|l| l.to_bytes()).collect();
+        let explicit: Vec<[u8; 16]> = imported[1..].iter().map(|l| l.to_bytes()).collect();
+        let mut seeded_config = garbling_config(&seeded, HashScheme::Ccrnd);
+        seeded_config.delta = delta.to_bytes();
+        let mut explicit_config = garbling_config(&explicit, HashScheme::Ccrnd);
+        explicit_config.delta = imported[0].to_bytes();
+
+        let gates = test_gates();
+        let mut hashers = [blake3::Hasher::new(), blake3::Hasher::new()];
+        let mut a = GarblingInstanceImpl::new(seeded_config);
+        let mut b = GarblingInstanceImpl::new(explicit_config);
+        feed(&mut a, &gates, &mut hashers[0]);
+        feed(&mut b, &gates, &mut hashers[1]);
+        assert_eq!(hashers[0].finalize(), hashers[1].finalize());
+        assert_eq!(output_labels(&a), output_labels(&b));
+    }
+
+    #[test]
+    fn test_batched_and_gates_match_sequential() {
+        let input_labels = [[0xAA; 16], [0xBB; 16], [0xCC; 16], [0xDD; 16]];
+
+        // Levels of 7 gates alternate between reading the low half of working
+        // space and writing the high half, and back, so each level's gates
+        // are independent and one full batch plus a remainder
+        let half = SCRATCH_SPACE as usize / 2;
+        let levels: Vec<Vec<(usize, usize, usize)>> = (0..20usize)
+            .map(|level| {
+                let (src, dst) = if level % 2 == 0 { (0, half) } else { (half, 0) };
+                (0..7usize)
+                    .map(|i| {
+                        let in1 = src + (i * 3 + level) % half;
+                        let in2 = src + (i * 5 + 1) % half;
+                        (in1, in2, dst + (i + level) % half)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for scheme in [HashScheme::Ccrnd, HashScheme::Tmmo] {
+            let config = garbling_config(&input_labels, scheme);
+            let mut sequential = GarblingInstanceImpl::new(config);
+            let mut batched = GarblingInstanceImpl::new(config);
+
+            for gates in &levels {
+                let expected: Vec<[u8; 16]> = gates
+                    .iter()
+                    .map(|&(in1, in2, out)| sequential.feed_and_gate(in1, in2, out).into())
+                    .collect();
+                let actual: Vec<[u8; 16]> = batched
+                    .feed_and_gates(gates)
+                    .into_iter()
+                    .map(Into::into)
+                    .collect();
+                assert_eq!(actual, expected, "{:?}", scheme);
+                // An XOR between levels keeps gate indices interleaved
+                sequential.feed_xor_gate(2, 3, 1);
+                batched.feed_xor_gate(2, 3, 1);
+            }
+            assert_eq!(batched.gates_garbled(), sequential.gates_garbled());
+            assert_eq!(output_labels(&batched), output_labels(&sequential));
+        }
+    }
+
+    #[test]
+    fn test_streamed_ciphertext_hash_matches_buffered() {
+        let input_labels = [[0xAA; 16], [0xBB; 16], [0xCC; 16]];
+        let gates = test_gates();
+        let mut buffered =
+            GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd));
+        let mut streamed =
+            GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd))
+                .with_ciphertext_hash();
+
+        let mut ciphertexts = Vec::new();
+        for &(in1, in2, out, is_and) in &gates {
+            if is_and {
+                ciphertexts.push(buffered.feed_and_gate(in1, in2, out));
+                streamed.feed_and_gate(in1, in2, out);
+            } else {
+                buffered.feed_xor_gate(in1, in2, out);
+                streamed.feed_xor_gate(in1, in2, out);
+            }
+        }
+
+        // Output translation ciphertexts are part of the stream too
+        let targets = [[0x77; 16], [0x88; 16]];
+        ciphertexts.extend(buffered.translate_outputs(&[2, 3], &targets));
+        streamed.translate_outputs(&[2, 3], &targets);
+
+        let expected_labels = output_labels(&buffered);
+        let bytes: Vec<u8> = ciphertexts.into_iter().flat_map(<[u8; 16]>::from).collect();
+        let (labels, hash) = streamed.finish_with_hash();
+        assert_eq!(hash, *blake3::hash(&bytes).as_bytes());
+        let labels: Vec<[u8; 16]> = labels.iter().map(|l| l.to_bytes()).collect();
+        assert_eq!(labels, expected_labels);
+    }
+
+    #[test]
+    fn test_input_label_commitments_open_to_label_pairs() {
+        let input_labels = [[0xAA; 16], [0xBB; 16], [0xCC; 16]];
+        let instance = GarblingInstanceImpl::new(garbling_config(&input_labels, HashScheme::Ccrnd));
+        let commitments = instance.input_label_commitments();
+        assert_eq!(commitments.len(), input_labels.len());
+
+        let wires: Vec<u64> = (2..2 + input_labels.len() as u64).collect();
+        let pairs: Vec<[[u8; 16]; 2]> = wires
+            .iter()
+            .map(|&wire| {
+                let mut pair = [[0u8; 16]; 2];
+                for (value, label) in pair.iter_mut().enumerate() {
+                    let values = BitVec::repeat(value == 1, 1);
+                    instance.get_selected_labels(&[wire], &values, std::slice::from_mut(label));
+                }
+                pair
+            })
+            .collect();
+
+        for ((commitment, &wire), &pair) in commitments.iter().zip(&wires).zip(&pairs) {
+            assert!(open_commitment(commitment, wire, pair));
+
+            // A wrong label, swapped labels or another wire's ID all fail
+            let mut wrong = pair;
+            wrong[1][5] ^= 1;
+            assert!(!open_commitment(commitment, wire, wrong));
+            assert!(!open_commitment(commitment, wire, [pair[1], pair[0]]));
+            assert!(!open_commitment(commitment, wire + 1, pair));
+        }
+    }
 }
@@ -0,0 +1,366 @@
+//! On-disk container for a garbled circuit, so garbling and evaluation can
+//! run in separate processes.
+//!
+//! Layout, all integers little-endian:
+//!
+//! ```text
+//! magic "GBc5" | version u8 | 3 reserved zero bytes
+//! primary_inputs u64 | num_outputs u64 | num_ciphertexts u64
+//! input labels, 16 bytes x primary_inputs
+//! ciphertexts, 16 bytes x num_ciphertexts
+//! BLAKE3 of every preceding byte, 32 bytes
+//! ```
+//!
+//! The input labels are the ones the evaluator starts from, i.e. the
+//! garbler's labels selected under the evaluator's input values. Storing
+//! false labels instead would hand the evaluator both labels of every input
+//! set to 1, and with them the delta. Nothing else secret is written; the
+//! AES key, public S, constant labels and hash scheme are public and travel
+//! separately in the evaluator's config.
+
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use crate::types::Ciphertext;
+
+/// Magic bytes opening a garbled circuit file.
+pub const GARBLED_MAGIC: [u8; 4] = *b"GBc5";
+
+/// Current container version.
+pub const GARBLED_VERSION: u8 = 1;
+
+/// Size of the fixed header preceding the input labels.
+pub const GARBLED_HEADER_SIZE: usize = 32;
+
+/// Fixed-size header of a garbled circuit file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GarbledCircuitHeader {
+    /// Number of primary inputs, and so of input labels.
+    pub primary_inputs: u64,
+    /// Number of circuit outputs.
+    pub num_outputs: u64,
+    /// Number of ciphertexts that follow the input labels.
+    pub num_ciphertexts: u64,
+}
+
+impl GarbledCircuitHeader {
+    fn to_bytes(self) -> [u8; GARBLED_HEADER_SIZE] {
+        let mut bytes = [0u8; GARBLED_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&GARBLED_MAGIC);
+        bytes[4] = GARBLED_VERSION;
+        bytes[8..16].copy_from_slice(&self.primary_inputs.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.num_outputs.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.num_ciphertexts.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; GARBLED_HEADER_SIZE]) -> io::Result<Self> {
+        if bytes[0..4] != GARBLED_MAGIC {
+            return Err(invalid("not a garbled circuit (bad magic)".to_string()));
+        }
+        if bytes[4] != GARBLED_VERSION {
+            return Err(invalid(format!(
+                "unsupported garbled circuit version {}",
+                bytes[4]
+            )));
+        }
+        if bytes[5..8] != [0; 3] {
+            return Err(invalid("reserved header bytes are not zero".to_string()));
+        }
+        let read_u64 = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        Ok(Self {
+            primary_inputs: read_u64(8),
+            num_outputs: read_u64(16),
+            num_ciphertexts: read_u64(24),
+        })
+    }
+}
+
+/// Streams a garbled circuit to `W`, hashing as it goes.
+///
+/// Ciphertexts are written as the garbler produces them, so the circuit
+/// never needs to be held in memory. [`GarbledCircuitWriter::finish`]
+/// appends the checksum and fails if fewer or more ciphertexts than the
+/// header declared were written.
+#[derive(Debug)]
+pub struct GarbledCircuitWriter<W: Write> {
+    inner: W,
+    hasher: blake3::Hasher,
+    header: GarbledCircuitHeader,
+    written: u64,
+}
+
+impl<W: Write> GarbledCircuitWriter<W> {
+    /// Writes the header and the evaluator's input labels.
+    pub fn new(
+        inner: W,
+        header: GarbledCircuitHeader,
+        input_labels: &[[u8; 16]],
+    ) -> io::Result<Self> {
+        if input_labels.len() as u64 != header.primary_inputs {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} input labels given for {} primary inputs",
+                    input_labels.len(),
+                    header.primary_inputs
+                ),
+            ));
+        }
+
+        let mut writer = Self {
+            inner,
+            hasher: blake3::Hasher::new(),
+            header,
+            written: 0,
+        };
+        writer.write_hashed(&header.to_bytes())?;
+        for label in input_labels {
+            writer.write_hashed(label)?;
+        }
+        Ok(writer)
+    }
+
+    /// Appends the next ciphertext.
+    pub fn write_ciphertext(&mut self, ciphertext: Ciphertext) -> io::Result<()> {
+        if self.written == self.header.num_ciphertexts {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "header declares {} ciphertexts",
+                    self.header.num_ciphertexts
+                ),
+            ));
+        }
+        self.written += 1;
+        self.write_hashed(&<[u8; 16]>::from(ciphertext))
+    }
+
+    /// Writes the checksum, flushes, and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.written != self.header.num_ciphertexts {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} ciphertexts written but header declares {}",
+                    self.written, self.header.num_ciphertexts
+                ),
+            ));
+        }
+        let checksum = self.hasher.finalize();
+        self.inner.write_all(checksum.as_bytes())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_hashed(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.update(bytes);
+        self.inner.write_all(bytes)
+    }
+}
+
+/// Reads a garbled circuit written by [`GarbledCircuitWriter`].
+///
+/// The checksum trails the ciphertexts, so it is only checked once the last
+/// ciphertext has been read: [`GarbledCircuitReader::next_ciphertext`]
+/// returns `Ok(None)` only if it matches, and keeps returning `Ok(None)`
+/// after that.
+#[derive(Debug)]
+pub struct GarbledCircuitReader<R: Read> {
+    inner: R,
+    hasher: blake3::Hasher,
+    header: GarbledCircuitHeader,
+    input_labels: Vec<[u8; 16]>,
+    remaining: u64,
+    finished: bool,
+}
+
+impl<R: Read> GarbledCircuitReader<R> {
+    /// Reads and validates the header and the input labels.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut hasher = blake3::Hasher::new();
+        let mut header_bytes = [0u8; GARBLED_HEADER_SIZE];
+        inner.read_exact(&mut header_bytes)?;
+        hasher.update(&header_bytes);
+        let header = GarbledCircuitHeader::from_bytes(&header_bytes)?;
+
+        let mut input_labels = Vec::new();
+        for _ in 0..header.primary_inputs {
+            let mut label = [0u8; 16];
+            inner.read_exact(&mut label)?;
+            hasher.update(&label);
+            input_labels.push(label);
+        }
+
+        Ok(Self {
+            inner,
+            hasher,
+            header,
+            input_labels,
+            remaining: header.num_ciphertexts,
+            finished: false,
+        })
+    }
+
+    /// The file's header.
+    pub fn header(&self) -> &GarbledCircuitHeader {
+        &self.header
+    }
+
+    /// Labels the evaluator starts from, one per primary input.
+    pub fn input_labels(&self) -> &[[u8; 16]] {
+        &self.input_labels
+    }
+
+    /// Reads the next ciphertext, or verifies the checksum and returns
+    /// `None` once all of them have been read.
+    pub fn next_ciphertext(&mut self) -> io::Result<Option<Ciphertext>> {
+        if self.finished {
+            return Ok(None);
+        }
+        if self.remaining == 0 {
+            let mut stored = [0u8; 32];
+            self.inner.read_exact(&mut stored)?;
+            if stored != *self.hasher.finalize().as_bytes() {
+                return Err(invalid("garbled circuit checksum mismatch".to_string()));
+            }
+            self.finished = true;
+            return Ok(None);
+        }
+
+        let mut bytes = [0u8; 16];
+        self.inner.read_exact(&mut bytes)?;
+        self.hasher.update(&bytes);
+        self.remaining -= 1;
+        Ok(Some(Ciphertext::from(bytes)))
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitvec::vec::BitVec;
+
+    use super::*;
+    use crate::hash::HashScheme;
+    use crate::test_util::{SCRATCH_SPACE, evaluation_config, garbling_config, test_gates};
+    use crate::traits::{EvaluationInstance, GarblingInstance};
+    use crate::{EvaluationInstance as Evaluator, GarblingInstance as Garbler};
+
+    const NUM_INPUTS: usize = 4;
+
+    /// Cleartext values of every wire after running [`test_gates`].
+    fn cleartext(inputs: &BitVec) -> Vec<bool> {
+        let mut wires = vec![false; SCRATCH_SPACE as usize];
+        wires[1] = true;
+        for i in 0..NUM_INPUTS {
+            wires[2 + i] = inputs[i];
+        }
+        for (in1, in2, out, is_and) in test_gates() {
+            wires[out] = if is_and {
+                wires[in1] & wires[in2]
+            } else {
+                wires[in1] ^ wires[in2]
+            };
+        }
+        wires
+    }
+
+    fn write_garbled(inputs: &BitVec) -> Vec<u8> {
+        let false_labels: Vec<[u8; 16]> = (0..NUM_INPUTS as u8).map(|i| [0xA0 + i; 16]).collect();
+        let mut garbler = Garbler::new(garbling_config(&false_labels, HashScheme::Ccrnd));
+
+        let input_wires: Vec<u64> = (2..2 + NUM_INPUTS as u64).collect();
+        let mut selected = vec![[0u8; 16]; NUM_INPUTS];
+        garbler.get_selected_labels(&input_wires, inputs, &mut selected);
+
+        let header = GarbledCircuitHeader {
+            primary_inputs: NUM_INPUTS as u64,
+            num_outputs: SCRATCH_SPACE as u64,
+            num_ciphertexts: test_gates().iter().filter(|g| g.3).count() as u64,
+        };
+        let mut writer = GarbledCircuitWriter::new(Vec::new(), header, &selected).unwrap();
+        for (in1, in2, out, is_and) in test_gates() {
+            if is_and {
+                let ct = garbler.feed_and_gate(in1, in2, out);
+                writer.write_ciphertext(ct).unwrap();
+            } else {
+                garbler.feed_xor_gate(in1, in2, out);
+            }
+        }
+        writer.finish().unwrap()
+    }
+
+    fn evaluate(bytes: &[u8], inputs: &BitVec) -> io::Result<Vec<bool>> {
+        let mut reader = GarbledCircuitReader::new(bytes)?;
+        let mut evaluator = Evaluator::new(evaluation_config(
+            reader.input_labels(),
+            inputs,
+            HashScheme::Ccrnd,
+        ));
+
+        for (in1, in2, out, is_and) in test_gates() {
+            if is_and {
+                let ct = reader.next_ciphertext()?.expect("ciphertext per AND gate");
+                evaluator.feed_and_gate(in1, in2, out, ct);
+            } else {
+                evaluator.feed_xor_gate(in1, in2, out);
+            }
+        }
+        assert!(reader.next_ciphertext()?.is_none());
+        // Fused: the checksum was consumed, so nothing more is read
+        assert!(reader.next_ciphertext()?.is_none());
+
+        let wires: Vec<u64> = (0..SCRATCH_SPACE as u64).collect();
+        let mut values = vec![false; wires.len()];
+        evaluator.get_values(&wires, &mut values);
+        Ok(values)
+    }
+
+    #[test]
+    fn test_garble_write_read_eval_round_trip() {
+        for bits in 0..1u8 << NUM_INPUTS {
+            let inputs: BitVec = (0..NUM_INPUTS).map(|i| (bits >> i) & 1 == 1).collect();
+            let bytes = write_garbled(&inputs);
+            assert_eq!(
+                evaluate(&bytes, &inputs).unwrap(),
+                cleartext(&inputs),
+                "inputs {:04b}",
+                bits
+            );
+        }
+    }
+
+    #[test]
+    fn test_reader_detects_corruption() {
+        let inputs: BitVec = (0..NUM_INPUTS).map(|i| i % 2 == 0).collect();
+        let mut bytes = write_garbled(&inputs);
+
+        // Flip a bit in the first ciphertext
+        bytes[GARBLED_HEADER_SIZE + 16 * NUM_INPUTS] ^= 1;
+        let err = evaluate(&bytes, &inputs).unwrap_err();
+        assert_eq!(err.to_string(), "garbled circuit checksum mismatch");
+
+        bytes[0] = b'X';
+        let err = GarbledCircuitReader::new(&bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_writer_enforces_declared_ciphertext_count() {
+        let header = GarbledCircuitHeader {
+            primary_inputs: 0,
+            num_outputs: 1,
+            num_ciphertexts: 2,
+        };
+        let mut writer = GarbledCircuitWriter::new(Vec::new(), header, &[]).unwrap();
+        writer.write_ciphertext(Ciphertext::default()).unwrap();
+        let err = writer.finish().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 ciphertexts written but header declares 2"
+        );
+    }
+}
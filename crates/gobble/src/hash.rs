@@ -0,0 +1,323 @@
+//! Selection of the correlation-robust hash used for AND gates.
+//!
+//! [`HashScheme::Ccrnd`] costs one AES call per hash but adds a linear
+//! orthomorphism, while [`HashScheme::Tmmo`] costs two AES calls and nothing
+//! else. Which is faster depends on the microarchitecture's AES throughput
+//! versus its shuffle latency, so [`autotune_hash_scheme`] measures both.
+//!
+//! Garbler and evaluator must use the same scheme. The garbler picks one,
+//! passes it in [`GarblingInstanceConfig`](crate::traits::GarblingInstanceConfig),
+//! and sends it to the evaluator next to the AES key and public S.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::scalar;
+use crate::types::{Inner, Label};
+
+#[cfg(target_arch = "aarch64")]
+use crate::aarch64::{
+    ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, tmmo_with_round_keys,
+};
+
+#[cfg(target_arch = "x86_64")]
+use crate::x86_64::{
+    ccrnd_with_round_keys, expand_aes128_key, index_to_tweak, tmmo_with_round_keys,
+};
+
+#[cfg(target_arch = "x86_64")]
+use crate::x86_64::{ccrnd_many_with_round_keys, tmmo_many_with_round_keys};
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+use crate::scalar::index_to_tweak;
+
+/// AES implementation behind the hashes, chosen when an instance is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AesBackend {
+    /// AES-NI on x86_64, or the AES extension on aarch64.
+    Hardware,
+    /// The software AES in [`crate::scalar`]: correct but much slower, and
+    /// not constant time.
+    Scalar,
+}
+
+impl AesBackend {
+    /// The hardware backend if the running CPU has AES instructions,
+    /// otherwise the scalar one.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("aes") {
+            return AesBackend::Hardware;
+        }
+        #[cfg(target_arch = "aarch64")]
+        if std::arch::is_aarch64_feature_detected!("aes") {
+            return AesBackend::Hardware;
+        }
+        AesBackend::Scalar
+    }
+}
+
+/// An expanded AES-128 key for the backend that hashes with it.
+///
+/// Hardware keys are only built for [`AesBackend::Hardware`], which
+/// [`AesBackend::detect`] only returns on a CPU with AES instructions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RoundKeys {
+    #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+    Hardware([Inner; 11]),
+    Scalar(scalar::Aes128RoundKeys),
+}
+
+impl RoundKeys {
+    /// Expands `key` for `backend`. Without hardware AES in the build, the
+    /// scalar backend is used either way.
+    pub(crate) fn new(backend: AesBackend, key: &[u8; 16]) -> Self {
+        match backend {
+            #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+            AesBackend::Hardware => RoundKeys::Hardware(unsafe { expand_aes128_key(key) }),
+            _ => RoundKeys::Scalar(unsafe { scalar::expand_aes128_key(key) }),
+        }
+    }
+}
+
+/// Hash function used to garble and evaluate AND gates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HashScheme {
+    /// CCRND: one AES call plus a linear orthomorphism.
+    #[default]
+    Ccrnd,
+    /// TMMO: two AES calls.
+    Tmmo,
+}
+
+impl HashScheme {
+    /// AES block encryptions per hash call.
+    pub fn aes_calls(self) -> u64 {
+        match self {
+            HashScheme::Ccrnd => 1,
+            HashScheme::Tmmo => 2,
+        }
+    }
+
+    /// Encodes the scheme as a single byte for sending to the evaluator.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            HashScheme::Ccrnd => 0,
+            HashScheme::Tmmo => 1,
+        }
+    }
+
+    /// Decodes a byte produced by [`HashScheme::to_byte`].
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(HashScheme::Ccrnd),
+            1 => Some(HashScheme::Tmmo),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of [`autotune_hash_scheme`].
+#[derive(Debug, Clone, Copy)]
+pub struct AutotuneReport {
+    /// The faster scheme.
+    pub scheme: HashScheme,
+    /// Time taken by the CCRND run.
+    pub ccrnd: Duration,
+    /// Time taken by the TMMO run.
+    pub tmmo: Duration,
+}
+
+/// Number of hashes per scheme used by [`HashScheme::autotune`].
+pub const DEFAULT_AUTOTUNE_ITERATIONS: u64 = 1 << 16;
+
+impl HashScheme {
+    /// Microbenchmarks both schemes and returns the faster one.
+    ///
+    /// Takes well under a millisecond on AES-capable CPUs, so it is cheap
+    /// enough to run once at garbler startup.
+    pub fn autotune() -> Self {
+        autotune_hash_scheme(DEFAULT_AUTOTUNE_ITERATIONS).scheme
+    }
+}
+
+/// Times `iterations` chained hashes with each scheme.
+///
+/// Each hash feeds the next so the measurement reflects latency as in the
+/// garbler's sequential gate loop. Ties go to CCRND.
+pub fn autotune_hash_scheme(iterations: u64) -> AutotuneReport {
+    let round_keys = RoundKeys::new(AesBackend::detect(), &[0x5A; 16]);
+    let public_s = crate::Label::from([0xA5; 16]).0;
+
+    let time = |scheme: HashScheme| {
+        let mut acc = crate::Label::from([0x3C; 16]).0;
+        let start = Instant::now();
+        for i in 0..iterations {
+            let t = unsafe { index_to_tweak(i) };
+            acc = unsafe { hash_with_round_keys(scheme, acc, t, &round_keys, public_s) };
+        }
+        black_box(acc);
+        start.elapsed()
+    };
+
+    // Warm up both paths before measuring
+    time(HashScheme::Ccrnd);
+    time(HashScheme::Tmmo);
+    let ccrnd = time(HashScheme::Ccrnd);
+    let tmmo = time(HashScheme::Tmmo);
+
+    AutotuneReport {
+        scheme: if tmmo < ccrnd {
+            HashScheme::Tmmo
+        } else {
+            HashScheme::Ccrnd
+        },
+        ccrnd,
+        tmmo,
+    }
+}
+
+/// Hashes `x` under tweak `tweak` with the given scheme.
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON/SSE2. AES support follows
+/// from how `round_keys` was built.
+#[inline]
+pub(crate) unsafe fn hash_with_round_keys(
+    scheme: HashScheme,
+    x: Inner,
+    tweak: Inner,
+    round_keys: &RoundKeys,
+    public_s: Inner,
+) -> Inner {
+    match round_keys {
+        #[cfg(any(target_arch = "aarch64", target_arch = "x86_64"))]
+        RoundKeys::Hardware(keys) => match scheme {
+            HashScheme::Ccrnd => unsafe { ccrnd_with_round_keys(x, tweak, keys, public_s) },
+            HashScheme::Tmmo => unsafe { tmmo_with_round_keys(x, tweak, keys) },
+        },
+        RoundKeys::Scalar(keys) => {
+            let (x, tweak) = (Label(x).to_bytes(), Label(tweak).to_bytes());
+            let hash = match scheme {
+                HashScheme::Ccrnd => unsafe {
+                    scalar::ccrnd_with_round_keys(x, tweak, keys, Label(public_s).to_bytes())
+                },
+                HashScheme::Tmmo => unsafe { scalar::tmmo_with_round_keys(x, tweak, keys) },
+            };
+            Label::from(hash).0
+        }
+    }
+}
+
+/// Hashes `N` independent inputs, each under its own tweak.
+///
+/// With hardware AES on x86_64 the AES calls of all lanes are interleaved,
+/// otherwise this is [`hash_with_round_keys`] once per lane.
+///
+/// # Safety
+///
+/// The caller must ensure the CPU supports NEON/SSE2. AES support follows
+/// from how `round_keys` was built.
+#[inline]
+pub(crate) unsafe fn hash_many_with_round_keys<const N: usize>(
+    scheme: HashScheme,
+    xs: [Inner; N],
+    tweaks: [Inner; N],
+    round_keys: &RoundKeys,
+    public_s: Inner,
+) -> [Inner; N] {
+    match round_keys {
+        #[cfg(target_arch = "x86_64")]
+        RoundKeys::Hardware(keys) => match scheme {
+            HashScheme::Ccrnd => unsafe { ccrnd_many_with_round_keys(xs, tweaks, keys, public_s) },
+            HashScheme::Tmmo => unsafe { tmmo_many_with_round_keys(xs, tweaks, keys) },
+        },
+        _ => std::array::from_fn(|i| unsafe {
+            hash_with_round_keys(scheme, xs[i], tweaks[i], round_keys, public_s)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitvec::vec::BitVec;
+
+    use super::*;
+    use crate::test_util::{SCRATCH_SPACE, evaluation_config, garbling_config, test_gates};
+    use crate::traits::{EvaluationInstance, GarblingInstance};
+    use crate::{EvaluationInstance as Evaluator, GarblingInstance as Garbler};
+
+    const NUM_INPUTS: usize = 4;
+
+    /// Garbles and evaluates, returning (garbler labels, evaluator labels)
+    /// for every wire under the evaluator's values.
+    fn round_trip(scheme: HashScheme, inputs: &BitVec) -> (Vec<[u8; 16]>, Vec<[u8; 16]>) {
+        let false_labels: Vec<[u8; 16]> = (0..NUM_INPUTS as u8).map(|i| [0xA0 + i; 16]).collect();
+        let mut garbler = Garbler::new(garbling_config(&false_labels, scheme));
+        assert_eq!(garbler.hash_scheme(), scheme);
+
+        let input_wires: Vec<u64> = (2..2 + NUM_INPUTS as u64).collect();
+        let mut selected = vec![[0u8; 16]; NUM_INPUTS];
+        garbler.get_selected_labels(&input_wires, inputs, &mut selected);
+
+        // The evaluator is told the scheme the garbler used
+        let mut evaluator = Evaluator::new(evaluation_config(
+            &selected,
+            inputs,
+            HashScheme::from_byte(garbler.hash_scheme().to_byte()).unwrap(),
+        ));
+
+        for (in1, in2, out, is_and) in test_gates() {
+            if is_and {
+                let ct = garbler.feed_and_gate(in1, in2, out);
+                evaluator.feed_and_gate(in1, in2, out, ct);
+            } else {
+                garbler.feed_xor_gate(in1, in2, out);
+                evaluator.feed_xor_gate(in1, in2, out);
+            }
+        }
+
+        let wires: Vec<u64> = (0..SCRATCH_SPACE as u64).collect();
+        let mut values = vec![false; wires.len()];
+        evaluator.get_values(&wires, &mut values);
+        let values: BitVec = values.into_iter().collect();
+
+        let mut expected = vec![[0u8; 16]; wires.len()];
+        garbler.get_selected_labels(&wires, &values, &mut expected);
+        let mut actual = vec![[0u8; 16]; wires.len()];
+        evaluator.get_labels(&wires, &mut actual);
+        (expected, actual)
+    }
+
+    #[test]
+    fn test_autotuned_scheme_round_trips() {
+        let report = autotune_hash_scheme(1 << 10);
+        assert!(report.scheme == HashScheme::Ccrnd || report.ccrnd > report.tmmo);
+
+        // Whichever scheme wins, garbling and evaluation must agree
+        for scheme in [HashScheme::autotune(), HashScheme::Ccrnd, HashScheme::Tmmo] {
+            for bits in 0..1u8 << NUM_INPUTS {
+                let inputs: BitVec = (0..NUM_INPUTS).map(|i| (bits >> i) & 1 == 1).collect();
+                let (expected, actual) = round_trip(scheme, &inputs);
+                assert_eq!(expected, actual, "{:?} with inputs {:04b}", scheme, bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_schemes_produce_different_labels() {
+        let inputs: BitVec = (0..NUM_INPUTS).map(|i| i % 2 == 0).collect();
+        let (ccrnd, _) = round_trip(HashScheme::Ccrnd, &inputs);
+        let (tmmo, _) = round_trip(HashScheme::Tmmo, &inputs);
+        assert_ne!(ccrnd, tmmo);
+    }
+
+    #[test]
+    fn test_scheme_byte_round_trip() {
+        for scheme in [HashScheme::Ccrnd, HashScheme::Tmmo] {
+            assert_eq!(HashScheme::from_byte(scheme.to_byte()), Some(scheme));
+        }
+        assert_eq!(HashScheme::from_byte(2), None);
+    }
+}
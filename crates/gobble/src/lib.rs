@@ -1,5 +1,6 @@
 //! Core crate for garbling, executing and evaluating garbled/boolean circuits.
 
+pub mod hash;
 pub mod traits;
 pub mod types;
 
@@ -14,16 +15,28 @@ pub mod aarch64;
 #[cfg(target_arch = "x86_64")]
 pub mod x86_64;
 
+// Portable fallback, used on other targets and on CPUs without AES
+pub mod scalar;
+
 // Unified implementations (architecture-independent logic)
 pub mod eval;
 pub mod exec;
 pub mod garb;
 
+// Garbled circuit container
+pub mod garbled;
+
+#[cfg(test)]
+mod test_util;
+
 // Re-export types at crate root
 pub use eval::EvaluationInstanceImpl as EvaluationInstance;
 pub use exec::CleartextExecutionInstance as ExecutionInstance;
 pub use garb::GarblingInstanceImpl as GarblingInstance;
-pub use types::{Ciphertext, Label, expand_seed, xor_bytes};
+pub use garb::{CheckpointError, GarblingCheckpoint, open_commitment};
+pub use garbled::{GarbledCircuitHeader, GarbledCircuitReader, GarbledCircuitWriter};
+pub use hash::{AesBackend, AutotuneReport, HashScheme, autotune_hash_scheme};
+pub use types::{Ciphertext, Label, accumulate, encode, expand_seed, xor_bytes};
 
 // Re-export input translation types and functions
 pub use input_translate::{
@@ -37,6 +50,7 @@ pub use output_translate::{
     generate_output_translation_material, translate_output, wide_hash_2x,
 };
 
+use bitvec::vec::BitVec;
 use traits::{
     EvaluationInstanceConfig, ExecutionInstanceConfig, GarblingInstanceConfig, GobbleEngine,
 };
@@ -46,23 +60,40 @@ use traits::{
 /// This is the main entry point for using the gobble crate. It implements
 /// the [`GobbleEngine`] trait and provides factory methods for creating
 /// garbling, execution, and evaluation instances.
+///
+/// [`GobbleEngine::new`] picks the AES backend at runtime: AES-NI on x86_64
+/// or the AES extension on aarch64 when the CPU has them, and otherwise the
+/// software AES of [`scalar::ScalarGobbleEngine`]. Both backends produce the
+/// same labels and ciphertexts, so a garbler and an evaluator may differ.
 #[derive(Debug)]
-pub struct Engine;
+pub struct Engine {
+    backend: AesBackend,
+}
+
+impl Engine {
+    /// The AES backend this engine's instances hash with.
+    pub fn backend(&self) -> AesBackend {
+        self.backend
+    }
+}
 
 impl GobbleEngine for Engine {
     fn new() -> Self {
-        Self
+        Self {
+            backend: AesBackend::detect(),
+        }
     }
 
     type GarblingInstance = GarblingInstance;
     type EvaluationInstance = EvaluationInstance;
     type ExecutionInstance = ExecutionInstance;
+    type Label = Label;
 
     fn new_garbling_instance<'labels>(
         &self,
         config: GarblingInstanceConfig<'labels>,
     ) -> Self::GarblingInstance {
-        GarblingInstance::new(config)
+        GarblingInstance::with_backend(config, self.backend)
     }
 
     fn new_execution_instance<'values>(
@@ -76,7 +107,15 @@ impl GobbleEngine for Engine {
         &self,
         config: EvaluationInstanceConfig<'labels>,
     ) -> Self::EvaluationInstance {
-        EvaluationInstance::new(config)
+        EvaluationInstance::with_backend(config, self.backend)
+    }
+
+    fn expand_seed(&self, seed: [u8; 32], num_inputs: u32) -> (Vec<Label>, Label) {
+        expand_seed(seed, num_inputs)
+    }
+
+    fn encode(&self, input: &BitVec, false_labels: &[Label], delta: Label) -> Vec<Label> {
+        encode(input, false_labels, delta)
     }
 }
 
@@ -84,4 +123,143 @@ impl GobbleEngine for Engine {
 mod tests {
     // Fixes a compiler warning
     use criterion as _;
+
+    use super::*;
+    use crate::scalar::ScalarGobbleEngine;
+    use crate::test_util::{SCRATCH_SPACE, evaluation_config, garbling_config, test_gates};
+    use crate::traits::{EvaluationInstance as _, GarblingInstance as _};
+
+    /// Garbler-side input encoding written only against the trait.
+    fn encode_inputs<E: GobbleEngine>(
+        engine: &E,
+        seed: [u8; 32],
+        input: &BitVec,
+    ) -> (Vec<E::Label>, E::Label, Vec<E::Label>) {
+        let (false_labels, delta) = engine.expand_seed(seed, input.len() as u32);
+        let selected = engine.encode(input, &false_labels, delta);
+        (false_labels, delta, selected)
+    }
+
+    #[test]
+    fn test_engine_encodes_inputs_like_the_garbler() {
+        let engine = Engine::new();
+        let input: BitVec = [true, false, false, true, true].into_iter().collect();
+        let (false_labels, delta, selected) = encode_inputs(&engine, [7; 32], &input);
+
+        // Same seed, same labels
+        let (again, _) = engine.expand_seed([7; 32], input.len() as u32);
+        let bytes = |labels: &[Label]| {
+            labels
+                .iter()
+                .map(|&l| <[u8; 16]>::from(l))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bytes(&again), bytes(&false_labels));
+
+        let delta_bytes: [u8; 16] = delta.into();
+        for (i, (bit, label)) in input.iter().zip(&selected).enumerate() {
+            let expected = if *bit {
+                xor_bytes(false_labels[i].into(), delta_bytes)
+            } else {
+                false_labels[i].into()
+            };
+            assert_eq!(<[u8; 16]>::from(*label), expected, "input {}", i);
+        }
+
+        // A garbler built from the same labels selects the same ones. It
+        // forces the point-and-permute bits, so normalize them first.
+        let mut delta_bytes = delta_bytes;
+        delta_bytes[0] |= 1;
+        let false_bytes: Vec<[u8; 16]> = bytes(&false_labels)
+            .into_iter()
+            .map(|mut l| {
+                l[0] &= !1;
+                l
+            })
+            .collect();
+        let garbler = engine.new_garbling_instance(GarblingInstanceConfig {
+            scratch_space: 16,
+            delta: delta_bytes,
+            primary_input_false_labels: &false_bytes,
+            aes128_key: [0x22; 16],
+            public_s: [0x33; 16],
+            constant_zero_label: [0x44; 16],
+            constant_one_label: [0x55; 16],
+            hash_scheme: HashScheme::default(),
+        });
+        let normalized: Vec<Label> = false_bytes.iter().map(|&l| Label::from(l)).collect();
+        let selected = engine.encode(&input, &normalized, Label::from(delta_bytes));
+        let wires: Vec<u64> = (2..2 + input.len() as u64).collect();
+        let mut from_garbler = vec![[0u8; 16]; wires.len()];
+        garbler.get_selected_labels(&wires, &input, &mut from_garbler);
+        assert_eq!(bytes(&selected), from_garbler);
+    }
+
+    #[test]
+    fn test_scalar_engine_matches_engine() {
+        // Engine hashes with hardware AES on CPUs that have it, so this
+        // compares the two backends; without it both are scalar
+        let engine = Engine::new();
+        assert_eq!(engine.backend(), AesBackend::detect());
+        let scalar = ScalarGobbleEngine::new();
+
+        let false_labels: Vec<[u8; 16]> = (0..4u8).map(|i| [0xA0 + i; 16]).collect();
+        let inputs: BitVec = [true, false, true, true].into_iter().collect();
+        let input_wires: Vec<u64> = (2..6).collect();
+        let wires: Vec<u64> = (0..SCRATCH_SPACE as u64).collect();
+
+        for scheme in [HashScheme::Ccrnd, HashScheme::Tmmo] {
+            let config = garbling_config(&false_labels, scheme);
+            let mut garblers = [
+                engine.new_garbling_instance(config),
+                scalar.new_garbling_instance(config),
+            ];
+            let mut selected = vec![[0u8; 16]; input_wires.len()];
+            garblers[0].get_selected_labels(&input_wires, &inputs, &mut selected);
+
+            let mut ciphertexts: [Vec<Ciphertext>; 2] = Default::default();
+            for (in1, in2, out, is_and) in test_gates() {
+                for (garbler, cts) in garblers.iter_mut().zip(&mut ciphertexts) {
+                    if is_and {
+                        cts.push(garbler.feed_and_gate(in1, in2, out));
+                    } else {
+                        garbler.feed_xor_gate(in1, in2, out);
+                    }
+                }
+            }
+            assert_eq!(ciphertexts[0], ciphertexts[1], "{:?}", scheme);
+
+            // Each engine's evaluator decodes the other's garbling
+            let config = evaluation_config(&selected, &inputs, scheme);
+            let mut evaluators = [
+                scalar.new_evaluation_instance(config),
+                engine.new_evaluation_instance(config),
+            ];
+            for (evaluator, cts) in evaluators.iter_mut().zip(&ciphertexts) {
+                let mut cts = cts.iter();
+                for (in1, in2, out, is_and) in test_gates() {
+                    if is_and {
+                        evaluator.feed_and_gate(in1, in2, out, *cts.next().unwrap());
+                    } else {
+                        evaluator.feed_xor_gate(in1, in2, out);
+                    }
+                }
+            }
+
+            for evaluator in &evaluators {
+                let mut values = vec![false; wires.len()];
+                evaluator.get_values(&wires, &mut values);
+                let values: BitVec = values.into_iter().collect();
+
+                let mut expected = [vec![[0u8; 16]; wires.len()], vec![[0u8; 16]; wires.len()]];
+                for (garbler, labels) in garblers.iter().zip(&mut expected) {
+                    garbler.get_selected_labels(&wires, &values, labels);
+                }
+                assert_eq!(expected[0], expected[1], "{:?}", scheme);
+                let mut actual = vec![[0u8; 16]; wires.len()];
+                evaluator.get_labels(&wires, &mut actual);
+                assert_eq!(actual, expected[0], "{:?}", scheme);
+            }
+        }
+    }
 }
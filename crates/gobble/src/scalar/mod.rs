@@ -0,0 +1,359 @@
+//! Portable fallback for targets without AES and SIMD intrinsics.
+//!
+//! Mirrors the `aarch64` and `x86_64` modules function for function, but on
+//! plain `[u8; 16]` blocks with a table-based software AES. It is much slower
+//! than the hardware paths and is not constant time (the S-box lookups are
+//! data dependent), so it exists to keep the crate building and correct on
+//! other targets rather than for production garbling.
+//!
+//! On other targets it replaces the intrinsic modules at compile time. On
+//! x86_64 and aarch64 its AES is picked at runtime when the CPU lacks AES
+//! instructions, see [`AesBackend::detect`], and [`ScalarGobbleEngine`]
+//! forces it regardless.
+//!
+//! The functions stay `unsafe fn` so the shared garbling and evaluation code
+//! calls them exactly as it calls the intrinsic versions.
+
+mod expand;
+
+use bitvec::vec::BitVec;
+use expand::{SBOX, expand_key};
+
+use crate::hash::AesBackend;
+use crate::traits::{
+    EvaluationInstanceConfig, ExecutionInstanceConfig, GarblingInstanceConfig, GobbleEngine,
+};
+use crate::{EvaluationInstance, ExecutionInstance, GarblingInstance};
+
+/// AES-128 round keys type.
+pub type Aes128RoundKeys = expand::Aes128RoundKeys;
+
+// Re-export the unified types
+pub use crate::types::{Ciphertext, Label};
+
+/// AES-128 key expansion in software.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+pub unsafe fn expand_aes128_key(key: &[u8; 16]) -> Aes128RoundKeys {
+    expand_key(key)
+}
+
+/// Extract the point-and-permute bit (LSB) from a label.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+#[inline]
+pub unsafe fn get_permute_bit(label: [u8; 16]) -> bool {
+    (label[0] & 1) == 1
+}
+
+/// Return `label` with its point-and-permute bit (LSB) set to `bit`.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+#[inline]
+pub unsafe fn with_permute_bit(mut label: [u8; 16], bit: bool) -> [u8; 16] {
+    label[0] = (label[0] & !1) | bit as u8;
+    label
+}
+
+/// Hint that the cache line holding `ptr` will be read soon.
+///
+/// # Safety
+///
+/// Always safe to call; a no-op without an intrinsic to issue.
+#[inline]
+pub unsafe fn prefetch_read<T>(_ptr: *const T) {}
+
+/// XOR two 128-bit values.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+#[inline]
+pub unsafe fn xor128(a: [u8; 16], b: [u8; 16]) -> [u8; 16] {
+    crate::types::xor_bytes(a, b)
+}
+
+/// Convert gate index to tweak value.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+#[inline]
+pub unsafe fn index_to_tweak(index: u64) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    bytes[0..8].copy_from_slice(&index.to_le_bytes());
+    bytes
+}
+
+/// Multiply by `x` in GF(2^8) modulo the AES polynomial.
+#[inline]
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// SubBytes and ShiftRows on a column-major state.
+#[inline]
+fn sub_shift(state: &[u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[4 * col + row] = SBOX[state[4 * ((col + row) % 4) + row] as usize];
+        }
+    }
+    out
+}
+
+/// MixColumns on a column-major state.
+#[inline]
+fn mix_columns(state: &mut [u8; 16]) {
+    for col in state.chunks_exact_mut(4) {
+        let [a0, a1, a2, a3] = [col[0], col[1], col[2], col[3]];
+        let all = a0 ^ a1 ^ a2 ^ a3;
+        col[0] ^= all ^ xtime(a0 ^ a1);
+        col[1] ^= all ^ xtime(a1 ^ a2);
+        col[2] ^= all ^ xtime(a2 ^ a3);
+        col[3] ^= all ^ xtime(a3 ^ a0);
+    }
+}
+
+/// AES-128 encryption in software using caller-provided round keys.
+///
+/// # Safety
+///
+/// The caller must ensure that the `round_keys` are a valid AES-128 key
+/// schedule; otherwise this is always safe to call.
+pub unsafe fn aes_encrypt_with_round_keys(
+    block: [u8; 16],
+    round_keys: &Aes128RoundKeys,
+) -> [u8; 16] {
+    // Initial whitening: XOR with first round key
+    let mut state = crate::types::xor_bytes(block, round_keys[0]);
+
+    // Rounds 1-9: Full rounds with MixColumns
+    for key in &round_keys[1..10] {
+        state = sub_shift(&state);
+        mix_columns(&mut state);
+        state = crate::types::xor_bytes(state, *key);
+    }
+
+    // Round 10: Final round without MixColumns
+    crate::types::xor_bytes(sub_shift(&state), round_keys[10])
+}
+
+/// Linear orthomorphism: L || R -> (L ⊕ R) || L, taken from <https://eprint.iacr.org/2019/074.pdf> Section 7.3
+///
+/// Byte-for-byte the same as the intrinsic versions: with `lo` the first
+/// eight bytes and `hi` the last eight, the result is `(lo ⊕ hi) || lo`.
+///
+/// # Safety
+///
+/// Always safe to call; `unsafe` only to match the intrinsic modules.
+#[inline]
+pub unsafe fn sigma(x: [u8; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    for i in 0..8 {
+        out[i] = x[i] ^ x[i + 8];
+        out[i + 8] = x[i];
+    }
+    out
+}
+
+/// CCRND hash function using caller-provided round keys and public S.
+///
+/// This is the CCRND hash function from Section 5 of <https://eprint.iacr.org/2019/074.pdf>,
+/// using one AES call and one linear orthomorphism.
+///
+/// # Safety
+///
+/// The caller must ensure that the `round_keys` are a valid AES-128 key
+/// schedule.
+#[inline]
+pub unsafe fn ccrnd_with_round_keys(
+    x: [u8; 16],
+    tweak: [u8; 16],
+    round_keys: &Aes128RoundKeys,
+    public_s: [u8; 16],
+) -> [u8; 16] {
+    unsafe {
+        let lin_orth_input = sigma(xor128(xor128(x, public_s), tweak));
+        xor128(
+            aes_encrypt_with_round_keys(lin_orth_input, round_keys),
+            lin_orth_input,
+        )
+    }
+}
+
+/// TMMO hash function using caller-provided round keys.
+///
+/// This is the tweakable MMO construction `π(π(x) ⊕ t) ⊕ π(x)` from
+/// <https://eprint.iacr.org/2019/074.pdf>, using two AES calls.
+///
+/// # Safety
+///
+/// The caller must ensure that the `round_keys` are a valid AES-128 key
+/// schedule.
+#[inline]
+pub unsafe fn tmmo_with_round_keys(
+    x: [u8; 16],
+    tweak: [u8; 16],
+    round_keys: &Aes128RoundKeys,
+) -> [u8; 16] {
+    unsafe {
+        let pi_x = aes_encrypt_with_round_keys(x, round_keys);
+        xor128(
+            aes_encrypt_with_round_keys(xor128(pi_x, tweak), round_keys),
+            pi_x,
+        )
+    }
+}
+
+/// Engine that always hashes with the software AES, whatever the CPU.
+///
+/// [`crate::Engine`] falls back to the same backend on CPUs without AES
+/// instructions. Its instances are the usual ones and produce the same
+/// labels and ciphertexts, only slower.
+#[derive(Debug, Default)]
+pub struct ScalarGobbleEngine;
+
+impl GobbleEngine for ScalarGobbleEngine {
+    fn new() -> Self {
+        Self
+    }
+
+    type GarblingInstance = GarblingInstance;
+    type EvaluationInstance = EvaluationInstance;
+    type ExecutionInstance = ExecutionInstance;
+    type Label = Label;
+
+    fn new_garbling_instance<'labels>(
+        &self,
+        config: GarblingInstanceConfig<'labels>,
+    ) -> Self::GarblingInstance {
+        GarblingInstance::with_backend(config, AesBackend::Scalar)
+    }
+
+    fn new_execution_instance<'values>(
+        &self,
+        config: ExecutionInstanceConfig<'values>,
+    ) -> Self::ExecutionInstance {
+        ExecutionInstance::new(config)
+    }
+
+    fn new_evaluation_instance<'labels>(
+        &self,
+        config: EvaluationInstanceConfig<'labels>,
+    ) -> Self::EvaluationInstance {
+        EvaluationInstance::with_backend(config, AesBackend::Scalar)
+    }
+
+    fn expand_seed(&self, seed: [u8; 32], num_inputs: u32) -> (Vec<Label>, Label) {
+        crate::expand_seed(seed, num_inputs)
+    }
+
+    fn encode(&self, input: &BitVec, false_labels: &[Label], delta: Label) -> Vec<Label> {
+        crate::encode(input, false_labels, delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::Aes128;
+    use aes::cipher::{BlockCipherEncrypt, KeyInit};
+    use rand::Rng;
+
+    #[test]
+    fn test_aes_encrypt_with_round_keys() {
+        let num_tests = 1000;
+        for i in 0..num_tests {
+            let mut key_bytes = [0u8; 16];
+            let mut plaintext = [0u8; 16];
+            let mut rng = rand::rng();
+            rng.fill_bytes(&mut key_bytes);
+            rng.fill_bytes(&mut plaintext);
+
+            let round_keys = unsafe { expand_aes128_key(&key_bytes) };
+            let ciphertext = unsafe { aes_encrypt_with_round_keys(plaintext, &round_keys) };
+
+            let cipher = Aes128::new(&key_bytes.into());
+            let mut expected_ciphertext = plaintext.into();
+            cipher.encrypt_block(&mut expected_ciphertext);
+
+            assert_eq!(ciphertext, &expected_ciphertext[..], "failed at test {}", i);
+        }
+    }
+
+    #[test]
+    fn test_fips197_appendix_b_vector() {
+        let key = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ];
+        let plaintext = [
+            0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d, 0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37,
+            0x07, 0x34,
+        ];
+        let expected = [
+            0x39, 0x25, 0x84, 0x1d, 0x02, 0xdc, 0x09, 0xfb, 0xdc, 0x11, 0x85, 0x97, 0x19, 0x6a,
+            0x0b, 0x32,
+        ];
+
+        let round_keys = unsafe { expand_aes128_key(&key) };
+        assert_eq!(
+            unsafe { aes_encrypt_with_round_keys(plaintext, &round_keys) },
+            expected
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_hashes_match_intrinsics() {
+        use crate::x86_64;
+
+        let mut rng = rand::rng();
+        let mut key = [0u8; 16];
+        let mut public_s = [0u8; 16];
+        rng.fill_bytes(&mut key);
+        rng.fill_bytes(&mut public_s);
+
+        let round_keys = unsafe { expand_aes128_key(&key) };
+        let hw_round_keys = unsafe { x86_64::expand_aes128_key(&key) };
+        let hw_s = Label::from(public_s).0;
+
+        for i in 0..1000 {
+            let mut x = [0u8; 16];
+            rng.fill_bytes(&mut x);
+            let tweak = unsafe { index_to_tweak(i) };
+            let hw_x = Label::from(x).0;
+            let hw_tweak = unsafe { x86_64::index_to_tweak(i) };
+
+            let (ccrnd, tmmo, hw_ccrnd, hw_tmmo) = unsafe {
+                (
+                    ccrnd_with_round_keys(x, tweak, &round_keys, public_s),
+                    tmmo_with_round_keys(x, tweak, &round_keys),
+                    x86_64::ccrnd_with_round_keys(hw_x, hw_tweak, &hw_round_keys, hw_s),
+                    x86_64::tmmo_with_round_keys(hw_x, hw_tweak, &hw_round_keys),
+                )
+            };
+
+            assert_eq!(
+                ccrnd,
+                <[u8; 16]>::from(Label(hw_ccrnd)),
+                "ccrnd failed at test {}",
+                i
+            );
+            assert_eq!(
+                tmmo,
+                <[u8; 16]>::from(Label(hw_tmmo)),
+                "tmmo failed at test {}",
+                i
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Fixtures shared by the garbling, hashing and garbled circuit tests.
+
+use bitvec::vec::BitVec;
+
+use crate::hash::HashScheme;
+use crate::traits::{EvaluationInstanceConfig, GarblingInstanceConfig};
+
+/// Working space of the [`test_gates`] circuit.
+pub(crate) const SCRATCH_SPACE: u32 = 16;
+
+/// Deterministic mix of XOR and AND gates over [`SCRATCH_SPACE`] wires, as
+/// `(in1, in2, out, is_and)`.
+pub(crate) fn test_gates() -> Vec<(usize, usize, usize, bool)> {
+    (0..200usize)
+        .map(|i| {
+            let in1 = (i * 7 + 1) % SCRATCH_SPACE as usize;
+            let in2 = (i * 13 + 3) % SCRATCH_SPACE as usize;
+            let out = 2 + (i * 5) % (SCRATCH_SPACE as usize - 2);
+            (in1, in2, out, i % 3 != 0)
+        })
+        .collect()
+}
+
+/// Garbler config with fixed secrets over [`SCRATCH_SPACE`] wires.
+pub(crate) fn garbling_config(
+    primary_input_false_labels: &[[u8; 16]],
+    hash_scheme: HashScheme,
+) -> GarblingInstanceConfig<'_> {
+    GarblingInstanceConfig {
+        scratch_space: SCRATCH_SPACE,
+        delta: [0x11; 16],
+        primary_input_false_labels,
+        aes128_key: [0x22; 16],
+        public_s: [0x33; 16],
+        constant_zero_label: [0x44; 16],
+        constant_one_label: [0x55; 16],
+        hash_scheme,
+    }
+}
+
+/// Evaluator config matching [`garbling_config`].
+pub(crate) fn evaluation_config<'a>(
+    selected_primary_input_labels: &'a [[u8; 16]],
+    selected_primary_input_values: &'a BitVec,
+    hash_scheme: HashScheme,
+) -> EvaluationInstanceConfig<'a> {
+    EvaluationInstanceConfig {
+        scratch_space: SCRATCH_SPACE,
+        selected_primary_input_labels,
+        selected_primary_input_values,
+        aes128_key: [0x22; 16],
+        public_s: [0x33; 16],
+        constant_zero_label: [0x44; 16],
+        constant_one_label: [0x55; 16],
+        hash_scheme,
+    }
+}
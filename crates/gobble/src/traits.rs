@@ -4,6 +4,8 @@ use std::fmt;
 
 use bitvec::vec::BitVec;
 
+use crate::hash::HashScheme;
+
 /// A generic trait for describing an instance used for garbling a boolean circuit. The garbling instance stores labels of active wires during garbling.
 pub trait GarblingInstance {
     /// The ciphertext type used by this implementation.
@@ -24,6 +26,20 @@ pub trait GarblingInstance {
 
     /// Return selected labels for the given wires and values.
     fn get_selected_labels(&self, wires: &[u64], values: &BitVec, labels: &mut [[u8; 16]]);
+
+    /// Remap the false labels of `output_wires` to the chosen `targets`.
+    ///
+    /// Emits one ciphertext per wire holding the target encrypted under the
+    /// wire's circuit-derived false label. Afterwards the instance stores the
+    /// target as the wire's false label. An evaluator holding the true label
+    /// learns nothing about the target. Call this after the last gate has
+    /// been fed. Panics if the number of wires does not match the number of
+    /// targets.
+    fn translate_outputs(
+        &mut self,
+        output_wires: &[u64],
+        targets: &[[u8; 16]],
+    ) -> Vec<Self::Ciphertext>;
 }
 
 /// A generic trait for describing an instance used for evaluating a garbled boolean circuit. The evaluation instance stores labels and values corresponding to active wires during evaluation.
@@ -52,6 +68,16 @@ pub trait EvaluationInstance {
     /// Return values for the given wires. Panics if the number of wires does not match the
     /// number of values.
     fn get_values(&self, wires: &[u64], values: &mut [bool]);
+
+    /// Apply the ciphertexts from [`GarblingInstance::translate_outputs`] to
+    /// `output_wires`, in the same order.
+    ///
+    /// Wires whose value is false end up holding the garbler's target label.
+    /// Wires whose value is true end up holding an unrelated label. Either way
+    /// the label no longer encodes the wire's value, so read values with
+    /// [`EvaluationInstance::get_values`] first. Panics if the number of wires
+    /// does not match the number of ciphertexts.
+    fn apply_translation(&mut self, output_wires: &[u64], ciphertexts: &[Self::Ciphertext]);
 }
 
 /// A generic trait for describing an instance used for executing a boolean circuit.
@@ -77,9 +103,12 @@ pub struct GarblingInstanceConfig<'labels> {
     pub scratch_space: u32,
 
     /// The delta is a global offset constant used for garbling a circuit. This value must be kept secret from the evaluator.
+    ///
+    /// Its LSB is forced to 1 so that the evaluator can read wire values from
+    /// label LSBs.
     pub delta: [u8; 16],
 
-    /// Input false labels for the circuit.
+    /// Input false labels for the circuit. Their LSBs are forced to 0.
     pub primary_input_false_labels: &'labels [[u8; 16]],
 
     /// AES-128 key for this garbling instance.
@@ -103,11 +132,18 @@ pub struct GarblingInstanceConfig<'labels> {
     /// Label for constant wire 1 (always true).
     ///
     /// This is the true label for wire 1. The garbler internally stores
-    /// `constant_one_label XOR delta` to maintain FreeXOR invariants.
+    /// `constant_one_label XOR delta` to maintain FreeXOR invariants. Both
+    /// sides force its LSB to 1, and that of `constant_zero_label` to 0.
     ///
     /// This is chosen randomly by the garbler for each instance and must be
     /// communicated to the evaluator along with the garbled circuit.
     pub constant_one_label: [u8; 16],
+
+    /// Hash function used for AND gates, e.g. from [`HashScheme::autotune`].
+    ///
+    /// This must be communicated to the evaluator along with the garbled
+    /// circuit.
+    pub hash_scheme: HashScheme,
 }
 
 impl<'labels> fmt::Debug for GarblingInstanceConfig<'labels> {
@@ -137,7 +173,8 @@ pub struct EvaluationInstanceConfig<'labels> {
     /// the primary inputs.
     pub selected_primary_input_labels: &'labels [[u8; 16]],
 
-    /// Selected values for each wire (1 bit per wire, boolean)
+    /// Selected values for each wire (1 bit per wire, boolean). Each value is
+    /// stored as the LSB of the corresponding label.
     pub selected_primary_input_values: &'labels BitVec,
 
     /// AES-128 key for this evaluation instance.
@@ -159,6 +196,11 @@ pub struct EvaluationInstanceConfig<'labels> {
     ///
     /// This must match the value used during garbling.
     pub constant_one_label: [u8; 16],
+
+    /// Hash function used for AND gates.
+    ///
+    /// This must match the scheme used during garbling.
+    pub hash_scheme: HashScheme,
 }
 
 /// GobbleEngine is the primary interface of `gobble`. It
@@ -181,6 +223,9 @@ pub trait GobbleEngine {
     /// The execution instance implementation for this engine.
     type ExecutionInstance: ExecutionInstance;
 
+    /// The wire label type used by this engine.
+    type Label: Copy + From<[u8; 16]> + Into<[u8; 16]>;
+
     /// Creates a new garbling instance using a specified config.
     fn new_garbling_instance<'labels>(
         &self,
@@ -198,4 +243,21 @@ pub trait GobbleEngine {
         &self,
         config: EvaluationInstanceConfig<'labels>,
     ) -> Self::EvaluationInstance;
+
+    /// Deterministically derives `num_inputs` false labels and a delta from
+    /// `seed`.
+    fn expand_seed(&self, seed: [u8; 32], num_inputs: u32) -> (Vec<Self::Label>, Self::Label);
+
+    /// Selects the label for each input bit: the false label for 0, and the
+    /// false label XOR `delta` for 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` and `false_labels` differ in length.
+    fn encode(
+        &self,
+        input: &BitVec,
+        false_labels: &[Self::Label],
+        delta: Self::Label,
+    ) -> Vec<Self::Label>;
 }
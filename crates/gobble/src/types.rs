@@ -1,8 +1,9 @@
 //! Architecture-agnostic Label and Ciphertext types.
 //!
-//! These types wrap architecture-specific SIMD types (uint8x16_t on ARM, __m128i on x86)
-//! and provide a unified interface for the rest of the crate.
+//! These types wrap architecture-specific SIMD types (uint8x16_t on ARM, __m128i on x86,
+//! plain `[u8; 16]` elsewhere) and provide a unified interface for the rest of the crate.
 
+use std::hash::{Hash, Hasher};
 use std::mem::transmute;
 
 // Architecture-specific inner type
@@ -12,6 +13,18 @@ pub(crate) type Inner = std::arch::aarch64::uint8x16_t;
 #[cfg(target_arch = "x86_64")]
 pub(crate) type Inner = std::arch::x86_64::__m128i;
 
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+pub(crate) type Inner = [u8; 16];
+
+#[cfg(target_arch = "aarch64")]
+use crate::aarch64::xor128;
+
+#[cfg(target_arch = "x86_64")]
+use crate::x86_64::xor128;
+
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+use crate::scalar::xor128;
+
 /// A 128-bit label used in garbled circuits.
 ///
 /// Labels are the fundamental unit of garbled circuit computation.
@@ -19,6 +32,20 @@ pub(crate) type Inner = std::arch::x86_64::__m128i;
 #[derive(Debug, Clone, Copy)]
 pub struct Label(pub Inner);
 
+impl Label {
+    /// The label's 16 bytes, e.g. for sending it to another process.
+    #[inline]
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.into()
+    }
+
+    /// Rebuilds a label from bytes produced by [`Label::to_bytes`].
+    #[inline]
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        bytes.into()
+    }
+}
+
 impl Default for Label {
     fn default() -> Self {
         Label::from([0u8; 16])
@@ -37,12 +64,53 @@ impl From<Label> for [u8; 16] {
     }
 }
 
+/// Compares the label bytes with `[u8; 16]` equality, which is not
+/// constant time. Meant for tests and bookkeeping, not for comparing secret
+/// labels where timing matters.
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Label {}
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state);
+    }
+}
+
 /// A 128-bit ciphertext produced during garbling.
 ///
 /// Ciphertexts are produced for AND gates and stored in the garbled circuit.
 #[derive(Debug, Clone, Copy)]
 pub struct Ciphertext(pub Inner);
 
+impl Ciphertext {
+    /// XOR of two ciphertexts.
+    #[inline]
+    pub fn xor(self, other: Self) -> Self {
+        Ciphertext(unsafe { xor128(self.0, other.0) })
+    }
+}
+
+/// XOR `deltas[i]` into `cts[i]` for every `i`.
+///
+/// # Panics
+///
+/// Panics if `cts` and `deltas` differ in length.
+pub fn accumulate(cts: &mut [Ciphertext], deltas: &[Ciphertext]) {
+    assert_eq!(
+        cts.len(),
+        deltas.len(),
+        "one delta is needed per ciphertext"
+    );
+    for (ct, &delta) in cts.iter_mut().zip(deltas) {
+        *ct = ct.xor(delta);
+    }
+}
+
 impl Default for Ciphertext {
     fn default() -> Self {
         Ciphertext::from([0u8; 16])
@@ -61,6 +129,21 @@ impl From<Ciphertext> for [u8; 16] {
     }
 }
 
+/// Byte equality, not constant time, like [`Label`]'s.
+impl PartialEq for Ciphertext {
+    fn eq(&self, other: &Self) -> bool {
+        <[u8; 16]>::from(*self) == <[u8; 16]>::from(*other)
+    }
+}
+
+impl Eq for Ciphertext {}
+
+impl Hash for Ciphertext {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        <[u8; 16]>::from(*self).hash(state);
+    }
+}
+
 /// XOR two byte arrays of the same length.
 #[inline]
 pub fn xor_bytes<const N: usize>(a: [u8; N], b: [u8; N]) -> [u8; N] {
@@ -71,6 +154,32 @@ pub fn xor_bytes<const N: usize>(a: [u8; N], b: [u8; N]) -> [u8; N] {
     result
 }
 
+/// Select the label for each input bit, adding `delta` to the false label
+/// where the bit is set.
+///
+/// # Panics
+///
+/// Panics if `input` and `false_labels` differ in length.
+pub fn encode(input: &bitvec::vec::BitVec, false_labels: &[Label], delta: Label) -> Vec<Label> {
+    assert_eq!(
+        input.len(),
+        false_labels.len(),
+        "one false label is needed per input bit"
+    );
+    let delta: [u8; 16] = delta.into();
+    input
+        .iter()
+        .zip(false_labels)
+        .map(|(bit, &label)| {
+            if *bit {
+                Label::from(xor_bytes(label.into(), delta))
+            } else {
+                label
+            }
+        })
+        .collect()
+}
+
 /// Expand a seed into a vector of labels and a delta value.
 ///
 /// This is useful for deterministic label generation in tests.
@@ -92,3 +201,68 @@ pub fn expand_seed(seed: [u8; 32], num_inputs: u32) -> (Vec<Label>, Label) {
 
     (labels, delta)
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn random_ciphertext(rng: &mut impl Rng) -> Ciphertext {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        Ciphertext::from(bytes)
+    }
+
+    #[test]
+    fn test_ciphertext_xor_is_self_inverse() {
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let a = random_ciphertext(&mut rng);
+            let b = random_ciphertext(&mut rng);
+            let a_bytes: [u8; 16] = a.into();
+            assert_eq!(<[u8; 16]>::from(a.xor(b).xor(b)), a_bytes);
+            assert_eq!(<[u8; 16]>::from(a.xor(b)), xor_bytes(a_bytes, b.into()));
+        }
+    }
+
+    #[test]
+    fn test_accumulate_matches_pairwise_xor() {
+        let mut rng = rand::rng();
+        let cts: Vec<Ciphertext> = (0..64).map(|_| random_ciphertext(&mut rng)).collect();
+        let deltas: Vec<Ciphertext> = (0..64).map(|_| random_ciphertext(&mut rng)).collect();
+
+        let mut acc = cts.clone();
+        accumulate(&mut acc, &deltas);
+        for i in 0..cts.len() {
+            assert_eq!(
+                <[u8; 16]>::from(acc[i]),
+                <[u8; 16]>::from(cts[i].xor(deltas[i]))
+            );
+        }
+
+        // Accumulating the same deltas again restores the originals
+        accumulate(&mut acc, &deltas);
+        let restored: Vec<[u8; 16]> = acc.into_iter().map(Into::into).collect();
+        let original: Vec<[u8; 16]> = cts.into_iter().map(Into::into).collect();
+        assert_eq!(restored, original);
+    }
+
+    #[test]
+    fn test_labels_and_ciphertexts_compare_by_bytes() {
+        use std::collections::HashSet;
+
+        let (a, b) = (Label::from([7; 16]), Label::from([7; 16]));
+        let mut c_bytes = [7; 16];
+        c_bytes[15] = 8;
+        let c = Label::from(c_bytes);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(HashSet::from([a, b, c]).len(), 2);
+
+        let (x, y) = (Ciphertext::from([7; 16]), Ciphertext::from(c_bytes));
+        assert_eq!(x, Ciphertext::from([7; 16]));
+        assert_ne!(x, y);
+        assert_eq!(HashSet::from([x, x.xor(y).xor(y), y]).len(), 2);
+    }
+}
@@ -39,6 +39,21 @@ pub unsafe fn get_permute_bit(label: __m128i) -> bool {
     (bytes[0] & 1) == 1
 }
 
+/// Return `label` with its point-and-permute bit (LSB) set to `bit`.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - The CPU supports the `sse2` target feature.
+/// - The `label` parameter contains initialized data (not uninitialized memory).
+#[inline]
+pub unsafe fn with_permute_bit(label: __m128i, bit: bool) -> __m128i {
+    unsafe {
+        let cleared = _mm_and_si128(label, _mm_set_epi64x(-1, !1));
+        _mm_or_si128(cleared, _mm_set_epi64x(0, bit as i64))
+    }
+}
+
 /// XOR two 128-bit values.
 ///
 /// # Safety
@@ -51,6 +66,17 @@ pub unsafe fn xor128(a: __m128i, b: __m128i) -> __m128i {
     unsafe { _mm_xor_si128(a, b) }
 }
 
+/// Hint that the cache line holding `ptr` will be read soon.
+///
+/// # Safety
+///
+/// The caller must ensure that the CPU supports the `sse` target feature.
+/// Prefetches never fault, so `ptr` need not be valid.
+#[inline]
+pub unsafe fn prefetch_read<T>(ptr: *const T) {
+    unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr.cast()) }
+}
+
 /// Convert gate index to tweak value.
 ///
 /// # Safety
@@ -88,6 +114,34 @@ pub unsafe fn aes_encrypt_with_round_keys(block: __m128i, round_keys: &Aes128Rou
     state
 }
 
+/// AES-128 encryption of `N` independent blocks using caller-provided round keys.
+///
+/// Each round is applied to every block before moving to the next round, so
+/// the `aesenc` chains are independent and overlap in the AES-NI pipeline
+/// rather than each waiting out the previous instruction's latency.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - The CPU supports the `aes` and `sse2` target features.
+/// - The `blocks` contain initialized data (not uninitialized memory).
+/// - The `round_keys` are a valid AES-128 key schedule.
+#[inline]
+#[target_feature(enable = "aes")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn aes_encrypt_many_with_round_keys<const N: usize>(
+    blocks: [__m128i; N],
+    round_keys: &Aes128RoundKeys,
+) -> [__m128i; N] {
+    let mut states = blocks.map(|block| _mm_xor_si128(block, round_keys[0]));
+    for &key in &round_keys[1..10] {
+        for state in &mut states {
+            *state = _mm_aesenc_si128(*state, key);
+        }
+    }
+    states.map(|state| _mm_aesenclast_si128(state, round_keys[10]))
+}
+
 /// Linear orthomorphism: L || R -> (L ⊕ R) || L, taken from <https://eprint.iacr.org/2019/074.pdf> Section 7.3
 ///
 /// Optimized implementation using `_mm_shuffle_epi32` as described in the paper:
@@ -144,6 +198,77 @@ pub unsafe fn ccrnd_with_round_keys(
     }
 }
 
+/// TMMO hash function using caller-provided round keys.
+///
+/// This is the tweakable MMO construction `π(π(x) ⊕ t) ⊕ π(x)` from
+/// <https://eprint.iacr.org/2019/074.pdf>, using two AES calls.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - The CPU supports the `aes` and `sse2` target features.
+/// - `x` and `tweak` are valid 128-bit values.
+/// - The `round_keys` are a valid AES-128 key schedule.
+#[inline]
+#[target_feature(enable = "aes")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn tmmo_with_round_keys(
+    x: __m128i,
+    tweak: __m128i,
+    round_keys: &Aes128RoundKeys,
+) -> __m128i {
+    let pi_x = unsafe { aes_encrypt_with_round_keys(x, round_keys) };
+    unsafe {
+        xor128(
+            aes_encrypt_with_round_keys(xor128(pi_x, tweak), round_keys),
+            pi_x,
+        )
+    }
+}
+
+/// CCRND hash of `N` independent inputs, interleaving their AES calls.
+///
+/// Equal lane by lane to [`ccrnd_with_round_keys`].
+///
+/// # Safety
+///
+/// Same requirements as [`ccrnd_with_round_keys`].
+#[inline]
+#[target_feature(enable = "aes")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn ccrnd_many_with_round_keys<const N: usize>(
+    xs: [__m128i; N],
+    tweaks: [__m128i; N],
+    round_keys: &Aes128RoundKeys,
+    public_s: __m128i,
+) -> [__m128i; N] {
+    let inputs: [__m128i; N] =
+        std::array::from_fn(|i| unsafe { sigma(xor128(xor128(xs[i], public_s), tweaks[i])) });
+    let encrypted = unsafe { aes_encrypt_many_with_round_keys(inputs, round_keys) };
+    std::array::from_fn(|i| unsafe { xor128(encrypted[i], inputs[i]) })
+}
+
+/// TMMO hash of `N` independent inputs, interleaving their AES calls.
+///
+/// Equal lane by lane to [`tmmo_with_round_keys`].
+///
+/// # Safety
+///
+/// Same requirements as [`tmmo_with_round_keys`].
+#[inline]
+#[target_feature(enable = "aes")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn tmmo_many_with_round_keys<const N: usize>(
+    xs: [__m128i; N],
+    tweaks: [__m128i; N],
+    round_keys: &Aes128RoundKeys,
+) -> [__m128i; N] {
+    let pi_xs = unsafe { aes_encrypt_many_with_round_keys(xs, round_keys) };
+    let inputs: [__m128i; N] = std::array::from_fn(|i| unsafe { xor128(pi_xs[i], tweaks[i]) });
+    let encrypted = unsafe { aes_encrypt_many_with_round_keys(inputs, round_keys) };
+    std::array::from_fn(|i| unsafe { xor128(encrypted[i], pi_xs[i]) })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,6 +302,125 @@ mod tests {
         }
     }
 
+    /// Byte-level σ: the low 8 bytes become `lo ⊕ hi` and the high 8 bytes
+    /// become `lo`, matching `L || R -> (L ⊕ R) || L` with `L` the low half.
+    fn sigma_reference(x: [u8; 16]) -> [u8; 16] {
+        let mut out = [0u8; 16];
+        for i in 0..8 {
+            out[i] = x[i] ^ x[i + 8];
+            out[i + 8] = x[i];
+        }
+        out
+    }
+
+    #[test]
+    fn test_sigma_matches_scalar_reference() {
+        let mut rng = rand::rng();
+        let mut inputs = vec![[0u8; 16], [0xFF; 16], std::array::from_fn(|i| i as u8)];
+        for _ in 0..1000 {
+            let mut x = [0u8; 16];
+            rng.fill_bytes(&mut x);
+            inputs.push(x);
+        }
+
+        for x in inputs {
+            let result: [u8; 16] = unsafe { transmute(sigma(transmute::<[u8; 16], __m128i>(x))) };
+            assert_eq!(result, sigma_reference(x), "input {:02x?}", x);
+        }
+    }
+
+    #[test]
+    fn test_sigma_known_vector() {
+        let x = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20,
+            0x40, 0x80,
+        ];
+        let expected = [
+            0x01, 0x13, 0x26, 0x3b, 0x54, 0x75, 0x26, 0xf7, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55,
+            0x66, 0x77,
+        ];
+        let result: [u8; 16] = unsafe { transmute(sigma(transmute::<[u8; 16], __m128i>(x))) };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_sigma_is_invertible() {
+        // σ⁻¹: A || B -> B || (A ⊕ B), again with the low half first
+        let sigma_inverse = |y: [u8; 16]| -> [u8; 16] {
+            std::array::from_fn(|i| if i < 8 { y[i + 8] } else { y[i - 8] ^ y[i] })
+        };
+
+        let mut rng = rand::rng();
+        for _ in 0..1000 {
+            let mut x = [0u8; 16];
+            rng.fill_bytes(&mut x);
+            let y: [u8; 16] = unsafe { transmute(sigma(transmute::<[u8; 16], __m128i>(x))) };
+            assert_eq!(sigma_inverse(y), x, "input {:02x?}", x);
+        }
+    }
+
+    #[test]
+    fn test_ccrnd_matches_scalar_reference() {
+        let mut rng = rand::rng();
+        for i in 0..1000 {
+            let [mut key, mut x, mut tweak, mut s] = [[0u8; 16]; 4];
+            for bytes in [&mut key, &mut x, &mut tweak, &mut s] {
+                rng.fill_bytes(bytes);
+            }
+
+            let round_keys = unsafe { expand_aes128_key(&key) };
+            let result: [u8; 16] = unsafe {
+                transmute(ccrnd_with_round_keys(
+                    transmute::<[u8; 16], __m128i>(x),
+                    transmute::<[u8; 16], __m128i>(tweak),
+                    &round_keys,
+                    transmute::<[u8; 16], __m128i>(s),
+                ))
+            };
+
+            // H(x) = π(σ(x ⊕ S ⊕ t)) ⊕ σ(x ⊕ S ⊕ t)
+            let input: [u8; 16] = std::array::from_fn(|j| x[j] ^ s[j] ^ tweak[j]);
+            let sigma_input = sigma_reference(input);
+            let mut block = sigma_input.into();
+            Aes128::new(&key.into()).encrypt_block(&mut block);
+            let expected: [u8; 16] = std::array::from_fn(|j| block[j] ^ sigma_input[j]);
+
+            assert_eq!(result, expected, "failed at test {}", i);
+        }
+    }
+
+    #[test]
+    fn test_many_hashes_match_single() {
+        let mut rng = rand::rng();
+        let random = |rng: &mut rand::rngs::ThreadRng| {
+            let mut bytes = [0u8; 16];
+            rng.fill_bytes(&mut bytes);
+            unsafe { transmute::<[u8; 16], __m128i>(bytes) }
+        };
+        let bytes = |x: __m128i| unsafe { transmute::<__m128i, [u8; 16]>(x) };
+
+        for _ in 0..100 {
+            let mut key = [0u8; 16];
+            rng.fill_bytes(&mut key);
+            let round_keys = unsafe { expand_aes128_key(&key) };
+            let s = random(&mut rng);
+            let xs: [__m128i; 8] = std::array::from_fn(|_| random(&mut rng));
+            let tweaks: [__m128i; 8] = std::array::from_fn(|_| random(&mut rng));
+
+            let aes = unsafe { aes_encrypt_many_with_round_keys(xs, &round_keys) };
+            let ccrnd = unsafe { ccrnd_many_with_round_keys(xs, tweaks, &round_keys, s) };
+            let tmmo = unsafe { tmmo_many_with_round_keys(xs, tweaks, &round_keys) };
+            for i in 0..8 {
+                let single = unsafe { aes_encrypt_with_round_keys(xs[i], &round_keys) };
+                assert_eq!(bytes(aes[i]), bytes(single), "lane {}", i);
+                let single = unsafe { ccrnd_with_round_keys(xs[i], tweaks[i], &round_keys, s) };
+                assert_eq!(bytes(ccrnd[i]), bytes(single), "lane {}", i);
+                let single = unsafe { tmmo_with_round_keys(xs[i], tweaks[i], &round_keys) };
+                assert_eq!(bytes(tmmo[i]), bytes(single), "lane {}", i);
+            }
+        }
+    }
+
     #[test]
     fn test_ccrnd_output_bytes() {
         // Test vectors: fixed inputs to ensure reproducible outputs
@@ -0,0 +1,687 @@
+//! Conversion from Bristol Fashion circuits to v5a and v5c.
+//!
+//! Bristol wire `w` becomes v5a wire `w + 2`, leaving 0 and 1 for the
+//! constants, so primary inputs keep their order. `INV` is written as XOR
+//! with the true constant, `EQW` as XOR with the false constant, and a
+//! `MAND` over `2k` inputs as its `k` independent ANDs. The Bristol file is
+//! read twice: once to count each wire's fan-out for credits, and once to
+//! write the gates.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
+use std::ops::Range;
+use std::path::Path;
+
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use ckt_fmtv5_types::v5::a::{
+    writer::CircuitWriterV5a, GateV5a, CREDITS_OUTPUT, MAX_CREDITS, MAX_WIRE_ID,
+};
+use ckt_fmtv5_types::GateType;
+
+use crate::prealloc::{prealloc_with_budget, AbsoluteWireId};
+
+const FALSE_WIRE: AbsoluteWireId = 0;
+const TRUE_WIRE: AbsoluteWireId = 1;
+
+/// The three header lines of a Bristol Fashion file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BristolHeader {
+    /// Declared number of gates.
+    pub num_gates: u64,
+    /// Declared number of wires, including inputs.
+    pub num_wires: u64,
+    /// Bit width of each input value.
+    pub input_widths: Vec<u64>,
+    /// Bit width of each output value.
+    pub output_widths: Vec<u64>,
+}
+
+impl BristolHeader {
+    /// Total input bits, which are Bristol wires `0..primary_inputs()`.
+    ///
+    /// Fails if the widths overflow a `u64`.
+    pub fn primary_inputs(&self) -> Result<u64> {
+        sum_widths(&self.input_widths, "input")
+    }
+
+    /// Total output bits.
+    ///
+    /// Fails if the widths overflow a `u64`.
+    pub fn num_outputs(&self) -> Result<u64> {
+        sum_widths(&self.output_widths, "output")
+    }
+
+    /// Bristol wires holding the outputs, which are always the last wires.
+    ///
+    /// Fails if there are more output bits than wires.
+    pub fn output_wires(&self) -> Result<Range<u64>> {
+        let num_outputs = self.num_outputs()?;
+        let start = self.num_wires.checked_sub(num_outputs).ok_or_else(|| {
+            invalid(format!(
+                "{} wires cannot hold {} outputs",
+                self.num_wires, num_outputs
+            ))
+        })?;
+        Ok(start..self.num_wires)
+    }
+}
+
+fn sum_widths(widths: &[u64], kind: &str) -> Result<u64> {
+    widths
+        .iter()
+        .try_fold(0u64, |total, &width| total.checked_add(width))
+        .ok_or_else(|| invalid(format!("{} widths overflow a u64", kind)))
+}
+
+/// Summary of a Bristol conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionStats {
+    /// Gates written, after INV and EQW are lowered to XOR and MAND is split
+    /// into ANDs.
+    pub total_gates: u64,
+    /// XOR gates written.
+    pub xor_gates: u64,
+    /// AND gates written.
+    pub and_gates: u64,
+    /// Primary inputs in the output header.
+    pub primary_inputs: u64,
+    /// Outputs in the output header.
+    pub num_outputs: u64,
+    /// Size of the Bristol input in bytes.
+    pub bristol_file_size: u64,
+    /// Size of the written circuit in bytes.
+    pub output_file_size: u64,
+}
+
+/// Convert a Bristol Fashion file to v5a.
+///
+/// `primary_inputs` must match the input widths declared in the header.
+pub async fn bristol_to_v5a(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    primary_inputs: u64,
+) -> Result<ConversionStats> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+
+    let (header, uses) = count_fan_out(input, primary_inputs)?;
+    let output_wires = header.output_wires()?;
+    let outputs: Vec<u64> = output_wires.clone().map(|w| w + 2).collect();
+    let mut writer = CircuitWriterV5a::new(output, primary_inputs, outputs, [0u8; 32]).await?;
+
+    // The first pass already checked every gate
+    for line in gate_lines(input)?.skip(3) {
+        let (lineno, line) = line?;
+        for mut gate in parse_gates(&line, lineno)? {
+            let bristol_out = gate.out - 2;
+            let reads = uses.get(&bristol_out).copied().unwrap_or(0);
+            gate.credits = gate_credits(bristol_out, &output_wires, reads)?;
+            writer.write_gate(gate).await?;
+        }
+    }
+
+    let stats = writer.finalize().await?;
+    Ok(ConversionStats {
+        total_gates: stats.total_gates,
+        xor_gates: stats.xor_gates,
+        and_gates: stats.and_gates,
+        primary_inputs: stats.primary_inputs,
+        num_outputs: stats.num_outputs,
+        bristol_file_size: std::fs::metadata(input)?.len(),
+        output_file_size: std::fs::metadata(output)?.len(),
+    })
+}
+
+/// Convert a Bristol Fashion file to v5c, going through a temporary v5a
+/// file next to `output` and [`prealloc_with_budget`].
+pub async fn bristol_to_v5c(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    primary_inputs: u64,
+) -> Result<ConversionStats> {
+    let output = output.as_ref();
+    let mut intermediate = output.as_os_str().to_owned();
+    intermediate.push(".v5a.tmp");
+
+    let result = async {
+        let stats = bristol_to_v5a(input, &intermediate, primary_inputs).await?;
+        prealloc_with_budget(path_str(Path::new(&intermediate))?, path_str(output)?, None).await?;
+        Ok(ConversionStats {
+            output_file_size: std::fs::metadata(output)?.len(),
+            ..stats
+        })
+    }
+    .await;
+
+    let _ = std::fs::remove_file(&intermediate);
+    result
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("path {} is not valid UTF-8", path.display()),
+        )
+    })
+}
+
+/// Read only the header of a Bristol Fashion file.
+///
+/// This is how callers learn the primary input count without having it
+/// passed in: every input value's bit width is summed.
+pub fn read_header(input: impl AsRef<Path>) -> Result<BristolHeader> {
+    parse_header(&mut gate_lines(input.as_ref())?)
+}
+
+/// First pass: parse the header, check every gate and count how many gate
+/// inputs read each Bristol wire.
+///
+/// Wires are tracked in maps keyed by the wires gates actually name, rather
+/// than in vectors sized by the header's wire count, so a header declaring
+/// billions of wires can't exhaust memory before any gate is checked.
+fn count_fan_out(input: &Path, primary_inputs: u64) -> Result<(BristolHeader, HashMap<u64, u32>)> {
+    let mut lines = gate_lines(input)?;
+    let header = parse_header(&mut lines)?;
+    let (header_inputs, num_outputs) = (header.primary_inputs()?, header.num_outputs()?);
+
+    if header_inputs != primary_inputs {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} primary inputs given but the header declares {}",
+                primary_inputs, header_inputs
+            ),
+        ));
+    }
+    if header.num_wires > MAX_WIRE_ID - 1 {
+        return Err(invalid(format!(
+            "{} wires exceed the v5a wire ID range",
+            header.num_wires
+        )));
+    }
+    if num_outputs > header.num_wires || header_inputs > header.num_wires {
+        return Err(invalid(format!(
+            "{} wires cannot hold {} inputs and {} outputs",
+            header.num_wires, header_inputs, num_outputs
+        )));
+    }
+
+    // Gate outputs written so far; primary inputs are always defined
+    let mut uses = HashMap::new();
+    let mut defined = HashSet::new();
+
+    // Counts Bristol lines, so a MAND is one gate here
+    let mut num_gates = 0u64;
+    for line in lines {
+        let (lineno, line) = line?;
+        num_gates += 1;
+
+        for gate in parse_gates(&line, lineno)? {
+            check_gate(
+                &gate,
+                &header,
+                primary_inputs,
+                &mut defined,
+                &mut uses,
+                lineno,
+            )?;
+        }
+    }
+
+    if num_gates != header.num_gates {
+        return Err(invalid(format!(
+            "header declares {} gates but the file has {}",
+            header.num_gates, num_gates
+        )));
+    }
+    if let Some(wire) = header
+        .output_wires()?
+        .find(|&w| w >= primary_inputs && !defined.contains(&w))
+    {
+        return Err(invalid(format!("output wire {} is never written", wire)));
+    }
+
+    Ok((header, uses))
+}
+
+/// Check that a gate only reads defined wires and writes a fresh non-input
+/// wire, and count its reads.
+fn check_gate(
+    gate: &GateV5a,
+    header: &BristolHeader,
+    primary_inputs: u64,
+    defined: &mut HashSet<u64>,
+    uses: &mut HashMap<u64, u32>,
+    lineno: usize,
+) -> Result<()> {
+    for wire in [gate.in1, gate.in2] {
+        if wire < 2 {
+            continue;
+        }
+        let wire = wire - 2;
+        if wire >= primary_inputs && !defined.contains(&wire) {
+            return Err(invalid(format!(
+                "line {}: wire {} is read before it is written",
+                lineno, wire
+            )));
+        }
+        let reads = uses.entry(wire).or_insert(0u32);
+        *reads = reads.saturating_add(1);
+    }
+
+    let out = gate.out - 2;
+    if out < primary_inputs || out >= header.num_wires || !defined.insert(out) {
+        return Err(invalid(format!(
+            "line {}: wire {} cannot be written",
+            lineno, out
+        )));
+    }
+    Ok(())
+}
+
+fn gate_credits(bristol_out: u64, output_wires: &Range<u64>, uses: u32) -> Result<u32> {
+    // Unread non-output wires are never freed either
+    if output_wires.contains(&bristol_out) || uses == 0 {
+        return Ok(CREDITS_OUTPUT);
+    }
+    if uses >= MAX_CREDITS {
+        return Err(invalid(format!(
+            "wire {} is read {} times, more than v5a credits can hold",
+            bristol_out, uses
+        )));
+    }
+    Ok(uses)
+}
+
+/// Non-empty lines with their 1-based line numbers.
+fn gate_lines(input: &Path) -> Result<impl Iterator<Item = Result<(usize, String)>>> {
+    let reader = BufReader::new(File::open(input)?);
+    Ok(reader
+        .lines()
+        .enumerate()
+        .map(|(i, line)| line.map(|line| (i + 1, line)))
+        .filter(|line| !matches!(line, Ok((_, l)) if l.trim().is_empty())))
+}
+
+fn parse_header(
+    lines: &mut impl Iterator<Item = Result<(usize, String)>>,
+) -> Result<BristolHeader> {
+    let mut next = || -> Result<(usize, Vec<u64>)> {
+        let (lineno, line) = lines
+            .next()
+            .ok_or_else(|| invalid("truncated Bristol header".to_string()))??;
+        let numbers = parse_numbers(line.split_whitespace(), lineno)?;
+        Ok((lineno, numbers))
+    };
+
+    let (lineno, counts) = next()?;
+    let [num_gates, num_wires] = counts[..] else {
+        return Err(invalid(format!(
+            "line {}: expected gate and wire counts",
+            lineno
+        )));
+    };
+    let input_widths = parse_widths(next()?)?;
+    let output_widths = parse_widths(next()?)?;
+
+    let header = BristolHeader {
+        num_gates,
+        num_wires,
+        input_widths,
+        output_widths,
+    };
+    // Reject widths that can't be summed before anyone asks for the totals
+    header.primary_inputs()?;
+    header.num_outputs()?;
+    Ok(header)
+}
+
+/// Parse an `n w_1 ... w_n` header line.
+fn parse_widths((lineno, numbers): (usize, Vec<u64>)) -> Result<Vec<u64>> {
+    match numbers.split_first() {
+        Some((&n, widths)) if n == widths.len() as u64 => Ok(widths.to_vec()),
+        _ => Err(invalid(format!(
+            "line {}: expected a count followed by that many widths",
+            lineno
+        ))),
+    }
+}
+
+fn parse_numbers<'a>(tokens: impl Iterator<Item = &'a str>, lineno: usize) -> Result<Vec<u64>> {
+    tokens
+        .map(|t| {
+            t.parse()
+                .map_err(|_| invalid(format!("line {}: invalid number '{}'", lineno, t)))
+        })
+        .collect()
+}
+
+/// Parse a gate line into v5a wire IDs, with credits left at zero.
+///
+/// Every line yields one gate except `MAND`, which yields one AND per output.
+fn parse_gates(line: &str, lineno: usize) -> Result<Vec<GateV5a>> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let Some((&kind, numbers)) = tokens.split_last() else {
+        return Err(invalid(format!("line {}: empty gate line", lineno)));
+    };
+    let numbers = parse_numbers(numbers.iter().copied(), lineno)?;
+    let wires: Vec<u64> = numbers
+        .iter()
+        .skip(2)
+        .map(|&w| {
+            w.checked_add(2)
+                .filter(|&w| w <= MAX_WIRE_ID)
+                .ok_or_else(|| invalid(format!("line {}: wire {} is out of range", lineno, w)))
+        })
+        .collect::<Result<_>>()?;
+    let gate = |gate_type, in1, in2, out| GateV5a {
+        in1,
+        in2,
+        out,
+        credits: 0,
+        gate_type,
+    };
+
+    match (kind, &numbers[..], &wires[..]) {
+        ("XOR", [2, 1, ..], &[a, b, c]) => Ok(vec![gate(GateType::XOR, a, b, c)]),
+        ("AND", [2, 1, ..], &[a, b, c]) => Ok(vec![gate(GateType::AND, a, b, c)]),
+        ("INV", [1, 1, ..], &[a, c]) => Ok(vec![gate(GateType::XOR, a, TRUE_WIRE, c)]),
+        ("EQW", [1, 1, ..], &[a, c]) => Ok(vec![gate(GateType::XOR, a, FALSE_WIRE, c)]),
+        // `2k k a_1..a_k b_1..b_k c_1..c_k MAND` computes c_i = a_i & b_i
+        ("MAND", &[n_in, k, ..], _) if k > 0 && n_in == 2 * k && wires.len() as u64 == 3 * k => {
+            let (a, rest) = wires.split_at(k as usize);
+            let (b, c) = rest.split_at(k as usize);
+            Ok((0..k as usize)
+                .map(|i| gate(GateType::AND, a[i], b[i], c[i]))
+                .collect())
+        }
+        ("XOR" | "AND" | "INV" | "EQW" | "MAND", _, _) => Err(invalid(format!(
+            "line {}: wrong wire counts for {}",
+            lineno, kind
+        ))),
+        _ => Err(invalid(format!(
+            "line {}: unsupported gate type '{}'",
+            lineno, kind
+        ))),
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credits::validate_credits;
+    use crate::exec::exec_compact;
+    use ckt_fmtv5_types::v5::a::reader::CircuitReaderV5a;
+    use ckt_fmtv5_types::v5::c::ReaderV5c;
+
+    // Inputs a (wire 0) and b (wire 1). Outputs are the last five wires,
+    // starting at wire 1, so one output is a primary input passed through.
+    const PASSTHROUGH: &str = "\
+4 6
+2 1 1
+1 5
+
+2 1 0 1 2 AND
+1 1 2 5 INV
+2 1 0 2 3 XOR
+1 1 5 4 EQW
+";
+
+    fn write_bristol(dir: &Path, text: &str) -> std::path::PathBuf {
+        let path = dir.join("circuit.txt");
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[monoio::test]
+    async fn test_v5a_conversion_preserves_passthrough_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(dir.path(), PASSTHROUGH);
+        let output = dir.path().join("circuit.v5a");
+
+        let stats = bristol_to_v5a(&bristol, &output, 2).await.unwrap();
+        assert_eq!((stats.total_gates, stats.and_gates), (4, 1));
+        assert_eq!(stats.num_outputs, 5);
+
+        let mut reader = CircuitReaderV5a::open(&output).unwrap();
+        assert_eq!(reader.header().num_outputs, 5);
+        assert_eq!(reader.outputs(), &[3, 4, 5, 6, 7]);
+        let mut gates = Vec::new();
+        while let Some(block) = reader.next_block().await.unwrap() {
+            gates.extend(block);
+        }
+        assert_eq!(validate_credits(2, reader.outputs(), &gates), vec![]);
+
+        for bits in 0..4u8 {
+            let (a, b) = (bits & 1 == 1, bits & 2 == 2);
+            let result = exec_compact(output.to_str().unwrap(), &[a, b])
+                .await
+                .unwrap();
+            assert_eq!(
+                result.outputs,
+                vec![b, a & b, a ^ (a & b), !(a & b), !(a & b)]
+            );
+        }
+    }
+
+    #[monoio::test]
+    async fn test_v5c_conversion_header_matches_declarations() {
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(dir.path(), PASSTHROUGH);
+        let output = dir.path().join("circuit.v5c");
+
+        let stats = bristol_to_v5c(&bristol, &output, 2).await.unwrap();
+        assert_eq!(
+            stats.output_file_size,
+            std::fs::metadata(&output).unwrap().len()
+        );
+
+        let reader = ReaderV5c::open(&output).unwrap();
+        assert_eq!(reader.header().num_outputs, 5);
+        assert_eq!(reader.header().primary_inputs, 2);
+        assert_eq!(reader.header().total_gates(), 4);
+        // The passthrough output keeps the primary input's address
+        assert_eq!(reader.outputs()[0], 3);
+
+        // The intermediate v5a file is cleaned up
+        let mut entries: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, ["circuit.txt", "circuit.v5c"]);
+    }
+
+    #[test]
+    fn test_parse_gates_lowers_inv_to_xor_with_true_wire() {
+        let snippet = ["2 1 0 1 2 XOR", "2 1 2 1 3 AND", "1 1 3 4 INV"];
+        let gates: Vec<_> = snippet
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| parse_gates(line, i + 1).unwrap())
+            .collect();
+
+        let lowered: Vec<_> = gates
+            .iter()
+            .map(|g| (g.gate_type, g.in1, g.in2, g.out))
+            .collect();
+        assert_eq!(
+            lowered,
+            vec![
+                (GateType::XOR, 2, 3, 4),
+                (GateType::AND, 4, 3, 5),
+                // Wire 1 is the true constant in the CKT convention
+                (GateType::XOR, 5, TRUE_WIRE, 6),
+            ]
+        );
+        assert_eq!(TRUE_WIRE, 1);
+
+        let err = parse_gates("2 1 3 4 5 INV", 4).unwrap_err();
+        assert_eq!(err.to_string(), "line 4: wrong wire counts for INV");
+    }
+
+    #[monoio::test]
+    async fn test_mand_expands_to_one_and_per_output() {
+        // Inputs a, b, c, d are wires 0..4; MAND writes a & c and b & d
+        let text = "\
+2 7
+1 4
+1 3
+
+4 2 0 1 2 3 4 5 MAND
+2 1 4 5 6 XOR
+";
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(dir.path(), text);
+        let output = dir.path().join("circuit.v5a");
+
+        let stats = bristol_to_v5a(&bristol, &output, 4).await.unwrap();
+        assert_eq!(
+            (stats.total_gates, stats.and_gates, stats.xor_gates),
+            (3, 2, 1)
+        );
+
+        let reader = CircuitReaderV5a::open(&output).unwrap();
+        assert_eq!(reader.outputs(), &[6, 7, 8]);
+
+        for bits in 0..16u8 {
+            let [a, b, c, d] = std::array::from_fn(|i| (bits >> i) & 1 == 1);
+            let result = exec_compact(output.to_str().unwrap(), &[a, b, c, d])
+                .await
+                .unwrap();
+            assert_eq!(result.outputs, vec![a & c, b & d, (a & c) ^ (b & d)]);
+        }
+
+        let err = parse_gates("4 2 0 1 2 3 4 MAND", 6).unwrap_err();
+        assert_eq!(err.to_string(), "line 6: wrong wire counts for MAND");
+    }
+
+    #[monoio::test]
+    async fn test_rejects_wrong_primary_inputs_and_bad_gates() {
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(dir.path(), PASSTHROUGH);
+        let output = dir.path().join("circuit.v5a");
+
+        let err = bristol_to_v5a(&bristol, &output, 3).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let bristol = write_bristol(dir.path(), "1 3\n1 2\n1 1\n2 1 0 1 2 OR\n");
+        let err = bristol_to_v5a(&bristol, &output, 2).await.unwrap_err();
+        assert_eq!(err.to_string(), "line 4: unsupported gate type 'OR'");
+    }
+
+    #[monoio::test]
+    async fn test_rejects_wire_ids_that_overflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("circuit.v5a");
+
+        // Offsetting this by the two constants would wrap it to wire 0
+        let bristol = write_bristol(
+            dir.path(),
+            "1 3\n1 2\n1 1\n2 1 0 18446744073709551615 2 XOR\n",
+        );
+        let err = bristol_to_v5a(&bristol, &output, 2).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "line 4: wire 18446744073709551615 is out of range"
+        );
+    }
+
+    #[monoio::test]
+    async fn test_huge_declared_wire_count_is_not_preallocated() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("circuit.v5a");
+
+        // Nearly 2^34 wires, of which one gate writes only the last
+        let bristol = write_bristol(
+            dir.path(),
+            "1 17179869180\n1 1\n1 1\n2 1 0 0 17179869179 XOR\n",
+        );
+        let stats = bristol_to_v5a(&bristol, &output, 1).await.unwrap();
+        assert_eq!(stats.total_gates, 1);
+        let reader = CircuitReaderV5a::open(&output).unwrap();
+        assert_eq!(reader.outputs(), &[17179869181]);
+    }
+
+    #[monoio::test]
+    async fn test_bad_line_deep_in_file_reports_its_line_number() {
+        // 1000 chained XORs with stray blank lines, and one malformed gate
+        let mut text = String::from("1000 1002\n1 2\n1 1\n\n");
+        for i in 0..1000u64 {
+            if i % 100 == 50 {
+                text.push_str("   \n");
+            }
+            if i == 837 {
+                text.push_str("2 1 838 x 839 XOR\n");
+            } else {
+                text.push_str(&format!("2 1 {} {} {} XOR\n", i + 1, i % 2, i + 2));
+            }
+        }
+        let lineno = text.lines().position(|l| l.contains(" x ")).unwrap() + 1;
+        // Header and blank line, eight stray blanks, then the 838th gate
+        assert_eq!(lineno, 4 + 8 + 838);
+
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(dir.path(), &text);
+        let err = bristol_to_v5a(&bristol, dir.path().join("circuit.v5a"), 2)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            format!("line {}: invalid number 'x'", lineno)
+        );
+    }
+
+    #[test]
+    fn test_read_header_sums_input_widths() {
+        // Header of the Bristol Fashion SHA-256 compression function: a 512
+        // bit block and a 256 bit chaining value in, the new chaining value out
+        let dir = tempfile::tempdir().unwrap();
+        let bristol = write_bristol(
+            dir.path(),
+            "22573 22829\n2 512 256\n1 256\n\n2 1 0 512 768 XOR\n",
+        );
+
+        let header = read_header(&bristol).unwrap();
+        assert_eq!(header.num_gates, 22573);
+        assert_eq!(header.input_widths, vec![512, 256]);
+        assert_eq!(header.primary_inputs().unwrap(), 768);
+        assert_eq!(header.num_outputs().unwrap(), 256);
+        assert_eq!(header.output_wires().unwrap(), 22573..22829);
+
+        let bristol = write_bristol(dir.path(), "22573 22829\n");
+        let err = read_header(&bristol).unwrap_err();
+        assert_eq!(err.to_string(), "truncated Bristol header");
+    }
+
+    #[test]
+    fn test_read_header_rejects_overflowing_widths() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = u64::MAX / 2 + 1;
+
+        let bristol = write_bristol(dir.path(), &format!("1 3\n2 {} {}\n1 1\n", big, big));
+        let err = read_header(&bristol).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "input widths overflow a u64");
+
+        let bristol = write_bristol(dir.path(), &format!("1 3\n1 2\n2 {} {}\n", big, big));
+        let err = read_header(&bristol).unwrap_err();
+        assert_eq!(err.to_string(), "output widths overflow a u64");
+
+        // Sums that fit but exceed the wire count fail at output_wires
+        let header = BristolHeader {
+            num_gates: 1,
+            num_wires: 3,
+            input_widths: vec![2],
+            output_widths: vec![4],
+        };
+        let err = header.output_wires().unwrap_err();
+        assert_eq!(err.to_string(), "3 wires cannot hold 4 outputs");
+    }
+}
@@ -12,8 +12,55 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Command {
+    /// Convert a Bristol Fashion circuit to v5a or v5c
+    Convert(ConvertCommand),
     /// Preallocate wires and convert v5a to v5c flat format
     Prealloc(PreallocCommand),
+    /// Detect a circuit's format and print its header summary
+    Info(InfoCommand),
+    /// Report estimated garbling cost of a v5a or v5c circuit
+    Cost(CostCommand),
+    /// Search a v5a circuit for gates using or producing given wires, or a
+    /// v5c circuit for gates reading or writing given addresses
+    Search(SearchCommand),
+    /// Fold redundant XOR chains in a v5a circuit, writing v5a
+    Minimize(MinimizeCommand),
+    /// Check the stored checksum of a v5a or v5c circuit
+    Verify(VerifyCommand),
+    /// Recompute and rewrite the stored checksum of a v5a or v5c circuit
+    Repair(RepairCommand),
+    /// Dump a v5c circuit as Bristol-like text, using addresses as wire IDs
+    Extract(ExtractCommand),
+    /// Report a v5a circuit's depth and gates per level
+    Levels(LevelsCommand),
+    /// Check that every v5a gate's credits match its output's real fan-out
+    CheckCredits(CheckCreditsCommand),
+    /// Report v5a gates whose outputs are never read and aren't outputs
+    AnalyzeDead(AnalyzeDeadCommand),
+    /// Compare two v5a circuits' gate counts, or their gates with --structural
+    Compare(CompareCommand),
+    /// Append one v5a circuit after another, feeding outputs into inputs
+    Compose(ComposeCommand),
+}
+
+#[derive(Parser, Debug)]
+pub struct ConvertCommand {
+    /// Input Bristol Fashion file path
+    #[arg(value_name = "INPUT")]
+    pub input: PathBuf,
+
+    /// Output CKT file path (defaults to INPUT with a .v5a or .v5c extension)
+    #[arg(short, long, value_name = "OUTPUT")]
+    pub output: Option<PathBuf>,
+
+    /// Output format version (v5a or v5c)
+    #[arg(long, default_value = "v5c", value_name = "VERSION")]
+    pub version: String,
+
+    /// Number of primary inputs, checked against the Bristol header
+    /// (defaults to the sum of the header's input widths)
+    #[arg(short, long, value_name = "COUNT")]
+    pub primary_inputs: Option<u64>,
 }
 
 #[derive(Parser, Debug)]
@@ -25,6 +72,123 @@ pub struct PreallocCommand {
     /// Output v5c CKT file path
     #[arg(value_name = "OUTPUT")]
     pub output: PathBuf,
+
+    /// Abort if scratch space would exceed this many slots
+    #[arg(long, value_name = "SLOTS")]
+    pub max_scratch: Option<u64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct InfoCommand {
+    /// Input CKT file path, in any v5 format
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct CostCommand {
+    /// Input v5a or v5c CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// AND gate hash to count AES operations for (ccrnd or tmmo)
+    #[arg(long, default_value = "ccrnd", value_name = "SCHEME")]
+    pub hash_scheme: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct SearchCommand {
+    /// Input v5a or v5c CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Input wire IDs (v5a) or addresses (v5c) to search for (can specify
+    /// multiple)
+    #[arg(short, long, value_name = "INPUT", num_args = 1..)]
+    pub inputs: Vec<u64>,
+
+    /// Output wire IDs (v5a) or addresses (v5c) to search for (can specify
+    /// multiple)
+    #[arg(short, long, value_name = "OUTPUT", num_args = 1..)]
+    pub outputs: Vec<u64>,
+}
+
+#[derive(Parser, Debug)]
+pub struct MinimizeCommand {
+    /// Input v5a CKT file path
+    #[arg(value_name = "INPUT")]
+    pub input: PathBuf,
+
+    /// Output v5a CKT file path
+    #[arg(value_name = "OUTPUT")]
+    pub output: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyCommand {
+    /// v5a or v5c CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Also recount XOR and AND gates in the same pass and check them
+    /// against the header (v5c only)
+    #[arg(long)]
+    pub and_count: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct RepairCommand {
+    /// v5a or v5c CKT file path, rewritten in place
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExtractCommand {
+    /// Input v5c CKT file path
+    #[arg(value_name = "INPUT")]
+    pub input: PathBuf,
+
+    /// Output text file path (defaults to INPUT with a .bristol extension)
+    #[arg(short, long, value_name = "OUTPUT")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckCreditsCommand {
+    /// Input v5a CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct LevelsCommand {
+    /// Input v5a CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct AnalyzeDeadCommand {
+    /// Input v5a CKT file path
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct CompareCommand {
+    /// First v5a CKT file path
+    #[arg(value_name = "FILE1")]
+    pub file1: PathBuf,
+
+    /// Second v5a CKT file path
+    #[arg(value_name = "FILE2")]
+    pub file2: PathBuf,
+
+    /// Stream both files and report the first gate whose inputs, output or
+    /// type differ
+    #[arg(long)]
+    pub structural: bool,
 }
 
 impl Cli {
@@ -33,3 +197,23 @@ impl Cli {
         Cli::parse()
     }
 }
+
+#[derive(Parser, Debug)]
+pub struct ComposeCommand {
+    /// First v5a CKT file path, whose gates come first
+    #[arg(value_name = "A")]
+    pub a: PathBuf,
+
+    /// Second v5a CKT file path, appended after A
+    #[arg(value_name = "B")]
+    pub b: PathBuf,
+
+    /// Output v5a CKT file path
+    #[arg(short, long, value_name = "OUTPUT")]
+    pub output: PathBuf,
+
+    /// Outputs of A feeding inputs of B, as "a_out0:b_in0,a_out1:b_in1,..."
+    /// (unfed inputs of B become primary inputs)
+    #[arg(long, value_name = "MAP", default_value = "")]
+    pub map: String,
+}
@@ -0,0 +1,159 @@
+//! Gate-by-gate comparison of v5a circuits.
+//!
+//! Matching gate counts say little about whether a conversion was lossless,
+//! so this streams both files in lockstep and stops at the first gate whose
+//! inputs, output or type differ. Credits are not compared, as they follow
+//! from the structure.
+
+use std::io::Result;
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::a::{reader::CircuitReaderV5a, GateV5a};
+
+/// Where two circuits first diverge.
+#[derive(Debug, Clone)]
+pub enum Difference {
+    /// Both files have a gate at `index` but they differ.
+    Gate {
+        /// Index of the gate in file order.
+        index: u64,
+        /// The gate in the first file.
+        left: GateV5a,
+        /// The gate in the second file.
+        right: GateV5a,
+    },
+    /// All shared gates match but one file ends after `index` gates.
+    Truncated {
+        /// Number of gates in the shorter file.
+        index: u64,
+        /// Number of gates in the first file.
+        left_gates: u64,
+        /// Number of gates in the second file.
+        right_gates: u64,
+    },
+}
+
+/// Whether two gates have the same inputs, output and type.
+pub fn same_structure(a: &GateV5a, b: &GateV5a) -> bool {
+    a.in1 == b.in1 && a.in2 == b.in2 && a.out == b.out && a.gate_type == b.gate_type
+}
+
+/// Stream two v5a files and return the first gate where they differ, or
+/// `None` if every gate matches.
+pub async fn first_difference(
+    path1: impl AsRef<Path>,
+    path2: impl AsRef<Path>,
+) -> Result<Option<Difference>> {
+    let mut left = CircuitReaderV5a::open(path1)?;
+    let mut right = CircuitReaderV5a::open(path2)?;
+    let left_gates = left.gate_counts().total;
+    let right_gates = right.gate_counts().total;
+    let mut index = 0u64;
+
+    // Every block but the last is full, so blocks line up until one file ends
+    loop {
+        let (Some(a), Some(b)) = (left.next_block_soa().await?, right.next_block_soa().await?)
+        else {
+            break;
+        };
+        let shared = a.gates_in_block.min(b.gates_in_block);
+        for i in 0..shared {
+            let (l, r) = (a.gate(i), b.gate(i));
+            if !same_structure(&l, &r) {
+                return Ok(Some(Difference::Gate {
+                    index: index + i as u64,
+                    left: l,
+                    right: r,
+                }));
+            }
+        }
+        index += shared as u64;
+        if a.gates_in_block != b.gates_in_block {
+            break;
+        }
+    }
+
+    if left_gates != right_gates {
+        return Ok(Some(Difference::Truncated {
+            index: left_gates.min(right_gates),
+            left_gates,
+            right_gates,
+        }));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_gates_v5a;
+    use adder::generate_adder;
+
+    #[monoio::test]
+    async fn test_reports_first_mutated_gate() {
+        let circuit = generate_adder(100);
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.v5a");
+        let mutated = dir.path().join("mutated.v5a");
+        write_gates_v5a(
+            &original,
+            circuit.primary_inputs,
+            &circuit.outputs,
+            &circuit.gates,
+        )
+        .await;
+
+        // Same gate mix, so only a structural diff can tell them apart
+        let mut gates = circuit.gates.clone();
+        gates[300].in1 = if gates[300].in1 == 2 { 3 } else { 2 };
+        write_gates_v5a(&mutated, circuit.primary_inputs, &circuit.outputs, &gates).await;
+
+        assert!(first_difference(&original, &original)
+            .await
+            .unwrap()
+            .is_none());
+        match first_difference(&original, &mutated).await.unwrap() {
+            Some(Difference::Gate { index, left, right }) => {
+                assert_eq!(index, 300);
+                assert_eq!(left.in1, circuit.gates[300].in1);
+                assert_eq!(right.in1, gates[300].in1);
+            }
+            other => panic!("expected a gate difference, got {:?}", other),
+        }
+    }
+
+    #[monoio::test]
+    async fn test_reports_truncation_point() {
+        let circuit = generate_adder(100);
+        let dir = tempfile::tempdir().unwrap();
+        let full = dir.path().join("full.v5a");
+        let short = dir.path().join("short.v5a");
+        write_gates_v5a(
+            &full,
+            circuit.primary_inputs,
+            &circuit.outputs,
+            &circuit.gates,
+        )
+        .await;
+        write_gates_v5a(
+            &short,
+            circuit.primary_inputs,
+            &circuit.outputs,
+            &circuit.gates[..300],
+        )
+        .await;
+
+        match first_difference(&short, &full).await.unwrap() {
+            Some(Difference::Truncated {
+                index,
+                left_gates,
+                right_gates,
+            }) => {
+                assert_eq!(index, 300);
+                assert_eq!(left_gates, 300);
+                assert_eq!(right_gates, circuit.gates.len() as u64);
+            }
+            other => panic!("expected truncation, got {:?}", other),
+        }
+    }
+}
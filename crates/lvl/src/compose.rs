@@ -0,0 +1,306 @@
+//! Sequential composition of v5a circuits.
+//!
+//! Appends circuit B's gates after circuit A's, feeding chosen outputs of A
+//! into inputs of B. The constants 0 and 1 are shared. A's primary inputs
+//! come first, followed by whichever of B's inputs are left unfed, and then
+//! every gate wire, A's before B's. A's outputs that feed B are consumed;
+//! the composite's outputs are A's remaining outputs followed by all of B's.
+//! With an empty map, this places the two circuits side by side.
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use ahash::{HashMap, HashMapExt};
+use ckt_fmtv5_types::v5::a::{
+    reader::CircuitReaderV5a, writer::CircuitWriterV5a, GateV5a, MAX_WIRE_ID,
+};
+
+use crate::minimize::assign_credits;
+use crate::prealloc::AbsoluteWireId;
+
+/// A v5a circuit held in memory.
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    /// Number of primary inputs, on wires `2..2 + primary_inputs`.
+    pub primary_inputs: u64,
+    /// Gates in topological order.
+    pub gates: Vec<GateV5a>,
+    /// Output wires.
+    pub outputs: Vec<AbsoluteWireId>,
+}
+
+impl Circuit {
+    /// Read a whole v5a file into memory.
+    pub async fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let mut reader = CircuitReaderV5a::open(path)?;
+        let header = reader.header();
+        let mut gates = Vec::with_capacity(header.total_gates() as usize);
+        while let Some(block) = reader.next_block_soa().await? {
+            gates.extend(block.gates());
+        }
+        Ok(Self {
+            primary_inputs: header.primary_inputs,
+            gates,
+            outputs: reader.outputs().to_vec(),
+        })
+    }
+
+    /// Write the circuit as v5a, keeping its credits as they are.
+    pub async fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer =
+            CircuitWriterV5a::new(path, self.primary_inputs, self.outputs.clone(), [0u8; 32])
+                .await?;
+        writer.write_gates(&self.gates).await?;
+        writer.finalize().await?;
+        Ok(())
+    }
+}
+
+/// Compose `a` then `b`, feeding output `i` of `a` into input `j` of `b`
+/// for every `(i, j)` in `map`.
+///
+/// One output of `a` may feed several inputs of `b`, but each input of `b`
+/// may be fed at most once. Credits are recomputed for the result, which
+/// fails if a wire ends up read more often than v5a credits can hold.
+pub fn compose(a: &Circuit, b: &Circuit, map: &[(usize, usize)]) -> Result<Circuit> {
+    let mut fed_by: HashMap<u64, usize> = HashMap::new();
+    let mut consumed = vec![false; a.outputs.len()];
+    for &(a_out, b_in) in map {
+        if a_out >= a.outputs.len() {
+            return Err(invalid(format!(
+                "output {} out of range, A has {} outputs",
+                a_out,
+                a.outputs.len()
+            )));
+        }
+        if b_in as u64 >= b.primary_inputs {
+            return Err(invalid(format!(
+                "input {} out of range, B has {} inputs",
+                b_in, b.primary_inputs
+            )));
+        }
+        if fed_by.insert(b_in as u64, a_out).is_some() {
+            return Err(invalid(format!("input {} of B is fed twice", b_in)));
+        }
+        consumed[a_out] = true;
+    }
+
+    // B's unfed inputs become primary inputs right after A's, pushing A's
+    // gate wires up by as many
+    let mut new_inputs: HashMap<u64, AbsoluteWireId> = HashMap::new();
+    for j in 0..b.primary_inputs {
+        if !fed_by.contains_key(&j) {
+            let wire = 2 + a.primary_inputs + new_inputs.len() as u64;
+            new_inputs.insert(j, wire);
+        }
+    }
+    let shift = new_inputs.len() as u64;
+    let map_a = |w: AbsoluteWireId| {
+        if w < a.primary_inputs + 2 {
+            w
+        } else {
+            w + shift
+        }
+    };
+
+    let a_top = a
+        .gates
+        .iter()
+        .map(|g| g.out)
+        .max()
+        .map_or(a.primary_inputs + 1, map_a)
+        .max(a.primary_inputs + shift + 1);
+    let map_b = |w: AbsoluteWireId| {
+        if w < 2 {
+            w
+        } else if w < b.primary_inputs + 2 {
+            let j = w - 2;
+            match fed_by.get(&j) {
+                Some(&a_out) => map_a(a.outputs[a_out]),
+                None => new_inputs[&j],
+            }
+        } else {
+            a_top + 1 + (w - b.primary_inputs - 2)
+        }
+    };
+
+    let mut gates = Vec::with_capacity(a.gates.len() + b.gates.len());
+    let renumber = |g: &GateV5a, f: &dyn Fn(AbsoluteWireId) -> AbsoluteWireId| GateV5a {
+        in1: f(g.in1),
+        in2: f(g.in2),
+        out: f(g.out),
+        ..*g
+    };
+    gates.extend(a.gates.iter().map(|g| renumber(g, &map_a)));
+    gates.extend(b.gates.iter().map(|g| renumber(g, &map_b)));
+    if let Some(g) = gates.iter().find(|g| g.out > MAX_WIRE_ID) {
+        return Err(invalid(format!(
+            "wire {} exceeds the v5a maximum of {}",
+            g.out, MAX_WIRE_ID
+        )));
+    }
+
+    let outputs: Vec<AbsoluteWireId> = a
+        .outputs
+        .iter()
+        .zip(&consumed)
+        .filter(|&(_, &consumed)| !consumed)
+        .map(|(&w, _)| map_a(w))
+        .chain(b.outputs.iter().map(|&w| map_b(w)))
+        .collect();
+
+    let primary_inputs = a.primary_inputs + shift;
+    assign_credits(primary_inputs, &mut gates, &outputs)?;
+
+    Ok(Circuit {
+        primary_inputs,
+        gates,
+        outputs,
+    })
+}
+
+/// Parse a comma-separated list of `A_OUT:B_IN` index pairs, each index
+/// optionally prefixed with `a_out` and `b_in` respectively.
+pub fn parse_wire_map(s: &str) -> Result<Vec<(usize, usize)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (a_out, b_in) = pair
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("expected A_OUT:B_IN, got {:?}", pair)))?;
+            let index = |s: &str, prefix: &str| {
+                let s = s.trim();
+                s.strip_prefix(prefix)
+                    .unwrap_or(s)
+                    .parse::<usize>()
+                    .map_err(|_| invalid(format!("bad index {:?} in {:?}", s, pair)))
+            };
+            Ok((index(a_out, "a_out")?, index(b_in, "b_in")?))
+        })
+        .collect()
+}
+
+/// Compose two v5a files, writing the result as v5a.
+pub async fn compose_files(
+    a: impl AsRef<Path>,
+    b: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    map: &[(usize, usize)],
+) -> Result<Circuit> {
+    let composed = compose(&Circuit::read(a).await?, &Circuit::read(b).await?, map)?;
+    composed.write(output).await?;
+    Ok(composed)
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credits::validate_credits;
+    use crate::exec::exec_compact;
+    use adder::generate_adder;
+
+    fn adder(n_bits: usize) -> Circuit {
+        let circuit = generate_adder(n_bits);
+        Circuit {
+            primary_inputs: circuit.primary_inputs,
+            gates: circuit.gates,
+            outputs: circuit.outputs,
+        }
+    }
+
+    fn to_bits(value: u64, n: usize) -> Vec<bool> {
+        (0..n).map(|i| (value >> i) & 1 == 1).collect()
+    }
+
+    async fn run(circuit: &Circuit, inputs: &[bool]) -> Vec<bool> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("composed.v5a");
+        circuit.write(&path).await.unwrap();
+        exec_compact(path.to_str().unwrap(), inputs)
+            .await
+            .unwrap()
+            .outputs
+    }
+
+    #[monoio::test]
+    async fn test_two_adders_compose_into_four_operand_adder() {
+        let n = 8;
+
+        // Two adders side by side sum x + y and z + w, then a wider adder
+        // takes both sums as its operands
+        let pair = compose(&adder(n), &adder(n), &[]).unwrap();
+        assert_eq!(pair.primary_inputs, 4 * n as u64);
+        assert_eq!(pair.outputs.len(), 2 * (n + 1));
+        let map: Vec<(usize, usize)> = (0..2 * (n + 1)).map(|i| (i, i)).collect();
+        let sum4 = compose(&pair, &adder(n + 1), &map).unwrap();
+
+        assert_eq!(sum4.primary_inputs, 4 * n as u64);
+        assert_eq!(sum4.outputs.len(), n + 2);
+        assert_eq!(sum4.gates.len(), 5 * (3 * n + 1));
+        assert_eq!(
+            validate_credits(sum4.primary_inputs, &sum4.outputs, &sum4.gates),
+            vec![]
+        );
+
+        for operands in [
+            [0, 0, 0, 0],
+            [255, 255, 255, 255],
+            [1, 2, 3, 4],
+            [200, 99, 17, 180],
+        ] {
+            let inputs: Vec<bool> = operands.iter().flat_map(|&x| to_bits(x, n)).collect();
+            let sum = operands.iter().sum::<u64>();
+            assert_eq!(
+                run(&sum4, &inputs).await,
+                to_bits(sum, n + 2),
+                "{:?}",
+                operands
+            );
+        }
+    }
+
+    #[monoio::test]
+    async fn test_unfed_inputs_become_primary_inputs() {
+        let n = 4;
+
+        // Only B's first operand comes from A; its second is a new input
+        let map: Vec<(usize, usize)> = (0..n + 1).map(|i| (i, i)).collect();
+        let sum3 = compose(&adder(n), &adder(n + 1), &map).unwrap();
+        assert_eq!(sum3.primary_inputs, (2 * n + n + 1) as u64);
+        assert_eq!(sum3.outputs.len(), n + 2);
+
+        // The third operand takes all n + 1 bits of B's second input
+        for (x, y, z) in [(0u64, 0u64, 0u64), (15, 15, 31), (9, 3, 20)] {
+            let mut inputs = to_bits(x, n);
+            inputs.extend(to_bits(y, n));
+            inputs.extend(to_bits(z, n + 1));
+            assert_eq!(
+                run(&sum3, &inputs).await,
+                to_bits(x + y + z, n + 2),
+                "{} + {} + {}",
+                x,
+                y,
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_maps_are_rejected() {
+        let (a, b) = (adder(2), adder(2));
+        assert!(compose(&a, &b, &[(3, 0)]).is_err());
+        assert!(compose(&a, &b, &[(0, 4)]).is_err());
+        assert!(compose(&a, &b, &[(0, 1), (2, 1)]).is_err());
+
+        assert_eq!(
+            parse_wire_map("a_out0:b_in1, 2:3").unwrap(),
+            vec![(0, 1), (2, 3)]
+        );
+        assert!(parse_wire_map("0-1").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Garbling cost estimates from circuit gate counts.
+//!
+//! The garbler uses privacy-free half-gates with free-XOR, so XOR gates cost
+//! one label XOR and produce nothing, while every AND gate costs two
+//! correlation-robust hash calls and emits a single 16-byte ciphertext. Each
+//! hash is one AES block encryption with CCRND and two with TMMO. Cost is
+//! therefore dominated by the AND count.
+
+use std::io::Result;
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::c;
+use ckt_gobble::HashScheme;
+
+use crate::info::file_info;
+
+/// Hash calls performed to garble one AND gate.
+pub const HASHES_PER_AND_GATE: u64 = 2;
+
+/// AES block encryptions performed to garble one AND gate with `scheme`.
+pub fn aes_ops_per_and_gate(scheme: HashScheme) -> u64 {
+    HASHES_PER_AND_GATE * scheme.aes_calls()
+}
+
+/// Ciphertexts emitted when garbling one AND gate.
+pub const CIPHERTEXTS_PER_AND_GATE: u64 = c::CIPHERTEXTS_PER_AND_GATE as u64;
+
+/// Size of a single garbled ciphertext in bytes.
+pub const CIPHERTEXT_SIZE: u64 = c::CIPHERTEXT_SIZE as u64;
+
+/// Estimated garbling cost for a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GarblingCost {
+    /// Hash scheme the AES operations are counted for.
+    pub hash_scheme: HashScheme,
+    /// Number of XOR gates.
+    pub xor_gates: u64,
+    /// Number of AND gates.
+    pub and_gates: u64,
+    /// AES operations with free-XOR (AND gates only).
+    pub aes_ops: u64,
+    /// Ciphertext bytes with free-XOR (AND gates only).
+    pub ciphertext_bytes: u64,
+    /// AES operations if every gate were garbled like an AND gate.
+    pub naive_aes_ops: u64,
+    /// Ciphertext bytes if every gate were garbled like an AND gate.
+    pub naive_ciphertext_bytes: u64,
+}
+
+impl GarblingCost {
+    /// Estimate the cost from XOR and AND gate counts when garbling with
+    /// `hash_scheme`.
+    pub fn from_counts(xor_gates: u64, and_gates: u64, hash_scheme: HashScheme) -> Self {
+        let total_gates = xor_gates + and_gates;
+        let per_gate_aes_ops = aes_ops_per_and_gate(hash_scheme);
+        let per_gate_bytes = CIPHERTEXTS_PER_AND_GATE * CIPHERTEXT_SIZE;
+        Self {
+            hash_scheme,
+            xor_gates,
+            and_gates,
+            aes_ops: and_gates * per_gate_aes_ops,
+            ciphertext_bytes: and_gates * per_gate_bytes,
+            naive_aes_ops: total_gates * per_gate_aes_ops,
+            naive_ciphertext_bytes: total_gates * per_gate_bytes,
+        }
+    }
+
+    /// Fraction of gates that are XOR, in `[0, 1]`.
+    pub fn xor_fraction(&self) -> f64 {
+        let total = self.xor_gates + self.and_gates;
+        if total == 0 {
+            0.0
+        } else {
+            self.xor_gates as f64 / total as f64
+        }
+    }
+
+    /// How many times cheaper free-XOR garbling is than the naive baseline.
+    pub fn savings_factor(&self) -> f64 {
+        if self.aes_ops == 0 {
+            0.0
+        } else {
+            self.naive_aes_ops as f64 / self.aes_ops as f64
+        }
+    }
+}
+
+/// Estimate the garbling cost of a v5a or v5c file from its header.
+pub fn estimate_file(path: impl AsRef<Path>, hash_scheme: HashScheme) -> Result<GarblingCost> {
+    let counts = file_info(path)?.gate_counts;
+    Ok(GarblingCost::from_counts(
+        counts.xor,
+        counts.and,
+        hash_scheme,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_gates_aes_ops() {
+        let cost = GarblingCost::from_counts(3000, 1000, HashScheme::Ccrnd);
+        assert_eq!(cost.aes_ops, 2000);
+        assert_eq!(cost.ciphertext_bytes, 16_000);
+        assert_eq!(cost.naive_aes_ops, 8000);
+        assert_eq!(cost.naive_ciphertext_bytes, 64_000);
+        assert_eq!(cost.xor_fraction(), 0.75);
+        assert_eq!(cost.savings_factor(), 4.0);
+    }
+
+    #[test]
+    fn test_tmmo_doubles_aes_ops() {
+        assert_eq!(aes_ops_per_and_gate(HashScheme::Ccrnd), 2);
+        assert_eq!(aes_ops_per_and_gate(HashScheme::Tmmo), 4);
+
+        let cost = GarblingCost::from_counts(3000, 1000, HashScheme::Tmmo);
+        assert_eq!(cost.aes_ops, 4000);
+        assert_eq!(cost.naive_aes_ops, 16_000);
+        // Ciphertexts don't depend on the hash
+        assert_eq!(cost.ciphertext_bytes, 16_000);
+        assert_eq!(cost.savings_factor(), 4.0);
+    }
+
+    #[test]
+    fn test_xor_only_circuit_is_free() {
+        let cost = GarblingCost::from_counts(10, 0, HashScheme::Ccrnd);
+        assert_eq!(cost.aes_ops, 0);
+        assert_eq!(cost.ciphertext_bytes, 0);
+        assert_eq!(cost.savings_factor(), 0.0);
+    }
+}
@@ -0,0 +1,278 @@
+//! Credit consistency checking for v5a circuits.
+//!
+//! Preallocation frees a wire's slot once its credits hit zero, so a wire
+//! whose claimed credits differ from the number of gates that actually read
+//! it is either freed too early (and later read from a recycled slot) or
+//! never freed at all. This tallies real fan-out per wire and compares it
+//! against the claimed credits. Output wires are never freed, so they must
+//! claim [`CREDITS_OUTPUT`] instead, however often they are read.
+
+use std::io::Result;
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::a::{reader::CircuitReaderV5a, GateV5a, CREDITS_OUTPUT};
+use roaring::RoaringTreemap;
+
+use crate::prealloc::AbsoluteWireId;
+
+/// A wire whose claimed credits don't match how often it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CreditMismatch {
+    /// Wire ID of the gate output.
+    pub wire: AbsoluteWireId,
+    /// Credits stored on the gate producing the wire.
+    pub claimed: u32,
+    /// Number of gate inputs that actually read the wire.
+    pub actual: u32,
+    /// Whether the wire is a circuit output, which should claim
+    /// [`CREDITS_OUTPUT`] rather than its fan-out.
+    pub output: bool,
+}
+
+/// Per-wire `u32` counter stored as one bitmap per bit of the count.
+///
+/// Wire IDs are dense, so the bitmaps stay compressed where a hash map
+/// would spend a full entry on every wire of a multi-billion gate circuit.
+#[derive(Debug, Default)]
+struct WireCounter {
+    // Bit k of a wire's count is set iff the wire is in planes[k]
+    planes: Vec<RoaringTreemap>,
+}
+
+impl WireCounter {
+    fn get(&self, wire: AbsoluteWireId) -> u32 {
+        self.planes
+            .iter()
+            .enumerate()
+            .filter(|(_, plane)| plane.contains(wire))
+            .fold(0, |count, (bit, _)| count | 1 << bit)
+    }
+
+    fn set(&mut self, wire: AbsoluteWireId, count: u32) {
+        let bits = (u32::BITS - count.leading_zeros()) as usize;
+        if self.planes.len() < bits {
+            self.planes.resize_with(bits, RoaringTreemap::new);
+        }
+        for (bit, plane) in self.planes.iter_mut().enumerate() {
+            if count >> bit & 1 == 1 {
+                plane.insert(wire);
+            } else {
+                plane.remove(wire);
+            }
+        }
+    }
+
+    fn increment(&mut self, wire: AbsoluteWireId) {
+        // Ripple the carry up through the planes
+        for plane in &mut self.planes {
+            if plane.insert(wire) {
+                return;
+            }
+            plane.remove(wire);
+        }
+        let mut plane = RoaringTreemap::new();
+        plane.insert(wire);
+        self.planes.push(plane);
+    }
+}
+
+/// Streaming checker comparing claimed credits with actual fan-out.
+///
+/// Constants and primary inputs are never freed and so are excluded from
+/// the check.
+#[derive(Debug)]
+pub struct CreditChecker {
+    primary_inputs: u64,
+    outputs: RoaringTreemap,
+    // Wires produced by a gate, whatever their credits
+    produced: RoaringTreemap,
+    claimed: WireCounter,
+    actual: WireCounter,
+}
+
+impl CreditChecker {
+    /// Create a checker for a circuit with `primary_inputs` inputs and the
+    /// given output wires.
+    pub fn new(primary_inputs: u64, outputs: &[AbsoluteWireId]) -> Self {
+        Self {
+            primary_inputs,
+            outputs: outputs.iter().copied().collect(),
+            produced: RoaringTreemap::new(),
+            claimed: WireCounter::default(),
+            actual: WireCounter::default(),
+        }
+    }
+
+    /// Record a gate, in file order.
+    pub fn feed_gate(
+        &mut self,
+        in1: AbsoluteWireId,
+        in2: AbsoluteWireId,
+        out: AbsoluteWireId,
+        credits: u32,
+    ) {
+        self.record_use(in1);
+        self.record_use(in2);
+        self.produced.insert(out);
+        self.claimed.set(out, credits);
+    }
+
+    /// Return every mismatching wire, sorted by wire ID.
+    pub fn finish(self) -> Vec<CreditMismatch> {
+        // Reads of wires no gate produced still count as fan-out
+        let mut wires = self.produced.clone();
+        for plane in &self.actual.planes {
+            wires |= plane;
+        }
+        wires
+            .iter()
+            .map(|wire| CreditMismatch {
+                wire,
+                claimed: self.claimed.get(wire),
+                actual: self.actual.get(wire),
+                output: self.outputs.contains(wire),
+            })
+            .filter(|m| {
+                let expected = if m.output { CREDITS_OUTPUT } else { m.actual };
+                m.claimed != expected
+            })
+            .collect()
+    }
+
+    fn record_use(&mut self, wire: AbsoluteWireId) {
+        if wire >= self.primary_inputs + 2 {
+            self.actual.increment(wire);
+        }
+    }
+}
+
+/// Check an in-memory gate list, returning every mismatching wire.
+pub fn validate_credits<'a>(
+    primary_inputs: u64,
+    outputs: &[AbsoluteWireId],
+    gates: impl IntoIterator<Item = &'a GateV5a>,
+) -> Vec<CreditMismatch> {
+    let mut checker = CreditChecker::new(primary_inputs, outputs);
+    for g in gates {
+        checker.feed_gate(g.in1, g.in2, g.out, g.credits);
+    }
+    checker.finish()
+}
+
+/// Stream a v5a file once, returning every mismatching wire.
+pub async fn check_file(path: impl AsRef<Path>) -> Result<Vec<CreditMismatch>> {
+    let mut reader = CircuitReaderV5a::open(path)?;
+    let mut checker = CreditChecker::new(reader.header().primary_inputs, reader.outputs());
+    while let Some(block) = reader.next_block_soa().await? {
+        for g in block.gates() {
+            checker.feed_gate(g.in1, g.in2, g.out, g.credits);
+        }
+    }
+    Ok(checker.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::generate_adder;
+
+    #[test]
+    fn test_wire_counter_carries_across_planes() {
+        let mut counter = WireCounter::default();
+        for _ in 0..300 {
+            counter.increment(7);
+        }
+        counter.increment(1 << 40);
+        assert_eq!(counter.get(7), 300);
+        assert_eq!(counter.get(1 << 40), 1);
+        assert_eq!(counter.get(8), 0);
+
+        counter.set(7, 2);
+        counter.set(9, CREDITS_OUTPUT);
+        assert_eq!(counter.get(7), 2);
+        assert_eq!(counter.get(9), CREDITS_OUTPUT);
+        counter.set(9, u32::MAX);
+        assert_eq!(counter.get(9), u32::MAX);
+    }
+
+    #[test]
+    fn test_adder_credits_match_fanout() {
+        for n_bits in [1, 8, 64] {
+            let circuit = generate_adder(n_bits);
+            assert_eq!(
+                validate_credits(circuit.primary_inputs, &circuit.outputs, &circuit.gates),
+                vec![],
+                "{}-bit adder",
+                n_bits
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrong_credits_are_reported() {
+        let mut circuit = generate_adder(4);
+        // half_sum of bit 0 feeds two gates; claim three
+        let wire = circuit.gates[0].out;
+        circuit.gates[0].credits = 3;
+
+        let mut checker = CreditChecker::new(circuit.primary_inputs, &circuit.outputs);
+        for g in &circuit.gates {
+            checker.feed_gate(g.in1, g.in2, g.out, g.credits);
+        }
+        assert_eq!(
+            checker.finish(),
+            vec![CreditMismatch {
+                wire,
+                claimed: 3,
+                actual: 2,
+                output: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_output_wires_must_claim_output_credits() {
+        let mut circuit = generate_adder(4);
+        // The final carry is an output; give it credits as if it were read once
+        let last = circuit.gates.len() - 1;
+        let wire = circuit.gates[last].out;
+        assert!(circuit.outputs.contains(&wire));
+        circuit.gates[last].credits = 1;
+
+        assert_eq!(
+            validate_credits(circuit.primary_inputs, &circuit.outputs, &circuit.gates),
+            vec![CreditMismatch {
+                wire,
+                claimed: 1,
+                actual: 0,
+                output: true,
+            }]
+        );
+        // Without knowing the outputs, the same wire looks like a fan-out mismatch
+        assert_eq!(
+            validate_credits(circuit.primary_inputs, &[], &circuit.gates)[0].wire,
+            wire
+        );
+    }
+
+    #[monoio::test]
+    async fn test_check_file_on_generated_adder() {
+        let mut circuit = generate_adder(100);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5a");
+
+        // Spans several v5a blocks
+        assert!(circuit.gates.len() > 256);
+        write_v5a(&circuit, &path).await;
+        assert_eq!(check_file(&path).await.unwrap(), vec![]);
+
+        let last = circuit.gates.len() - 10;
+        circuit.gates[last].credits += 1;
+        let wire = circuit.gates[last].out;
+        write_v5a(&circuit, &path).await;
+        let mismatches = check_file(&path).await.unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].wire, wire);
+    }
+}
@@ -0,0 +1,112 @@
+//! Dead gate detection for v5a circuits.
+//!
+//! A gate whose output is never read by a later gate and isn't a circuit
+//! output still costs a ciphertext (if AND) and a slot, but contributes
+//! nothing. Such circuits are valid, so nothing else rejects them; this
+//! streams the circuit once and reports the wires of those gates. Only
+//! directly dead gates are found: a gate feeding nothing but dead gates
+//! counts as live here, though [`minimize`](crate::minimize) removes both.
+
+use std::io::Result;
+use std::path::Path;
+
+use ahash::{HashSet, HashSetExt};
+use ckt_fmtv5_types::v5::a::{reader::CircuitReaderV5a, GateV5a};
+
+use crate::prealloc::AbsoluteWireId;
+
+/// Streaming finder of gates whose outputs are never used.
+#[derive(Debug)]
+pub struct DeadGateFinder {
+    outputs: HashSet<AbsoluteWireId>,
+    // Gate outputs not read by any gate seen so far
+    unread: HashSet<AbsoluteWireId>,
+}
+
+impl DeadGateFinder {
+    /// Create a finder for a circuit with the given output wires.
+    pub fn new(outputs: impl IntoIterator<Item = AbsoluteWireId>) -> Self {
+        Self {
+            outputs: outputs.into_iter().collect(),
+            unread: HashSet::new(),
+        }
+    }
+
+    /// Record a gate, in file order.
+    pub fn feed_gate(&mut self, in1: AbsoluteWireId, in2: AbsoluteWireId, out: AbsoluteWireId) {
+        self.unread.remove(&in1);
+        self.unread.remove(&in2);
+        self.unread.insert(out);
+    }
+
+    /// Return the output wire of every dead gate, sorted by wire ID.
+    pub fn finish(self) -> Vec<AbsoluteWireId> {
+        let mut dead: Vec<AbsoluteWireId> = self
+            .unread
+            .into_iter()
+            .filter(|wire| !self.outputs.contains(wire))
+            .collect();
+        dead.sort_unstable();
+        dead
+    }
+}
+
+/// Find dead gates in an in-memory gate list.
+pub fn find_dead_gates<'a>(
+    gates: impl IntoIterator<Item = &'a GateV5a>,
+    outputs: &[AbsoluteWireId],
+) -> Vec<AbsoluteWireId> {
+    let mut finder = DeadGateFinder::new(outputs.iter().copied());
+    for g in gates {
+        finder.feed_gate(g.in1, g.in2, g.out);
+    }
+    finder.finish()
+}
+
+/// Stream a v5a file once, returning the output wire of every dead gate.
+pub async fn check_file(path: impl AsRef<Path>) -> Result<Vec<AbsoluteWireId>> {
+    let mut reader = CircuitReaderV5a::open(path)?;
+    let mut finder = DeadGateFinder::new(reader.outputs().iter().copied());
+    while let Some(block) = reader.next_block_soa().await? {
+        for g in block.gates() {
+            finder.feed_gate(g.in1, g.in2, g.out);
+        }
+    }
+    Ok(finder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::generate_adder;
+    use ckt_fmtv5_types::GateType;
+
+    #[test]
+    fn test_adder_has_no_dead_gates() {
+        let circuit = generate_adder(8);
+        assert_eq!(find_dead_gates(&circuit.gates, &circuit.outputs), vec![]);
+    }
+
+    #[monoio::test]
+    async fn test_check_file_finds_dead_gate() {
+        let mut circuit = generate_adder(100);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5a");
+
+        // Read the final carry once more into a gate nobody reads
+        let next_wire = circuit.gates.iter().map(|g| g.out).max().unwrap() + 1;
+        let carry = *circuit.outputs.last().unwrap();
+        circuit.gates.push(GateV5a {
+            in1: carry,
+            in2: 2,
+            out: next_wire,
+            credits: 1,
+            gate_type: GateType::AND,
+        });
+
+        write_v5a(&circuit, &path).await;
+
+        assert_eq!(check_file(&path).await.unwrap(), vec![next_wire]);
+    }
+}
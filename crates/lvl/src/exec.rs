@@ -0,0 +1,280 @@
+//! Compact-state cleartext execution of v5a circuits.
+//!
+//! A full-state executor keeps one value per wire ID, which for large
+//! feed-forward circuits is mostly wires that died long ago. Here we use the
+//! credits stored in v5a gates to keep only live wire values, recycling slots
+//! through [`FakeSlabAllocator`] the same way [`prealloc`](crate::prealloc)
+//! assigns v5c addresses. Memory use is bounded by the peak number of
+//! concurrently live wires rather than the total wire count.
+
+use std::io::{Error, ErrorKind, Result};
+
+use ahash::HashMapExt;
+use ckt_fmtv5_types::v5::a::reader::CircuitReaderV5a;
+use ckt_fmtv5_types::v5::a::CREDITS_CONSTANT;
+use ckt_fmtv5_types::GateType;
+
+use crate::prealloc::{lookup_wire, AbsoluteWireId, WireEntry, WireMap};
+use crate::slab::FakeSlabAllocator;
+
+/// Result of a compact-state execution.
+#[derive(Debug, Clone)]
+pub struct CompactExecResult {
+    /// Output values in the order listed in the circuit header.
+    pub outputs: Vec<bool>,
+    /// Peak number of wire slots held at once, including constants and inputs.
+    pub max_live_wires: usize,
+}
+
+/// Executor state holding only the values of currently live wires.
+#[derive(Debug)]
+pub struct CompactExecutor {
+    slab: FakeSlabAllocator,
+    wire_map: WireMap,
+    values: Vec<bool>,
+    primary_inputs: u64,
+}
+
+impl CompactExecutor {
+    /// Create an executor with the constants and primary inputs loaded.
+    ///
+    /// Wires 0 and 1 are the false and true constants, and `inputs[i]` is the
+    /// value of wire `i + 2`.
+    pub fn new(inputs: &[bool]) -> Self {
+        let primary_inputs = inputs.len() as u64;
+        let mut slab = FakeSlabAllocator::new();
+        for _ in 0..primary_inputs + 2 {
+            slab.allocate();
+        }
+
+        let mut values = Vec::with_capacity(inputs.len() + 2);
+        values.push(false);
+        values.push(true);
+        values.extend_from_slice(inputs);
+
+        Self {
+            slab,
+            wire_map: WireMap::new(),
+            values,
+            primary_inputs,
+        }
+    }
+
+    /// Evaluate a single gate, consuming one credit from each input wire.
+    ///
+    /// Fails if an input wire isn't live, i.e. it was never produced or its
+    /// credits were already exhausted.
+    pub fn eval_gate(
+        &mut self,
+        in1: AbsoluteWireId,
+        in2: AbsoluteWireId,
+        out: AbsoluteWireId,
+        credits: u32,
+        gate_type: GateType,
+    ) -> Result<()> {
+        let a = self.consume(in1)?;
+        let b = self.consume(in2)?;
+        let value = match gate_type {
+            GateType::XOR => a ^ b,
+            GateType::AND => a & b,
+        };
+
+        let slab_idx = self.slab.allocate();
+        if slab_idx >= self.values.len() {
+            self.values.resize(slab_idx + 1, false);
+        }
+        self.values[slab_idx] = value;
+        self.wire_map.insert(
+            out,
+            WireEntry {
+                slab_idx,
+                credits_remaining: credits,
+            },
+        );
+        Ok(())
+    }
+
+    /// Read the value of a live wire without consuming a credit.
+    pub fn wire_value(&mut self, wire: AbsoluteWireId) -> Option<bool> {
+        lookup_wire::<true>(
+            &mut self.wire_map,
+            &mut self.slab,
+            wire,
+            self.primary_inputs,
+        )
+        .map(|idx| self.values[idx])
+    }
+
+    /// Current credit count of a wire, for checking credit accounting.
+    ///
+    /// Constants and primary inputs report [`CREDITS_CONSTANT`]. Returns
+    /// `None` if the wire was never produced or its last credit was spent.
+    pub fn credits_remaining(&self, wire: AbsoluteWireId) -> Option<u32> {
+        if wire < self.primary_inputs + 2 {
+            return Some(CREDITS_CONSTANT);
+        }
+        self.wire_map.get(&wire).map(|e| e.credits_remaining)
+    }
+
+    /// Peak number of wire slots held at once.
+    pub fn max_live_wires(&self) -> usize {
+        self.slab.max_allocated_concurrently()
+    }
+
+    fn consume(&mut self, wire: AbsoluteWireId) -> Result<bool> {
+        let idx = lookup_wire::<false>(
+            &mut self.wire_map,
+            &mut self.slab,
+            wire,
+            self.primary_inputs,
+        )
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("wire {} is not live", wire)))?;
+        Ok(self.values[idx])
+    }
+}
+
+/// Execute a v5a circuit, keeping only live wire values in memory.
+///
+/// `inputs` must have exactly `primary_inputs` entries.
+pub async fn exec_compact(input: &str, inputs: &[bool]) -> Result<CompactExecResult> {
+    let mut reader = CircuitReaderV5a::open(input)?;
+    let header = reader.header();
+    if inputs.len() as u64 != header.primary_inputs {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "expected {} inputs, got {}",
+                header.primary_inputs,
+                inputs.len()
+            ),
+        ));
+    }
+
+    let mut executor = CompactExecutor::new(inputs);
+
+    while let Some(block) = reader.next_block_soa().await? {
+        for i in 0..block.gates_in_block {
+            executor.eval_gate(
+                block.in1[i],
+                block.in2[i],
+                block.out[i],
+                block.credits[i],
+                block.gate_types[i],
+            )?;
+        }
+    }
+
+    let outputs = reader
+        .outputs()
+        .iter()
+        .map(|&wire| {
+            executor.wire_value(wire).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("output wire {} is not live", wire),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CompactExecResult {
+        outputs,
+        max_live_wires: executor.max_live_wires(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::generate_adder;
+    use ckt_fmtv5_types::v5::a::GateV5a;
+
+    /// Reference executor holding one value per wire ID.
+    fn exec_full(
+        primary_inputs: u64,
+        gates: &[GateV5a],
+        outputs: &[u64],
+        inputs: &[bool],
+    ) -> Vec<bool> {
+        let max_wire = gates.iter().map(|g| g.out).max().unwrap_or(0);
+        let mut state = vec![false; (max_wire + 1).max(primary_inputs + 2) as usize];
+        state[1] = true;
+        state[2..2 + inputs.len()].copy_from_slice(inputs);
+
+        for g in gates {
+            let a = state[g.in1 as usize];
+            let b = state[g.in2 as usize];
+            state[g.out as usize] = match g.gate_type {
+                GateType::XOR => a ^ b,
+                GateType::AND => a & b,
+            };
+        }
+
+        outputs.iter().map(|&o| state[o as usize]).collect()
+    }
+
+    fn to_bits(value: u64, n: usize) -> Vec<bool> {
+        (0..n).map(|i| (value >> i) & 1 == 1).collect()
+    }
+
+    #[monoio::test]
+    async fn test_compact_matches_full_state_adder() {
+        let n_bits = 16;
+        let circuit = generate_adder(n_bits);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5a");
+        write_v5a(&circuit, &path).await;
+
+        for (a, b) in [(0u64, 0u64), (1, 1), (12345, 54321), (0xFFFF, 0xFFFF)] {
+            let mut inputs = to_bits(a, n_bits);
+            inputs.extend(to_bits(b, n_bits));
+
+            let full = exec_full(
+                circuit.primary_inputs,
+                &circuit.gates,
+                &circuit.outputs,
+                &inputs,
+            );
+            let compact = exec_compact(path.to_str().unwrap(), &inputs).await.unwrap();
+
+            assert_eq!(compact.outputs, full, "mismatch for {} + {}", a, b);
+            assert_eq!(compact.outputs, to_bits(a + b, n_bits + 1));
+
+            // Compact state should hold far fewer wires than the circuit has
+            let total_wires = 2 + circuit.primary_inputs as usize + circuit.gates.len();
+            assert!(compact.max_live_wires < total_wires);
+        }
+    }
+
+    #[test]
+    fn test_dead_wire_is_rejected() {
+        let mut exec = CompactExecutor::new(&[true, false]);
+        // Wire 4 has one credit, so the second use must fail
+        exec.eval_gate(2, 3, 4, 1, GateType::XOR).unwrap();
+        exec.eval_gate(4, 2, 5, 1, GateType::AND).unwrap();
+        assert!(exec.eval_gate(4, 2, 6, 1, GateType::AND).is_err());
+        assert_eq!(exec.wire_value(5), Some(true));
+    }
+
+    #[test]
+    fn test_credits_decrement_with_fanout() {
+        let mut exec = CompactExecutor::new(&[true, true]);
+        assert_eq!(exec.credits_remaining(2), Some(CREDITS_CONSTANT));
+        assert_eq!(exec.credits_remaining(4), None);
+
+        // Wire 4 feeds three gates
+        exec.eval_gate(2, 3, 4, 3, GateType::AND).unwrap();
+        assert_eq!(exec.credits_remaining(4), Some(3));
+
+        for (out, expected) in [(5, Some(2)), (6, Some(1)), (7, None)] {
+            exec.eval_gate(4, 2, out, 1, GateType::XOR).unwrap();
+            assert_eq!(exec.credits_remaining(4), expected);
+        }
+
+        // Once credits run out the slot is recycled
+        assert_eq!(exec.credits_remaining(5), Some(1));
+        assert!(exec.eval_gate(4, 2, 8, 1, GateType::XOR).is_err());
+    }
+}
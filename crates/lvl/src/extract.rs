@@ -0,0 +1,146 @@
+//! Dump v5c circuits to Bristol-like text for debugging.
+//!
+//! v5c gates name memory addresses rather than wires, so the dump uses the
+//! addresses as wire IDs. The layout follows Bristol Fashion:
+//!
+//! ```text
+//! <gates> <scratch_space>
+//! 1 <primary_inputs>
+//! 1 <num_outputs>
+//!
+//! 2 1 <in1> <in2> <out> XOR|AND
+//! ...
+//!
+//! <output address> ...
+//! ```
+//!
+//! with the wire count replaced by the scratch space and the inputs at
+//! addresses `2..2 + primary_inputs`, after the false and true constants.
+//! Bristol outputs are always the last wires, which addresses are not, so a
+//! final line lists the output addresses.
+//!
+//! Addresses are reused once a value dies, so the dump is not single
+//! assignment and can't be converted back. It is still topologically valid:
+//! evaluated line by line, every gate reads the values its inputs hold at
+//! that point, exactly as when executing the v5c file.
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::c::ReaderV5c;
+use ckt_fmtv5_types::GateType;
+
+/// Write `input` (v5c) to `output` as Bristol-like text, in execution order.
+///
+/// Returns the number of gates written.
+pub async fn v5c_to_bristol(input: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<u64> {
+    let mut reader = ReaderV5c::open(input)?;
+    let header = *reader.header();
+    let total_gates = header.total_gates();
+
+    let mut out = BufWriter::new(File::create(output)?);
+    writeln!(out, "{} {}", total_gates, header.scratch_space)?;
+    writeln!(out, "1 {}", header.primary_inputs)?;
+    writeln!(out, "1 {}", header.num_outputs)?;
+    writeln!(out)?;
+
+    let mut block_index = 0;
+    while let Some(chunk) = reader.next_blocks_chunk().await? {
+        for block in chunk.blocks_iter() {
+            for g in block.iter_gates(header.block_num_gates(block_index)) {
+                let name = match g.gate_type {
+                    GateType::XOR => "XOR",
+                    GateType::AND => "AND",
+                };
+                writeln!(
+                    out,
+                    "2 1 {} {} {} {}",
+                    g.gate.in1, g.gate.in2, g.gate.out, name
+                )?;
+            }
+            block_index += 1;
+        }
+    }
+
+    let outputs: Vec<String> = reader.outputs().iter().map(u32::to_string).collect();
+    writeln!(out)?;
+    writeln!(out, "{}", outputs.join(" "))?;
+    out.flush()?;
+    Ok(total_gates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::generate_adder;
+
+    fn to_bits(value: u64, n: usize) -> Vec<bool> {
+        (0..n).map(|i| (value >> i) & 1 == 1).collect()
+    }
+
+    /// Evaluate a dump by treating its wire IDs as memory addresses.
+    fn eval_dump(text: &str, inputs: &[bool]) -> Vec<bool> {
+        let mut lines = text.lines();
+        let numbers = |line: &str| -> Vec<u64> {
+            line.split_whitespace()
+                .map(|t| t.parse().unwrap())
+                .collect()
+        };
+        let [num_gates, scratch_space] = numbers(lines.next().unwrap())[..] else {
+            panic!("bad header");
+        };
+        assert_eq!(numbers(lines.next().unwrap()), [1, inputs.len() as u64]);
+        let num_outputs = numbers(lines.next().unwrap())[1];
+        assert_eq!(lines.next(), Some(""));
+
+        let mut memory = vec![false; scratch_space as usize];
+        memory[1] = true;
+        memory[2..2 + inputs.len()].copy_from_slice(inputs);
+        for _ in 0..num_gates {
+            let tokens: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+            let [in1, in2, out] = [2, 3, 4].map(|i| tokens[i].parse::<usize>().unwrap());
+            memory[out] = match tokens[5] {
+                "XOR" => memory[in1] ^ memory[in2],
+                "AND" => memory[in1] & memory[in2],
+                other => panic!("unexpected gate {}", other),
+            };
+        }
+
+        assert_eq!(lines.next(), Some(""));
+        let outputs = numbers(lines.next().unwrap());
+        assert_eq!(outputs.len() as u64, num_outputs);
+        assert_eq!(lines.next(), None);
+        outputs.iter().map(|&o| memory[o as usize]).collect()
+    }
+
+    #[monoio::test]
+    async fn test_dump_evaluates_like_the_circuit() {
+        let n_bits = 8;
+        let circuit = generate_adder(n_bits);
+        let dir = tempfile::tempdir().unwrap();
+        let v5a = dir.path().join("adder.v5a");
+        let v5c = dir.path().join("adder.v5c");
+        let dump = dir.path().join("adder.bristol");
+
+        write_v5a(&circuit, &v5a).await;
+        crate::prealloc::prealloc(v5a.to_str().unwrap(), v5c.to_str().unwrap()).await;
+
+        let written = v5c_to_bristol(&v5c, &dump).await.unwrap();
+        assert_eq!(written, circuit.gates.len() as u64);
+
+        let text = std::fs::read_to_string(&dump).unwrap();
+        for (a, b) in [(0u64, 0u64), (1, 1), (200, 99), (0xFF, 0xFF)] {
+            let mut inputs = to_bits(a, n_bits);
+            inputs.extend(to_bits(b, n_bits));
+            assert_eq!(
+                eval_dump(&text, &inputs),
+                to_bits(a + b, n_bits + 1),
+                "{} + {}",
+                a,
+                b
+            );
+        }
+    }
+}
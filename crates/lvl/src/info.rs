@@ -0,0 +1,278 @@
+//! Format detection, header summaries and checksum checks for circuit files.
+//!
+//! Every v5 file starts with [`MAGIC`], a version byte and a format type
+//! byte, and v5a additionally flags delta-encoded wires in byte 6. Reading
+//! those first bytes is enough to pick the right header parser or checksum
+//! routine.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::{
+    a::{parse_header_with_flags, reader::verify_v5a_checksum, FLAG_DELTA_WIRES, HEADER_SIZE_V5A},
+    c::{
+        estimate_garbling, verify_v5c_checksum, verify_v5c_checksum_and_count, GarbleEstimate,
+        HeaderV5c, HEADER_SIZE,
+    },
+    FormatType, GateCounts, MAGIC,
+};
+
+/// Circuit format detected from a file's first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedFormat {
+    /// v5a with fixed-width wire IDs.
+    V5a,
+    /// v5a with delta-encoded wire IDs.
+    V5aDelta,
+    /// v5c with preallocated memory addresses.
+    V5c,
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DetectedFormat::V5a => "v5a",
+            DetectedFormat::V5aDelta => "v5a (delta-encoded)",
+            DetectedFormat::V5c => "v5c",
+        })
+    }
+}
+
+/// Header summary of a circuit file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileInfo {
+    /// Detected format.
+    pub format: DetectedFormat,
+    /// Version byte from the header.
+    pub version: u8,
+    /// XOR, AND and total gate counts.
+    pub gate_counts: GateCounts,
+    /// Number of primary inputs.
+    pub primary_inputs: u64,
+    /// Number of outputs.
+    pub num_outputs: u64,
+    /// Scratch space slots, only known once addresses are preallocated (v5c).
+    pub scratch_space: Option<u64>,
+    /// Garbled output size, estimated for v5c only as that's what gets garbled.
+    pub garble_estimate: Option<GarbleEstimate>,
+    /// File size in bytes.
+    pub file_size: u64,
+}
+
+/// Identify a circuit's format from its magic, version and format bytes.
+pub fn detect_format(path: impl AsRef<Path>) -> Result<DetectedFormat> {
+    let mut prefix = [0u8; 8];
+    let mut file = File::open(path)?;
+    let len = read_prefix(&mut file, &mut prefix)?;
+    if len < 6 || prefix[0..4] != MAGIC {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "not a v5 circuit (bad magic)",
+        ));
+    }
+
+    match FormatType::from_byte(prefix[5]) {
+        Some(FormatType::TypeA) if len == 8 && prefix[6] == FLAG_DELTA_WIRES && prefix[7] == 0 => {
+            Ok(DetectedFormat::V5aDelta)
+        }
+        Some(FormatType::TypeA) => Ok(DetectedFormat::V5a),
+        Some(FormatType::TypeC) => Ok(DetectedFormat::V5c),
+        None => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unknown format type {:#04x}", prefix[5]),
+        )),
+    }
+}
+
+/// Read up to `buf.len()` bytes, stopping early only at end of file.
+fn read_prefix(file: &mut File, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+/// Detect a circuit's format and summarize its header.
+pub fn file_info(path: impl AsRef<Path>) -> Result<FileInfo> {
+    let path = path.as_ref();
+    let format = detect_format(path)?;
+    let file_size = std::fs::metadata(path)?.len();
+
+    // Only the header is read; opening a reader would start streaming gates
+    let flags = match format {
+        DetectedFormat::V5a => 0,
+        DetectedFormat::V5aDelta => FLAG_DELTA_WIRES,
+        DetectedFormat::V5c => {
+            let mut bytes = [0u8; HEADER_SIZE];
+            File::open(path)?.read_exact(&mut bytes)?;
+            let header = HeaderV5c::from_bytes(&bytes)?;
+            return Ok(FileInfo {
+                format,
+                version: header.version,
+                gate_counts: header.gate_counts(),
+                primary_inputs: header.primary_inputs,
+                num_outputs: header.num_outputs,
+                scratch_space: Some(header.scratch_space),
+                garble_estimate: Some(estimate_garbling(&header)),
+                file_size,
+            });
+        }
+    };
+    let mut bytes = [0u8; HEADER_SIZE_V5A];
+    File::open(path)?.read_exact(&mut bytes)?;
+    let header = parse_header_with_flags(&bytes, flags)?;
+
+    Ok(FileInfo {
+        format,
+        version: header.version,
+        gate_counts: header.gate_counts(),
+        primary_inputs: header.primary_inputs,
+        num_outputs: header.num_outputs,
+        scratch_space: None,
+        garble_estimate: None,
+        file_size,
+    })
+}
+
+/// Detect a circuit's format and check its stored checksum, recomputed
+/// over the same bytes, in the same order, as its writer hashed them.
+///
+/// Delta-encoded v5a is verified by its reader as the last gate is decoded,
+/// so it is not supported here.
+pub async fn verify_checksum(path: impl AsRef<Path>) -> Result<bool> {
+    let path = path.as_ref();
+    match detect_format(path)? {
+        DetectedFormat::V5a => verify_v5a_checksum(path).await,
+        DetectedFormat::V5c => verify_v5c_checksum(path).await,
+        DetectedFormat::V5aDelta => Err(Error::new(
+            ErrorKind::Unsupported,
+            "checksum verification of delta-encoded v5a is not supported",
+        )),
+    }
+}
+
+/// Check a v5c file's checksum and recount its gate types in one pass.
+///
+/// Only v5c stores gate types in blocks that the checksum pass already
+/// reads, so other formats are not supported.
+pub async fn verify_checksum_and_count(path: impl AsRef<Path>) -> Result<(bool, GateCounts)> {
+    let path = path.as_ref();
+    match detect_format(path)? {
+        DetectedFormat::V5c => verify_v5c_checksum_and_count(path).await,
+        format => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "gate counting during verification needs v5c, not {}",
+                format
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::generate_adder;
+    use ckt_fmtv5_types::v5::a::{delta::DeltaWriterV5a, HEADER_SIZE_V5A};
+    use ckt_fmtv5_types::v5::c::{padded_size, ALIGNMENT};
+    use ckt_fmtv5_types::GateType;
+
+    #[monoio::test]
+    async fn test_detects_each_format() {
+        let circuit = generate_adder(4);
+        let dir = tempfile::tempdir().unwrap();
+        let v5a = dir.path().join("adder.v5a");
+        let delta = dir.path().join("adder.delta.v5a");
+        let v5c = dir.path().join("adder.v5c");
+
+        write_v5a(&circuit, &v5a).await;
+
+        let mut writer = DeltaWriterV5a::new(
+            &delta,
+            circuit.primary_inputs,
+            circuit.outputs.clone(),
+            [0; 32],
+        )
+        .await
+        .unwrap();
+        writer.write_gates(&circuit.gates).await.unwrap();
+        writer.finalize().await.unwrap();
+
+        crate::prealloc::prealloc(v5a.to_str().unwrap(), v5c.to_str().unwrap()).await;
+
+        let and_gates = circuit
+            .gates
+            .iter()
+            .filter(|g| g.gate_type == GateType::AND)
+            .count() as u64;
+        let expected = GateCounts::new(circuit.gates.len() as u64 - and_gates, and_gates);
+        for (path, format) in [
+            (&v5a, DetectedFormat::V5a),
+            (&delta, DetectedFormat::V5aDelta),
+            (&v5c, DetectedFormat::V5c),
+        ] {
+            let info = file_info(path).unwrap();
+            assert_eq!(info.format, format);
+            assert_eq!(info.gate_counts, expected, "{}", format);
+            assert_eq!(info.primary_inputs, 8);
+            assert_eq!(info.num_outputs, 5);
+            assert_eq!(info.scratch_space.is_some(), format == DetectedFormat::V5c);
+            if let Some(estimate) = info.garble_estimate {
+                assert_eq!(estimate.ciphertext_bytes, 16 * and_gates);
+            }
+            assert_eq!(info.file_size, std::fs::metadata(path).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_rejects_files_without_v5_magic() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A Bristol file and a file too short to hold a header
+        for (name, bytes) in [("bristol.txt", &b"1 3\n1 2\n1 1\n"[..]), ("tiny", b"Zk")] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+            let err = detect_format(&path).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", name);
+        }
+
+        let path = dir.path().join("unknown");
+        std::fs::write(&path, [MAGIC.as_slice(), &[0x06, 0x01, 0, 0]].concat()).unwrap();
+        assert_eq!(
+            detect_format(&path).unwrap_err().to_string(),
+            "unknown format type 0x01"
+        );
+    }
+
+    #[monoio::test]
+    async fn test_verify_checksum_detects_flipped_gate_byte() {
+        let circuit = generate_adder(4);
+        let dir = tempfile::tempdir().unwrap();
+        let v5a = dir.path().join("adder.v5a");
+        let v5c = dir.path().join("adder.v5c");
+
+        write_v5a(&circuit, &v5a).await;
+        crate::prealloc::prealloc(v5a.to_str().unwrap(), v5c.to_str().unwrap()).await;
+
+        // First gate byte in each format, just past the outputs
+        let num_outputs = circuit.outputs.len();
+        let first_gate_v5a = HEADER_SIZE_V5A + 5 * num_outputs;
+        let first_gate_v5c = ALIGNMENT + padded_size(4 * num_outputs);
+
+        for (path, offset) in [(&v5a, first_gate_v5a), (&v5c, first_gate_v5c)] {
+            assert!(verify_checksum(path).await.unwrap(), "{}", path.display());
+
+            let mut bytes = std::fs::read(path).unwrap();
+            bytes[offset] ^= 0x01;
+            std::fs::write(path, &bytes).unwrap();
+            assert!(!verify_checksum(path).await.unwrap(), "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Level-size histograms for v5a circuits.
+//!
+//! A gate's level is one more than the deepest of its inputs, with constants
+//! and primary inputs at level 0, so all gates on one level can be garbled or
+//! evaluated in parallel. The number of gates per level is the circuit's
+//! parallelism profile, which is what decides how many workers are worth
+//! running. Like [`exec`](crate::exec), this keeps only live wires' levels,
+//! recycling slots through [`FakeSlabAllocator`] as credits run out.
+
+use std::io::{Error, ErrorKind, Result};
+
+use ahash::HashMapExt;
+use ckt_fmtv5_types::v5::a::reader::CircuitReaderV5a;
+
+use crate::prealloc::{lookup_wire, AbsoluteWireId, WireEntry, WireMap};
+use crate::slab::FakeSlabAllocator;
+
+/// Count the gates on each level of a v5a circuit.
+///
+/// Entry `i` of the result is the number of gates on level `i + 1`, as no
+/// gate sits on level 0, so the result's length is the circuit's depth.
+pub async fn level_histogram(reader: &mut CircuitReaderV5a) -> Result<Vec<usize>> {
+    let primary_inputs = reader.header().primary_inputs;
+    let mut slab = FakeSlabAllocator::new();
+    for _ in 0..primary_inputs + 2 {
+        slab.allocate();
+    }
+    let mut wire_map = WireMap::new();
+    let mut levels = vec![0u32; primary_inputs as usize + 2];
+    let mut histogram = Vec::new();
+
+    while let Some(block) = reader.next_block_soa().await? {
+        for i in 0..block.gates_in_block {
+            let a = level_of(
+                &mut wire_map,
+                &mut slab,
+                &levels,
+                block.in1[i],
+                primary_inputs,
+            )?;
+            let b = level_of(
+                &mut wire_map,
+                &mut slab,
+                &levels,
+                block.in2[i],
+                primary_inputs,
+            )?;
+            let level = a.max(b) + 1;
+            if level as usize > histogram.len() {
+                histogram.resize(level as usize, 0);
+            }
+            histogram[level as usize - 1] += 1;
+
+            let slab_idx = slab.allocate();
+            if slab_idx >= levels.len() {
+                levels.resize(slab_idx + 1, 0);
+            }
+            levels[slab_idx] = level;
+            wire_map.insert(
+                block.out[i],
+                WireEntry {
+                    slab_idx,
+                    credits_remaining: block.credits[i],
+                },
+            );
+        }
+    }
+
+    Ok(histogram)
+}
+
+/// Depth and level widths of a circuit, from its level histogram.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelStats {
+    /// Number of levels, which is the length of the critical path.
+    pub depth: usize,
+    /// Gates on the narrowest level.
+    pub min_width: usize,
+    /// Gates on the widest level.
+    pub max_width: usize,
+    /// Median gates per level, the lower of the middle two for an even depth.
+    pub median_width: usize,
+    /// Level holding the most gates, the first if several tie.
+    pub widest_level: usize,
+}
+
+impl LevelStats {
+    /// Summarize a histogram as returned by [`level_histogram`].
+    ///
+    /// Levels are numbered from 1, so `widest_level` is 1 for the first
+    /// entry. An empty histogram gives all zeros.
+    pub fn from_histogram(histogram: &[usize]) -> Self {
+        let Some((widest, &max_width)) = histogram
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, width)| width)
+        else {
+            return Self::default();
+        };
+        let mut sorted = histogram.to_vec();
+        sorted.sort_unstable();
+        Self {
+            depth: histogram.len(),
+            min_width: sorted[0],
+            max_width,
+            median_width: sorted[(sorted.len() - 1) / 2],
+            widest_level: widest + 1,
+        }
+    }
+}
+
+/// Level of a live wire, consuming one of its credits.
+fn level_of(
+    wire_map: &mut WireMap,
+    slab: &mut FakeSlabAllocator,
+    levels: &[u32],
+    wire: AbsoluteWireId,
+    primary_inputs: u64,
+) -> Result<u32> {
+    lookup_wire::<false>(wire_map, slab, wire, primary_inputs)
+        .map(|idx| levels[idx])
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("wire {} is not live", wire)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::write_v5a;
+    use adder::{generate_adder, generate_adder_cla, AdderCircuit};
+
+    async fn histogram_of(circuit: &AdderCircuit) -> Vec<usize> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5a");
+        write_v5a(circuit, &path).await;
+
+        let mut reader = CircuitReaderV5a::open(&path).unwrap();
+        level_histogram(&mut reader).await.unwrap()
+    }
+
+    #[monoio::test]
+    async fn test_adder_levels_follow_the_carry_chain() {
+        let circuit = generate_adder(8);
+        let histogram = histogram_of(&circuit).await;
+
+        // Every bit's a XOR b and a AND b are on level 1, then the carry
+        // ripples two levels per bit: sum and carry AND, then carry out
+        assert_eq!(histogram.len(), 17);
+        assert_eq!(histogram[0], 16);
+        for (i, &count) in histogram.iter().enumerate().skip(1) {
+            let level = i + 1;
+            assert_eq!(count, if level % 2 == 0 { 2 } else { 1 }, "level {}", level);
+        }
+        assert_eq!(histogram.iter().sum::<usize>(), circuit.gates.len());
+
+        // Eight levels each of one and two gates, plus the first
+        assert_eq!(
+            LevelStats::from_histogram(&histogram),
+            LevelStats {
+                depth: 17,
+                min_width: 1,
+                max_width: 16,
+                median_width: 2,
+                widest_level: 1,
+            }
+        );
+    }
+
+    #[monoio::test]
+    async fn test_cla_adder_is_shallow_and_wide() {
+        let ripple = histogram_of(&generate_adder(32)).await;
+        let cla = histogram_of(&generate_adder_cla(32)).await;
+
+        // Per-bit generate and propagate, two levels for each of the five
+        // prefix merges, then the sum XORs
+        assert_eq!(ripple.len(), 65);
+        assert_eq!(cla.len(), 12);
+        let width = |h: &[usize]| h.iter().sum::<usize>() / h.len();
+        assert!(width(&cla) > 4 * width(&ripple));
+    }
+}
@@ -4,8 +4,22 @@
 //! with wire address preallocation.
 //!
 //! # Key Features
+//! - **Bristol conversion**: Read Bristol Fashion circuits into v5a or v5c
 //! - **Preallocation**: Convert v5a (wire IDs + credits) to v5c (memory addresses)
 //! - **Memory efficient**: Uses slab allocation for minimal memory footprint
+//! - **Credit checking**: Verify claimed v5a credits match actual fan-out
+//! - **Dead gates**: Find v5a gates whose outputs are never used
+//! - **Comparison**: Find the first gate where two v5a circuits differ
+//! - **Composition**: Chain two v5a circuits, feeding outputs into inputs
+//! - **Search**: Find v5a gates by wire ID, with their credits, or v5c gates
+//!   by address
+//! - **Cost estimation**: Report garbling cost from gate counts
+//! - **Format detection**: Identify v5a/v5c files from their magic bytes and
+//!   verify their checksums
+//! - **Compact execution**: Run v5a circuits keeping only live wire values
+//! - **Level histograms**: Count gates per level to profile parallelism
+//! - **Minimization**: Fold redundant XOR chains and drop dead gates
+//! - **Extraction**: Dump v5c circuits as Bristol-like text for debugging
 //!
 //! # Example
 //! ```ignore
@@ -14,6 +28,23 @@
 //! prealloc::prealloc("input.v5a", "output.v5c").await;
 //! ```
 
+pub mod bristol;
+pub mod compare;
+pub mod compose;
+pub mod cost;
+pub mod credits;
+pub mod dead;
+pub mod exec;
+pub mod extract;
+pub mod info;
+pub mod levels;
+pub mod minimize;
 pub mod prealloc;
+pub mod search;
 pub mod slab;
 pub mod types;
+
+#[cfg(test)]
+mod test_util;
+
+pub use levels::level_histogram;
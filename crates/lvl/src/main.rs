@@ -1,6 +1,17 @@
 mod cli;
 
-use ckt_lvl::prealloc;
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::{
+    a::reader::{repair_v5a_checksum, CircuitReaderV5a},
+    c::repair_v5c_checksum,
+};
+use ckt_gobble::HashScheme;
+use ckt_lvl::compare::{self, Difference};
+use ckt_lvl::compose;
+use ckt_lvl::info::{self, DetectedFormat};
+use ckt_lvl::levels::{self, LevelStats};
+use ckt_lvl::{bristol, cost, credits, dead, extract, minimize, prealloc, search};
 use cli::{Cli, Command};
 
 use mimalloc::MiMalloc;
@@ -13,20 +24,423 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Cli::parse_args();
 
     match args.command {
+        Command::Convert(convert_args) => run_convert(convert_args).await,
         Command::Prealloc(prealloc_args) => run_prealloc(prealloc_args).await,
+        Command::Info(info_args) => run_info(info_args),
+        Command::Cost(cost_args) => run_cost(cost_args),
+        Command::Search(search_args) => run_search(search_args).await,
+        Command::Minimize(minimize_args) => run_minimize(minimize_args).await,
+        Command::Verify(verify_args) => run_verify(verify_args).await,
+        Command::Repair(repair_args) => run_repair(repair_args).await,
+        Command::Extract(extract_args) => run_extract(extract_args).await,
+        Command::Levels(levels_args) => run_levels(levels_args).await,
+        Command::CheckCredits(check_args) => run_check_credits(check_args).await,
+        Command::AnalyzeDead(dead_args) => run_analyze_dead(dead_args).await,
+        Command::Compare(compare_args) => run_compare(compare_args).await,
+        Command::Compose(compose_args) => run_compose(compose_args).await,
     }
 }
 
+async fn run_convert(args: cli::ConvertCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let primary_inputs = match args.primary_inputs {
+        Some(primary_inputs) => primary_inputs,
+        None => bristol::read_header(&args.input)?.primary_inputs()?,
+    };
+    let extension = match args.version.as_str() {
+        "v5a" | "v5c" => args.version.as_str(),
+        _ => {
+            return Err(format!("Unsupported version: {}. Use 'v5a' or 'v5c'", args.version).into())
+        }
+    };
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.with_extension(extension));
+
+    println!("Bristol Conversion - Bristol to {}", args.version);
+    println!("=============================================");
+    println!("Input:  {}", args.input.display());
+    println!("Output: {}", output.display());
+    println!();
+
+    let stats = if extension == "v5a" {
+        bristol::bristol_to_v5a(&args.input, &output, primary_inputs).await?
+    } else {
+        bristol::bristol_to_v5c(&args.input, &output, primary_inputs).await?
+    };
+
+    let percent = |n: u64| n as f64 / stats.total_gates.max(1) as f64 * 100.0;
+    println!();
+    println!("Conversion Summary:");
+    println!("  Total gates:       {}", stats.total_gates);
+    println!(
+        "  XOR gates:         {} ({:.1}%)",
+        stats.xor_gates,
+        percent(stats.xor_gates)
+    );
+    println!(
+        "  AND gates:         {} ({:.1}%)",
+        stats.and_gates,
+        percent(stats.and_gates)
+    );
+    println!("  Primary inputs:    {}", stats.primary_inputs);
+    println!("  Outputs:           {}", stats.num_outputs);
+    println!("  Bristol file size: {} bytes", stats.bristol_file_size);
+    println!("  Output file size:  {} bytes", stats.output_file_size);
+    Ok(())
+}
+
 async fn run_prealloc(args: cli::PreallocCommand) -> Result<(), Box<dyn std::error::Error>> {
     println!("Circuit Preallocation - v5a to v5c Converter");
     println!("=============================================");
     println!("Input:  {}", args.input.display());
     println!("Output: {}", args.output.display());
+    if let Some(max) = args.max_scratch {
+        println!("Budget: {} slots", max);
+    }
     println!();
 
-    prealloc::prealloc(args.input.to_str().unwrap(), args.output.to_str().unwrap()).await;
+    prealloc::prealloc_with_budget(
+        args.input.to_str().unwrap(),
+        args.output.to_str().unwrap(),
+        args.max_scratch,
+    )
+    .await?;
 
     println!();
     println!("Conversion complete!");
     Ok(())
 }
+
+fn run_info(args: cli::InfoCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let info = info::file_info(&args.file)?;
+
+    println!("Circuit Info - {}", args.file.display());
+    println!("=============================================");
+    println!(
+        "Format:         {} (version {:#04x})",
+        info.format, info.version
+    );
+    println!("File size:      {} bytes", info.file_size);
+    println!("Primary inputs: {}", info.primary_inputs);
+    println!("Outputs:        {}", info.num_outputs);
+    println!("Total gates:    {}", info.gate_counts.total);
+    println!("  XOR gates:    {}", info.gate_counts.xor);
+    println!("  AND gates:    {}", info.gate_counts.and);
+    if let Some(scratch_space) = info.scratch_space {
+        println!("Scratch space:  {} slots", scratch_space);
+    }
+    if let Some(estimate) = info.garble_estimate {
+        println!(
+            "Garbled size:   {} bytes ({} AND gates over {} blocks)",
+            estimate.ciphertext_bytes, estimate.and_gates, estimate.blocks
+        );
+    }
+    Ok(())
+}
+
+fn run_cost(args: cli::CostCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let hash_scheme = match args.hash_scheme.as_str() {
+        "ccrnd" => HashScheme::Ccrnd,
+        "tmmo" => HashScheme::Tmmo,
+        _ => {
+            return Err(format!(
+                "Unsupported hash scheme: {}. Use 'ccrnd' or 'tmmo'",
+                args.hash_scheme
+            )
+            .into())
+        }
+    };
+    let cost = cost::estimate_file(&args.file, hash_scheme)?;
+
+    println!("Garbling Cost - {}", args.file.display());
+    println!("=============================================");
+    println!("AND gates:         {}", cost.and_gates);
+    println!(
+        "XOR gates:         {} ({:.2}% of total)",
+        cost.xor_gates,
+        cost.xor_fraction() * 100.0
+    );
+    println!();
+    println!("Hash scheme:       {:?}", cost.hash_scheme);
+    println!();
+    println!("Free-XOR half-gates:");
+    println!("  AES operations:  {}", cost.aes_ops);
+    println!("  Ciphertext size: {} bytes", cost.ciphertext_bytes);
+    println!();
+    println!("Naive (all gates garbled):");
+    println!("  AES operations:  {}", cost.naive_aes_ops);
+    println!("  Ciphertext size: {} bytes", cost.naive_ciphertext_bytes);
+    println!();
+    println!("Savings factor:    {:.2}x", cost.savings_factor());
+    Ok(())
+}
+
+async fn run_search(args: cli::SearchCommand) -> Result<(), Box<dyn std::error::Error>> {
+    if args.inputs.is_empty() && args.outputs.is_empty() {
+        return Err("must specify at least one input (-i) or output (-o) to search for".into());
+    }
+
+    println!("Searching {}", args.file.display());
+    if !args.inputs.is_empty() {
+        println!("  Inputs:  {:?}", args.inputs);
+    }
+    if !args.outputs.is_empty() {
+        println!("  Outputs: {:?}", args.outputs);
+    }
+    println!();
+
+    let found = match info::detect_format(&args.file)? {
+        DetectedFormat::V5a => {
+            let matches = search::search_v5a(&args.file, &args.inputs, &args.outputs).await?;
+            for (n, m) in matches.iter().enumerate() {
+                println!("Match #{} at gate index {}", n + 1, m.gate_index);
+                println!("  Type:    {:?}", m.gate.gate_type);
+                println!(
+                    "  Gate:    ({}, {}) -> {}",
+                    m.gate.in1, m.gate.in2, m.gate.out
+                );
+                println!("  Credits: {}", m.gate.credits);
+                println!("  Matched: {}", m.reasons.join(", "));
+                println!();
+            }
+            matches.len()
+        }
+        DetectedFormat::V5c => {
+            let matches = search::search_v5c(&args.file, &args.inputs, &args.outputs).await?;
+            for (n, m) in matches.iter().enumerate() {
+                println!("Match #{} at gate index {}", n + 1, m.gate_index);
+                println!("  Type:    {:?}", m.gate_type);
+                println!(
+                    "  Gate:    [{}], [{}] -> [{}]",
+                    m.gate.in1, m.gate.in2, m.gate.out
+                );
+                println!("  Matched: {}", m.reasons.join(", "));
+                println!();
+            }
+            matches.len()
+        }
+        DetectedFormat::V5aDelta => {
+            return Err("searching delta-encoded v5a is not supported".into())
+        }
+    };
+
+    println!("Matches found: {}", found);
+    Ok(())
+}
+
+async fn run_minimize(args: cli::MinimizeCommand) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Circuit Minimization - v5a to v5a");
+    println!("=============================================");
+    println!("Input:  {}", args.input.display());
+    println!("Output: {}", args.output.display());
+    println!();
+
+    let stats = minimize::minimize_file(&args.input, &args.output).await?;
+
+    println!("Original gates:     {}", stats.original_gates);
+    println!("XORs folded:        {}", stats.xors_folded);
+    println!("Dead gates removed: {}", stats.dead_gates_removed);
+    println!("Remaining gates:    {}", stats.remaining_gates());
+    Ok(())
+}
+
+async fn run_verify(args: cli::VerifyCommand) -> Result<(), Box<dyn std::error::Error>> {
+    if args.and_count {
+        return run_verify_and_count(&args.file).await;
+    }
+    if !info::verify_checksum(&args.file).await? {
+        return Err(format!("checksum mismatch in {}", args.file.display()).into());
+    }
+    println!("Checksum of {} is valid", args.file.display());
+    Ok(())
+}
+
+async fn run_verify_and_count(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (valid, counts) = info::verify_checksum_and_count(path).await?;
+    let declared = info::file_info(path)?.gate_counts;
+
+    println!("Gates counted:  {}", counts.total);
+    println!("  XOR gates:    {}", counts.xor);
+    println!("  AND gates:    {}", counts.and);
+    if !valid {
+        return Err(format!("checksum mismatch in {}", path.display()).into());
+    }
+    if counts != declared {
+        return Err(format!(
+            "header declares {} XOR and {} AND gates",
+            declared.xor, declared.and
+        )
+        .into());
+    }
+    println!("Checksum and gate counts of {} are valid", path.display());
+    Ok(())
+}
+
+async fn run_repair(args: cli::RepairCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let repaired = match info::detect_format(&args.file)? {
+        DetectedFormat::V5a => repair_v5a_checksum(&args.file).await?,
+        DetectedFormat::V5c => repair_v5c_checksum(&args.file).await?,
+        DetectedFormat::V5aDelta => {
+            return Err("repairing delta-encoded v5a is not supported".into())
+        }
+    };
+
+    if repaired {
+        println!("Rewrote stale checksum in {}", args.file.display());
+    } else {
+        println!("Checksum of {} is already valid", args.file.display());
+    }
+    Ok(())
+}
+
+async fn run_extract(args: cli::ExtractCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let format = info::detect_format(&args.input)?;
+    if format != DetectedFormat::V5c {
+        return Err(format!("extract only supports v5c, not {}", format).into());
+    }
+    let output = args
+        .output
+        .unwrap_or_else(|| args.input.with_extension("bristol"));
+
+    let gates = extract::v5c_to_bristol(&args.input, &output).await?;
+    println!(
+        "Wrote {} gates from {} to {}",
+        gates,
+        args.input.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+async fn run_levels(args: cli::LevelsCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = CircuitReaderV5a::open(&args.file)?;
+    let histogram = levels::level_histogram(&mut reader).await?;
+    let stats = LevelStats::from_histogram(&histogram);
+    let total_gates: usize = histogram.iter().sum();
+
+    println!("Levels in {}", args.file.display());
+    println!("  Depth:          {}", stats.depth);
+    println!(
+        "  Average width:  {:.1}",
+        total_gates as f64 / stats.depth.max(1) as f64
+    );
+    println!("  Min width:      {}", stats.min_width);
+    println!("  Median width:   {}", stats.median_width);
+    println!(
+        "  Max width:      {} (level {})",
+        stats.max_width, stats.widest_level
+    );
+    Ok(())
+}
+
+async fn run_check_credits(
+    args: cli::CheckCreditsCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mismatches = credits::check_file(&args.file).await?;
+    if mismatches.is_empty() {
+        println!(
+            "Credits of every gate in {} match their fan-out",
+            args.file.display()
+        );
+        return Ok(());
+    }
+
+    for m in &mismatches {
+        if m.output {
+            println!(
+                "  wire {}: output claims {} credits, should claim none",
+                m.wire, m.claimed
+            );
+        } else {
+            println!(
+                "  wire {}: claimed {} credits, read {} times",
+                m.wire, m.claimed, m.actual
+            );
+        }
+    }
+    Err(format!(
+        "{} wires in {} have wrong credits",
+        mismatches.len(),
+        args.file.display()
+    )
+    .into())
+}
+
+async fn run_analyze_dead(args: cli::AnalyzeDeadCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let dead = dead::check_file(&args.file).await?;
+    println!("Dead gates in {}: {}", args.file.display(), dead.len());
+    for wire in &dead {
+        println!("  wire {}", wire);
+    }
+    Ok(())
+}
+
+async fn run_compare(args: cli::CompareCommand) -> Result<(), Box<dyn std::error::Error>> {
+    println!("File 1: {}", args.file1.display());
+    println!("File 2: {}", args.file2.display());
+    println!();
+
+    if !args.structural {
+        let counts1 = CircuitReaderV5a::open(&args.file1)?.gate_counts();
+        let counts2 = CircuitReaderV5a::open(&args.file2)?.gate_counts();
+        println!("          {:>14} {:>14}", "File 1", "File 2");
+        println!("  Gates:  {:>14} {:>14}", counts1.total, counts2.total);
+        println!("  XOR:    {:>14} {:>14}", counts1.xor, counts2.xor);
+        println!("  AND:    {:>14} {:>14}", counts1.and, counts2.and);
+        println!();
+        if counts1.xor == counts2.xor && counts1.and == counts2.and {
+            println!("Same gate type distribution (use --structural to compare gates)");
+        } else {
+            println!("Different gate type distribution");
+        }
+        return Ok(());
+    }
+
+    match compare::first_difference(&args.file1, &args.file2).await? {
+        None => {
+            println!("Every gate matches");
+            Ok(())
+        }
+        Some(Difference::Gate { index, left, right }) => {
+            println!("First difference at gate index {}", index);
+            for (name, gate) in [("File 1", left), ("File 2", right)] {
+                println!(
+                    "  {}: {:?} ({}, {}) -> {}",
+                    name, gate.gate_type, gate.in1, gate.in2, gate.out
+                );
+            }
+            Err("circuits differ".into())
+        }
+        Some(Difference::Truncated {
+            index,
+            left_gates,
+            right_gates,
+        }) => {
+            println!(
+                "All shared gates match, but file {} ends after {} gates ({} vs {})",
+                if left_gates < right_gates { 1 } else { 2 },
+                index,
+                left_gates,
+                right_gates
+            );
+            Err("circuits differ in length".into())
+        }
+    }
+}
+
+async fn run_compose(args: cli::ComposeCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let map = compose::parse_wire_map(&args.map)?;
+    let composed = compose::compose_files(&args.a, &args.b, &args.output, &map).await?;
+
+    println!("Circuit Composition - v5a then v5a to v5a");
+    println!("=============================================");
+    println!("A:      {}", args.a.display());
+    println!("B:      {}", args.b.display());
+    println!("Output: {}", args.output.display());
+    println!();
+    println!("Wires fed from A to B: {}", map.len());
+    println!("Primary inputs:        {}", composed.primary_inputs);
+    println!("Gates:                 {}", composed.gates.len());
+    println!("Outputs:               {}", composed.outputs.len());
+    Ok(())
+}
@@ -0,0 +1,322 @@
+//! Algebraic simplification of XOR chains in v5a circuits.
+//!
+//! Naive circuit construction leaves patterns like `XOR(XOR(a, b), b)` that
+//! always equal `a`. Since wire 1 is the true constant, NOT is `XOR(x, 1)`,
+//! so double negation `XOR(XOR(x, 1), 1)` is the same pattern. Folded gates
+//! become aliases for an existing wire, gates left without consumers are
+//! dropped, and credits are recomputed for the survivors.
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use ahash::{HashMap, HashMapExt, HashSet};
+use ckt_fmtv5_types::v5::a::{
+    reader::CircuitReaderV5a, writer::CircuitWriterV5a, GateV5a, CREDITS_OUTPUT, MAX_CREDITS,
+};
+use ckt_fmtv5_types::GateType;
+
+use crate::prealloc::AbsoluteWireId;
+
+/// Counts of what a minimization pass removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MinimizeStats {
+    /// Gates in the input circuit.
+    pub original_gates: u64,
+    /// XOR gates replaced by an alias to an existing wire.
+    pub xors_folded: u64,
+    /// Gates removed because nothing consumed their output anymore.
+    pub dead_gates_removed: u64,
+}
+
+impl MinimizeStats {
+    /// Gates remaining after minimization.
+    pub fn remaining_gates(&self) -> u64 {
+        self.original_gates - self.xors_folded - self.dead_gates_removed
+    }
+}
+
+/// A minimized circuit, ready to be written as v5a.
+#[derive(Debug, Clone)]
+pub struct MinimizedCircuit {
+    /// Surviving gates in their original order, with recomputed credits.
+    pub gates: Vec<GateV5a>,
+    /// Output wires, redirected through any folded gates.
+    pub outputs: Vec<u64>,
+    /// What the pass removed.
+    pub stats: MinimizeStats,
+}
+
+/// Simplify redundant XOR chains in a topologically ordered gate list.
+///
+/// Folds `XOR(x, XOR(x, z))` to `z`, `XOR(x, x)` to false and `XOR(x, 0)`
+/// to `x`, then removes dead gates. The result computes the same outputs.
+///
+/// Fails if a surviving wire is read more often than v5a credits can hold.
+pub fn minimize(
+    primary_inputs: u64,
+    gates: &[GateV5a],
+    outputs: &[u64],
+) -> Result<MinimizedCircuit> {
+    let mut stats = MinimizeStats {
+        original_gates: gates.len() as u64,
+        ..Default::default()
+    };
+
+    // Folded wire -> equivalent wire, always already resolved
+    let mut alias: HashMap<AbsoluteWireId, AbsoluteWireId> = HashMap::new();
+    // Surviving XOR output -> its (resolved) inputs
+    let mut xor_inputs: HashMap<AbsoluteWireId, (AbsoluteWireId, AbsoluteWireId)> = HashMap::new();
+    let resolve = |alias: &HashMap<_, _>, w| *alias.get(&w).unwrap_or(&w);
+
+    let mut kept = Vec::with_capacity(gates.len());
+    for gate in gates {
+        let a = resolve(&alias, gate.in1);
+        let b = resolve(&alias, gate.in2);
+
+        if gate.gate_type == GateType::XOR {
+            if let Some(target) = fold_xor(&xor_inputs, a, b) {
+                alias.insert(gate.out, target);
+                stats.xors_folded += 1;
+                continue;
+            }
+            xor_inputs.insert(gate.out, (a, b));
+        }
+
+        kept.push(GateV5a {
+            in1: a,
+            in2: b,
+            ..*gate
+        });
+    }
+
+    let outputs: Vec<u64> = outputs.iter().map(|&o| resolve(&alias, o)).collect();
+
+    // Walk backwards keeping only gates that feed an output
+    let mut live: HashSet<AbsoluteWireId> = outputs.iter().copied().collect();
+    let mut is_live = vec![false; kept.len()];
+    for (i, gate) in kept.iter().enumerate().rev() {
+        if live.contains(&gate.out) {
+            is_live[i] = true;
+            live.insert(gate.in1);
+            live.insert(gate.in2);
+        }
+    }
+    let mut kept: Vec<GateV5a> = kept
+        .into_iter()
+        .zip(is_live)
+        .filter_map(|(gate, live)| live.then_some(gate))
+        .collect();
+    stats.dead_gates_removed = stats.original_gates - stats.xors_folded - kept.len() as u64;
+
+    assign_credits(primary_inputs, &mut kept, &outputs)?;
+
+    Ok(MinimizedCircuit {
+        gates: kept,
+        outputs,
+        stats,
+    })
+}
+
+/// Return the wire `XOR(a, b)` is equivalent to, if any.
+fn fold_xor(
+    xor_inputs: &HashMap<AbsoluteWireId, (AbsoluteWireId, AbsoluteWireId)>,
+    a: AbsoluteWireId,
+    b: AbsoluteWireId,
+) -> Option<AbsoluteWireId> {
+    if a == b {
+        return Some(0);
+    }
+    if a == 0 {
+        return Some(b);
+    }
+    if b == 0 {
+        return Some(a);
+    }
+    // XOR(x, XOR(x, z)) = z, with the inner XOR on either side
+    for (outer, inner) in [(a, b), (b, a)] {
+        if let Some(&(x, z)) = xor_inputs.get(&inner) {
+            if outer == x {
+                return Some(z);
+            }
+            if outer == z {
+                return Some(x);
+            }
+        }
+    }
+    None
+}
+
+/// Set every gate's credits to its output's fan-out, or [`CREDITS_OUTPUT`]
+/// for outputs.
+///
+/// Fails if a wire's fan-out doesn't fit in v5a credits, rather than
+/// claiming fewer reads than there are and having the slot freed early.
+pub(crate) fn assign_credits(
+    primary_inputs: u64,
+    gates: &mut [GateV5a],
+    outputs: &[u64],
+) -> Result<()> {
+    let mut uses: HashMap<AbsoluteWireId, u32> = HashMap::new();
+    for gate in gates.iter() {
+        for wire in [gate.in1, gate.in2] {
+            if wire >= primary_inputs + 2 {
+                let reads = uses.entry(wire).or_insert(0);
+                *reads = reads.saturating_add(1);
+            }
+        }
+    }
+    let outputs: HashSet<AbsoluteWireId> = outputs.iter().copied().collect();
+
+    for gate in gates.iter_mut() {
+        gate.credits = if outputs.contains(&gate.out) {
+            CREDITS_OUTPUT
+        } else {
+            fan_out_credits(gate.out, uses.get(&gate.out).copied().unwrap_or(0))?
+        };
+    }
+    Ok(())
+}
+
+/// Credits for a non-output wire read `uses` times.
+fn fan_out_credits(wire: AbsoluteWireId, uses: u32) -> Result<u32> {
+    // MAX_CREDITS itself marks constants and primary inputs
+    if uses >= MAX_CREDITS {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "wire {} is read {} times, more than v5a credits can hold",
+                wire, uses
+            ),
+        ));
+    }
+    Ok(uses)
+}
+
+/// Minimize a v5a file, writing the result as v5a.
+///
+/// The whole gate list is held in memory, since dead-gate removal needs a
+/// backward pass.
+pub async fn minimize_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+) -> Result<MinimizeStats> {
+    let mut reader = CircuitReaderV5a::open(input)?;
+    let header = reader.header();
+
+    let mut gates = Vec::with_capacity(header.total_gates() as usize);
+    while let Some(block) = reader.next_block_soa().await? {
+        gates.extend(block.gates());
+    }
+
+    let minimized = minimize(header.primary_inputs, &gates, reader.outputs())?;
+
+    let mut writer = CircuitWriterV5a::new(
+        output,
+        header.primary_inputs,
+        minimized.outputs,
+        header.memo,
+    )
+    .await?;
+    writer.write_gates(&minimized.gates).await?;
+    writer.finalize().await?;
+
+    Ok(minimized.stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credits::validate_credits;
+    use crate::exec::exec_compact;
+    use crate::test_util::write_gates_v5a;
+    use adder::generate_adder;
+
+    fn gate(in1: u64, in2: u64, out: u64, credits: u32, gate_type: GateType) -> GateV5a {
+        GateV5a {
+            in1,
+            in2,
+            out,
+            credits,
+            gate_type,
+        }
+    }
+
+    #[monoio::test]
+    async fn test_redundant_xor_chain_is_folded() {
+        use GateType::{AND, XOR};
+
+        // Inputs are wires 2 and 3
+        let gates = vec![
+            gate(2, 3, 4, 1, XOR),              // a ^ b
+            gate(4, 3, 5, CREDITS_OUTPUT, XOR), // (a ^ b) ^ b = a
+            gate(2, 1, 6, 1, XOR),              // !a
+            gate(6, 1, 7, 1, XOR),              // !!a = a
+            gate(5, 7, 8, CREDITS_OUTPUT, AND), // a & a
+            gate(2, 3, 9, 2, AND),
+            gate(9, 9, 10, CREDITS_OUTPUT, XOR), // always false
+        ];
+        let outputs = vec![8, 5, 10];
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("chain.v5a");
+        let output = dir.path().join("chain.min.v5a");
+        write_gates_v5a(&input, 2, &outputs, &gates).await;
+
+        let stats = minimize_file(&input, &output).await.unwrap();
+        assert_eq!(
+            stats,
+            MinimizeStats {
+                original_gates: 7,
+                xors_folded: 3,
+                dead_gates_removed: 3,
+            }
+        );
+
+        let reader = CircuitReaderV5a::open(&output).unwrap();
+        assert_eq!(reader.header().total_gates(), stats.remaining_gates());
+        assert_eq!(reader.outputs(), &[8, 2, 0]);
+
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        for bits in 0..4u8 {
+            let inputs = [bits & 1 == 1, bits & 2 == 2];
+            let original = exec_compact(input, &inputs).await.unwrap();
+            let minimized = exec_compact(output, &inputs).await.unwrap();
+            assert_eq!(minimized.outputs, original.outputs, "inputs {:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn test_adder_only_loses_constant_carry_in() {
+        let circuit = generate_adder(8);
+        let minimized = minimize(circuit.primary_inputs, &circuit.gates, &circuit.outputs).unwrap();
+
+        // Bit 0's carry-in is constant false, so sum[0] = half_sum ^ 0 folds
+        assert_eq!(minimized.stats.xors_folded, 1);
+        assert_eq!(minimized.stats.dead_gates_removed, 0);
+        assert_eq!(minimized.outputs[0], circuit.gates[0].out);
+        assert_eq!(minimized.outputs[1..], circuit.outputs[1..]);
+        assert_eq!(
+            validate_credits(circuit.primary_inputs, &minimized.outputs, &minimized.gates),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_fan_out_beyond_credits_is_an_error() {
+        // A circuit reading one wire 2^24 times would need gigabytes here,
+        // so check the limit where assign_credits applies it
+        assert_eq!(
+            fan_out_credits(7, MAX_CREDITS - 1).unwrap(),
+            MAX_CREDITS - 1
+        );
+        let err = fan_out_credits(7, MAX_CREDITS).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "wire 7 is read {} times, more than v5a credits can hold",
+                MAX_CREDITS
+            )
+        );
+    }
+}
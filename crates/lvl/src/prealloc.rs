@@ -1,27 +1,72 @@
 use std::collections::hash_map::Entry;
+use std::io::{Error, ErrorKind, Result};
 
 use ahash::{HashMap, HashMapExt};
-use ckt_fmtv5_types::v5::{a::reader::CircuitReaderV5a, c::*};
+use ckt_fmtv5_types::v5::{
+    a::{reader::CircuitReaderV5a, CREDITS_OUTPUT},
+    c::*,
+};
 use indicatif::ProgressBar;
 
 use crate::slab::FakeSlabAllocator;
 
 pub async fn prealloc(input: &str, output: &str) {
+    prealloc_with_budget(input, output, None).await.unwrap();
+}
+
+/// Preallocate like [`prealloc`], failing once scratch space would exceed
+/// `max_scratch` slots.
+///
+/// This is checked as gates are assigned, so an over-budget circuit fails
+/// at the first offending gate instead of after a full conversion. On
+/// failure the partially written output file is removed.
+pub async fn prealloc_with_budget(
+    input: &str,
+    output: &str,
+    max_scratch: Option<u64>,
+) -> Result<()> {
+    let result = prealloc_inner(input, output, max_scratch).await;
+    if result.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+fn budget_exceeded(max_scratch: u64, needed: usize, location: &str) -> Error {
+    Error::new(
+        ErrorKind::OutOfMemory,
+        format!(
+            "scratch space budget of {} slots exceeded {} (needs at least {})",
+            max_scratch, location, needed
+        ),
+    )
+}
+
+async fn prealloc_inner(input: &str, output: &str, max_scratch: Option<u64>) -> Result<()> {
     let mut slab = FakeSlabAllocator::new();
 
-    let mut reader = CircuitReaderV5a::open(input).unwrap();
+    let mut reader = CircuitReaderV5a::open(input)?;
     let header = reader.header();
+
+    let reserved = header.primary_inputs + 2;
+    if let Some(max) = max_scratch.filter(|&max| reserved > max) {
+        return Err(budget_exceeded(
+            max,
+            reserved as usize,
+            "by constants and primary inputs",
+        ));
+    }
+
     let mut writer = WriterV5c::new(
         output,
         header.primary_inputs,
         header.num_outputs,
         header.memo,
     )
-    .await
-    .unwrap();
+    .await?;
     let mut wire_map = WireMap::new();
 
-    for _ in 0..header.primary_inputs + 2 {
+    for _ in 0..reserved {
         slab.allocate();
     }
 
@@ -30,8 +75,9 @@ pub async fn prealloc(input: &str, output: &str) {
     let pb = ProgressBar::new(header.total_gates());
 
     let mut temp_count = 0;
+    let mut gate_index = 0u64;
 
-    while let Some(block) = reader.next_block_soa().await.unwrap() {
+    while let Some(block) = reader.next_block_soa().await? {
         for i in 0..block.gates_in_block {
             let in1 = lookup_wire::<false>(
                 &mut wire_map,
@@ -39,34 +85,40 @@ pub async fn prealloc(input: &str, output: &str) {
                 block.in1[i],
                 header.primary_inputs,
             )
-            .unwrap();
+            .ok_or_else(|| dead_wire(block.in1[i]))?;
             let in2 = lookup_wire::<false>(
                 &mut wire_map,
                 &mut slab,
                 block.in2[i],
                 header.primary_inputs,
             )
-            .unwrap();
+            .ok_or_else(|| dead_wire(block.in2[i]))?;
+
+            // Overwriting a live wire would silently redirect its readers
+            let out = block.out[i];
+            if out < reserved || wire_map.contains_key(&out) {
+                return Err(duplicate_output(out, gate_index));
+            }
 
             let out_wire_id = slab.allocate();
+            let needed = slab.max_allocated_concurrently();
+            if let Some(max) = max_scratch.filter(|&max| needed as u64 > max) {
+                return Err(budget_exceeded(
+                    max,
+                    needed,
+                    &format!("at gate {}", gate_index),
+                ));
+            }
             wire_map.insert(
-                block.out[i],
+                out,
                 WireEntry {
                     slab_idx: out_wire_id,
                     credits_remaining: block.credits[i],
                 },
             );
-            writer
-                .write_gate(
-                    GateV5c {
-                        in1: in1 as u32,
-                        in2: in2 as u32,
-                        out: out_wire_id as u32,
-                    },
-                    block.gate_types[i],
-                )
-                .await
-                .unwrap();
+            let gate = GateV5c::try_from_addresses(in1 as u64, in2 as u64, out_wire_id as u64)?;
+            writer.write_gate(gate, block.gate_types[i]).await?;
+            gate_index += 1;
         }
         temp_count += block.gates_in_block;
         if temp_count > 1_000_000 {
@@ -76,33 +128,62 @@ pub async fn prealloc(input: &str, output: &str) {
     }
     pb.finish();
 
+    // Constants and primary inputs resolve to their reserved slots
     let outputs = reader
         .outputs()
         .iter()
-        .map(|o| {
-            lookup_wire::<true>(&mut wire_map, &mut slab, *o, header.primary_inputs)
-                .expect("output wire to be produced by a gate or passed from inputs")
-                as u32
+        .enumerate()
+        .map(|(i, o)| {
+            let idx = lookup_wire::<true>(&mut wire_map, &mut slab, *o, header.primary_inputs)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "output {} (wire {}) is not live at the end of the circuit",
+                            i, o
+                        ),
+                    )
+                })?;
+            u32::try_from(idx).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("output {} address {} does not fit in 32 bits", i, idx),
+                )
+            })
         })
-        .collect();
+        .collect::<Result<Vec<_>>>()?;
 
     writer
         .finalize(slab.max_allocated_concurrently() as u64, outputs)
-        .await
-        .unwrap();
+        .await?;
+    Ok(())
+}
+
+fn dead_wire(wire: AbsoluteWireId) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("wire {} is not live", wire))
+}
+
+fn duplicate_output(wire: AbsoluteWireId, gate_index: u64) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!(
+            "gate {} writes wire {}, which is already live",
+            gate_index, wire
+        ),
+    )
 }
 
-type AbsoluteWireId = u64;
+pub(crate) type AbsoluteWireId = u64;
 
 #[derive(Debug)]
-struct WireEntry {
-    slab_idx: usize,
-    credits_remaining: u32,
+pub(crate) struct WireEntry {
+    pub(crate) slab_idx: usize,
+    pub(crate) credits_remaining: u32,
 }
 
-type WireMap = HashMap<AbsoluteWireId, WireEntry>;
+pub(crate) type WireMap = HashMap<AbsoluteWireId, WireEntry>;
 
-fn lookup_wire<const IGNORE_CREDS: bool>(
+pub(crate) fn lookup_wire<const IGNORE_CREDS: bool>(
     map: &mut WireMap,
     slab: &mut FakeSlabAllocator,
     wire: AbsoluteWireId,
@@ -119,6 +200,8 @@ fn lookup_wire<const IGNORE_CREDS: bool>(
     let idx = entry.get().slab_idx;
     if !IGNORE_CREDS {
         match entry.get().credits_remaining {
+            // Output wires are never freed
+            CREDITS_OUTPUT => {}
             1 => {
                 entry.remove();
                 slab.deallocate(idx);
@@ -128,3 +211,219 @@ fn lookup_wire<const IGNORE_CREDS: bool>(
     }
     Some(idx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{write_gates_v5a, write_v5a};
+    use adder::generate_adder;
+
+    #[monoio::test]
+    async fn test_tiny_scratch_budget_fails_early() {
+        let circuit = generate_adder(8);
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("adder.v5a");
+        let output = dir.path().join("adder.v5c");
+        write_v5a(&circuit, &input).await;
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+        // Room for constants and inputs plus a single gate output
+        let budget = circuit.primary_inputs + 3;
+        let err = prealloc_with_budget(input, output, Some(budget))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::OutOfMemory);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "scratch space budget of {} slots exceeded at gate 1 (needs at least {})",
+                budget,
+                budget + 1
+            )
+        );
+        assert!(!std::path::Path::new(output).exists());
+
+        // Too small to even hold the primary inputs
+        let err = prealloc_with_budget(input, output, Some(4))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("by constants and primary inputs"));
+
+        // The exact scratch space needed is accepted
+        prealloc_with_budget(input, output, None).await.unwrap();
+        let scratch = ReaderV5c::open(output).unwrap().header().scratch_space;
+        prealloc_with_budget(input, output, Some(scratch))
+            .await
+            .unwrap();
+        assert!(prealloc_with_budget(input, output, Some(scratch - 1))
+            .await
+            .is_err());
+    }
+
+    #[monoio::test]
+    async fn test_duplicate_output_wire_is_an_error() {
+        use ckt_fmtv5_types::v5::a::{GateV5a, CREDITS_OUTPUT};
+        use ckt_fmtv5_types::GateType;
+
+        let gate = |in1, in2, out, credits| GateV5a {
+            in1,
+            in2,
+            out,
+            credits,
+            gate_type: GateType::XOR,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("dup.v5c");
+
+        // Gate 1 overwrites wire 4 while gate 2 still has to read it, and
+        // gate 0 of the second circuit overwrites primary input 3
+        let cases = [
+            (
+                vec![
+                    gate(2, 3, 4, 1),
+                    gate(2, 3, 4, 1),
+                    gate(4, 2, 5, CREDITS_OUTPUT),
+                ],
+                "gate 1 writes wire 4, which is already live",
+            ),
+            (
+                vec![gate(2, 2, 3, CREDITS_OUTPUT)],
+                "gate 0 writes wire 3, which is already live",
+            ),
+        ];
+        for (n, (gates, message)) in cases.into_iter().enumerate() {
+            let input = dir.path().join(format!("dup{}.v5a", n));
+            let out = gates.last().unwrap().out;
+            write_gates_v5a(&input, 2, &[out], &gates).await;
+
+            let err = prealloc_with_budget(input.to_str().unwrap(), output.to_str().unwrap(), None)
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[monoio::test]
+    async fn test_single_gate_single_output_circuit() {
+        use ckt_fmtv5_types::v5::a::{GateV5a, CREDITS_OUTPUT};
+        use ckt_fmtv5_types::GateType;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("one.v5a");
+        let output = dir.path().join("one.v5c");
+
+        // One AND of the two primary inputs, and its result is the only output
+        let gate = GateV5a {
+            in1: 2,
+            in2: 3,
+            out: 4,
+            credits: CREDITS_OUTPUT,
+            gate_type: GateType::AND,
+        };
+        write_gates_v5a(&input, 2, &[4], &[gate]).await;
+
+        prealloc_with_budget(input.to_str().unwrap(), output.to_str().unwrap(), None)
+            .await
+            .unwrap();
+        assert!(verify_v5c_checksum(&output).await.unwrap());
+
+        let mut reader = ReaderV5c::open(&output).unwrap();
+        let header = *reader.header();
+        assert_eq!(header.num_outputs, 1);
+        assert_eq!((header.and_gates, header.xor_gates), (1, 0));
+        // Constants and inputs take slots 0..4, so the gate lands in slot 4
+        assert_eq!(header.scratch_space, 5);
+        assert_eq!(reader.outputs(), &[4]);
+
+        let chunk = reader.next_blocks_chunk().await.unwrap().unwrap();
+        let block = chunk.blocks_iter().next().unwrap();
+        assert_eq!((block.gates[0].in1, block.gates[0].in2), (2, 3));
+        assert_eq!(block.gates[0].out, 4);
+        assert_eq!(block.gate_type(0), GateType::AND);
+    }
+
+    #[monoio::test]
+    async fn test_output_wire_read_by_later_gates_stays_live() {
+        use ckt_fmtv5_types::v5::a::{GateV5a, CREDITS_OUTPUT};
+        use ckt_fmtv5_types::GateType;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("reused.v5a");
+        let output = dir.path().join("reused.v5c");
+
+        // Wire 4 is an output that two later gates also read
+        let gate = |in1, in2, out, credits| GateV5a {
+            in1,
+            in2,
+            out,
+            credits,
+            gate_type: GateType::XOR,
+        };
+        let gates = [
+            gate(2, 3, 4, CREDITS_OUTPUT),
+            gate(4, 2, 5, 1),
+            gate(5, 4, 6, CREDITS_OUTPUT),
+        ];
+        write_gates_v5a(&input, 2, &[4, 6], &gates).await;
+
+        prealloc_with_budget(input.to_str().unwrap(), output.to_str().unwrap(), None)
+            .await
+            .unwrap();
+
+        let mut reader = ReaderV5c::open(&output).unwrap();
+        assert_eq!(reader.outputs()[0], 4);
+        let chunk = reader.next_blocks_chunk().await.unwrap().unwrap();
+        let block = chunk.blocks_iter().next().unwrap();
+        assert_eq!(block.gates[0].out, 4);
+        assert_eq!(block.gates[1].in1, 4);
+        assert_eq!(block.gates[2].in2, 4);
+        assert_ne!(block.gates[2].out, 4);
+    }
+
+    #[monoio::test]
+    async fn test_primary_input_output_resolves_to_reserved_slot() {
+        use ckt_fmtv5_types::v5::a::{GateV5a, CREDITS_OUTPUT};
+        use ckt_fmtv5_types::GateType;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.v5c");
+
+        // Wire 10 is freed by gate 1, whose output then reuses its slot
+        let gates = [
+            GateV5a {
+                in1: 2,
+                in2: 3,
+                out: 10,
+                credits: 1,
+                gate_type: GateType::XOR,
+            },
+            GateV5a {
+                in1: 10,
+                in2: 2,
+                out: 11,
+                credits: CREDITS_OUTPUT,
+                gate_type: GateType::AND,
+            },
+        ];
+        let convert = |name: &str, outputs: Vec<u64>| {
+            let input = dir.path().join(name);
+            let output = output.clone();
+            async move {
+                write_gates_v5a(&input, 2, &outputs, &gates).await;
+                prealloc_with_budget(input.to_str().unwrap(), output.to_str().unwrap(), None).await
+            }
+        };
+
+        // Primary input 3 keeps its reserved slot next to the gate output
+        convert("ok.v5a", vec![3, 11]).await.unwrap();
+        assert_eq!(ReaderV5c::open(&output).unwrap().outputs(), &[3, 4]);
+
+        let err = convert("bad.v5a", vec![11, 12]).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "output 1 (wire 12) is not live at the end of the circuit"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Search circuits for gates touching given wires.
+//!
+//! In v5a, gates name wire IDs, so a search finds the one gate producing a
+//! wire and every gate consuming it. Matches are reported with the gate's
+//! credits, so users can see how widely an output wire is consumed without
+//! a second pass.
+//!
+//! In v5c, gates name memory addresses, which are reused once a value dies.
+//! A v5c search matches addresses, and so finds every gate that ever writes
+//! or reads a slot, across all the values it holds.
+
+use std::io::Result;
+use std::path::Path;
+
+use ckt_fmtv5_types::v5::a::{reader::CircuitReaderV5a, GateV5a};
+use ckt_fmtv5_types::v5::c::{GateV5c, ReaderV5c};
+use ckt_fmtv5_types::GateType;
+
+/// A gate matching one of the searched wires.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    /// Index of the gate in file order.
+    pub gate_index: u64,
+    /// The matched gate, including its credits.
+    pub gate: GateV5a,
+    /// Why the gate matched, e.g. `input1=42` or `output=42`.
+    pub reasons: Vec<String>,
+}
+
+/// A v5c gate matching one of the searched addresses.
+#[derive(Debug, Clone)]
+pub struct SearchMatchV5c {
+    /// Index of the gate in file order.
+    pub gate_index: u64,
+    /// The matched gate.
+    pub gate: GateV5c,
+    /// The gate's type.
+    pub gate_type: GateType,
+    /// Why the gate matched, e.g. `input1=42` or `output=42`.
+    pub reasons: Vec<String>,
+}
+
+/// Return the reasons a gate matches, or an empty list if it doesn't.
+pub fn match_gate(gate: &GateV5a, inputs: &[u64], outputs: &[u64]) -> Vec<String> {
+    match_wires([gate.in1, gate.in2, gate.out], inputs, outputs)
+}
+
+fn match_wires([in1, in2, out]: [u64; 3], inputs: &[u64], outputs: &[u64]) -> Vec<String> {
+    let mut reasons = Vec::new();
+    for &input in inputs {
+        if in1 == input {
+            reasons.push(format!("input1={}", input));
+        }
+        if in2 == input {
+            reasons.push(format!("input2={}", input));
+        }
+    }
+    for &output in outputs {
+        if out == output {
+            reasons.push(format!("output={}", output));
+        }
+    }
+    reasons
+}
+
+/// Stream a v5a file and collect every gate reading one of `inputs` or
+/// writing one of `outputs`.
+pub async fn search_v5a(
+    path: impl AsRef<Path>,
+    inputs: &[u64],
+    outputs: &[u64],
+) -> Result<Vec<SearchMatch>> {
+    let mut reader = CircuitReaderV5a::open(path)?;
+    let mut matches = Vec::new();
+    let mut gate_index = 0u64;
+
+    while let Some(block) = reader.next_block_soa().await? {
+        for gate in block.gates() {
+            let reasons = match_gate(&gate, inputs, outputs);
+            if !reasons.is_empty() {
+                matches.push(SearchMatch {
+                    gate_index,
+                    gate,
+                    reasons,
+                });
+            }
+            gate_index += 1;
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Stream a v5c file and collect every gate reading one of the `inputs`
+/// addresses or writing one of the `outputs` addresses.
+pub async fn search_v5c(
+    path: impl AsRef<Path>,
+    inputs: &[u64],
+    outputs: &[u64],
+) -> Result<Vec<SearchMatchV5c>> {
+    let mut reader = ReaderV5c::open(path)?;
+    let header = *reader.header();
+    let mut matches = Vec::new();
+    let mut gate_index = 0u64;
+    let mut block_index = 0;
+
+    while let Some(chunk) = reader.next_blocks_chunk().await? {
+        for block in chunk.blocks_iter() {
+            for g in block.iter_gates(header.block_num_gates(block_index)) {
+                let wires = [g.gate.in1, g.gate.in2, g.gate.out].map(u64::from);
+                let reasons = match_wires(wires, inputs, outputs);
+                if !reasons.is_empty() {
+                    matches.push(SearchMatchV5c {
+                        gate_index,
+                        gate: g.gate,
+                        gate_type: g.gate_type,
+                        reasons,
+                    });
+                }
+                gate_index += 1;
+            }
+            block_index += 1;
+        }
+    }
+
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{write_v5a, write_v5c};
+    use adder::generate_adder;
+
+    #[monoio::test]
+    async fn test_search_reports_credits() {
+        let circuit = generate_adder(8);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5a");
+        write_v5a(&circuit, &path).await;
+
+        // Bit 3's half_sum feeds both the sum and carry-propagate gates
+        let half_sum = circuit.gates[15].out;
+
+        let producers = search_v5a(&path, &[], &[half_sum]).await.unwrap();
+        assert_eq!(producers.len(), 1);
+        assert_eq!(producers[0].gate_index, 15);
+        assert_eq!(producers[0].gate.credits, 2);
+        assert_eq!(producers[0].reasons, vec![format!("output={}", half_sum)]);
+
+        // The reported credits match the number of consumers found
+        let consumers = search_v5a(&path, &[half_sum], &[]).await.unwrap();
+        assert_eq!(consumers.len() as u32, producers[0].gate.credits);
+        assert!(consumers.iter().all(|m| m.gate.in1 == half_sum));
+    }
+
+    #[monoio::test]
+    async fn test_search_v5c_matches_addresses() {
+        let circuit = generate_adder(8);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5c");
+
+        // Each address holds one value, so the matches are those of the
+        // v5a search
+        write_v5c(&circuit, &path).await;
+
+        let half_sum = circuit.gates[15].out;
+        let matches = search_v5c(&path, &[half_sum], &[half_sum]).await.unwrap();
+        let indices: Vec<u64> = matches.iter().map(|m| m.gate_index).collect();
+        let expected: Vec<u64> = (0..circuit.gates.len() as u64)
+            .filter(|&i| {
+                !match_gate(&circuit.gates[i as usize], &[half_sum], &[half_sum]).is_empty()
+            })
+            .collect();
+        assert_eq!(indices, expected);
+        assert_eq!(indices.len(), 3);
+        assert_eq!(matches[0].gate_index, 15);
+        assert_eq!(matches[0].gate_type, circuit.gates[15].gate_type);
+        assert_eq!(matches[0].reasons, vec![format!("output={}", half_sum)]);
+        assert!(matches[1..].iter().all(|m| m.gate.in1 as u64 == half_sum));
+    }
+}
@@ -1,9 +1,27 @@
 use roaring::RoaringBitmap;
 
+#[derive(Debug)]
 pub struct FakeSlabAllocator {
     next_free: usize,
     free_list: RoaringBitmap,
     max_allocated: usize, // Track peak allocation count
+    total_allocations: u64,
+    total_frees: u64,
+}
+
+/// Snapshot of a [`FakeSlabAllocator`]'s usage, from [`FakeSlabAllocator::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlabStats {
+    /// Maximum number of slots allocated concurrently
+    pub high_water: usize,
+    /// Slots currently allocated
+    pub live: usize,
+    /// Number of `allocate` calls
+    pub total_allocations: u64,
+    /// Number of `deallocate` calls that freed a live slot
+    pub total_frees: u64,
+    /// Freed slots below the fresh-index frontier waiting to be reused
+    pub free_list_len: usize,
 }
 
 impl FakeSlabAllocator {
@@ -13,6 +31,8 @@ impl FakeSlabAllocator {
             next_free: 0,
             free_list: RoaringBitmap::new(),
             max_allocated: 0,
+            total_allocations: 0,
+            total_frees: 0,
         }
     }
 
@@ -22,6 +42,8 @@ impl FakeSlabAllocator {
             next_free: start,
             free_list: RoaringBitmap::new(),
             max_allocated: 0,
+            total_allocations: 0,
+            total_frees: 0,
         }
     }
 
@@ -37,6 +59,7 @@ impl FakeSlabAllocator {
             self.next_free += 1;
             index
         };
+        self.total_allocations += 1;
 
         // Update max_allocated if we've hit a new peak
         let current_allocated = self.allocated_count();
@@ -53,6 +76,7 @@ impl FakeSlabAllocator {
         // Only add if it's less than next_free (was previously allocated)
         if index < self.next_free && !self.free_list.contains(index as u32) {
             self.free_list.insert(index as u32);
+            self.total_frees += 1;
         }
     }
 
@@ -76,6 +100,20 @@ impl FakeSlabAllocator {
         self.max_allocated
     }
 
+    /// Get usage counters, e.g. to check how compact the handed-out indices are
+    ///
+    /// A free list that stays long relative to `high_water` means freed slots
+    /// are scattered below the frontier rather than reused promptly.
+    pub fn stats(&self) -> SlabStats {
+        SlabStats {
+            high_water: self.max_allocated,
+            live: self.allocated_count(),
+            total_allocations: self.total_allocations,
+            total_frees: self.total_frees,
+            free_list_len: self.free_list.len() as usize,
+        }
+    }
+
     /// Reset the max allocated counter (useful for monitoring periods)
     pub fn reset_max_allocated(&mut self) {
         self.max_allocated = self.allocated_count();
@@ -157,4 +195,32 @@ mod tests {
 
         assert_eq!(allocator.max_allocated_concurrently(), 5);
     }
+
+    #[test]
+    fn test_stats_after_freeing_the_middle() {
+        let mut allocator = FakeSlabAllocator::new();
+        let indices: Vec<usize> = (0..100).map(|_| allocator.allocate()).collect();
+        for &i in &indices[25..75] {
+            allocator.deallocate(i);
+        }
+        // Freeing twice is not counted
+        allocator.deallocate(indices[50]);
+
+        assert_eq!(
+            allocator.stats(),
+            SlabStats {
+                high_water: 100,
+                live: 50,
+                total_allocations: 100,
+                total_frees: 50,
+                free_list_len: 50,
+            }
+        );
+
+        // Reuse drains the free list before touching fresh indices
+        assert_eq!(allocator.allocate(), 25);
+        let stats = allocator.stats();
+        assert_eq!((stats.live, stats.free_list_len), (51, 49));
+        assert_eq!(stats.total_allocations, 101);
+    }
 }
@@ -0,0 +1,60 @@
+//! Circuit fixtures shared by the unit tests.
+
+use std::path::Path;
+
+use adder::AdderCircuit;
+use ckt_fmtv5_types::v5::a::writer::CircuitWriterV5a;
+use ckt_fmtv5_types::v5::a::GateV5a;
+use ckt_fmtv5_types::v5::c::{GateV5c, WriterV5c};
+
+/// Write `gates` as a v5a file.
+pub(crate) async fn write_gates_v5a(
+    path: &Path,
+    primary_inputs: u64,
+    outputs: &[u64],
+    gates: &[GateV5a],
+) {
+    let mut writer = CircuitWriterV5a::new(path, primary_inputs, outputs.to_vec(), [0u8; 32])
+        .await
+        .unwrap();
+    writer.write_gates(gates).await.unwrap();
+    writer.finalize().await.unwrap();
+}
+
+/// Write `circuit` as a v5a file.
+pub(crate) async fn write_v5a(circuit: &AdderCircuit, path: &Path) {
+    write_gates_v5a(
+        path,
+        circuit.primary_inputs,
+        &circuit.outputs,
+        &circuit.gates,
+    )
+    .await;
+}
+
+/// Write `circuit` as a v5c file without preallocation.
+///
+/// Wire IDs double as addresses, so no slot is ever reused and each address
+/// holds a single value.
+pub(crate) async fn write_v5c(circuit: &AdderCircuit, path: &Path) {
+    let mut writer = WriterV5c::new(
+        path,
+        circuit.primary_inputs,
+        circuit.outputs.len() as u64,
+        [0u8; 32],
+    )
+    .await
+    .unwrap();
+    for g in &circuit.gates {
+        writer
+            .write_gate(
+                GateV5c::new(g.in1 as u32, g.in2 as u32, g.out as u32),
+                g.gate_type,
+            )
+            .await
+            .unwrap();
+    }
+    let scratch_space = circuit.gates.iter().map(|g| g.out).max().unwrap() + 1;
+    let outputs = circuit.outputs.iter().map(|&w| w as u32).collect();
+    writer.finalize(scratch_space, outputs).await.unwrap();
+}
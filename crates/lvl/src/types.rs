@@ -1,4 +1,6 @@
 use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Result};
+
 /// A compact 34-bit wire identifier stored in 5 bytes.
 ///
 /// Wire IDs are used throughout the levelling algorithm to identify circuit wires.
@@ -31,6 +33,22 @@ impl CompactWireId {
         Self(bytes)
     }
 
+    /// Creates a CompactWireId from a u64, failing if it needs more than 34
+    /// bits.
+    ///
+    /// Use this for wire IDs read from files, where an oversized ID is a
+    /// format violation that [`from_u64`](Self::from_u64) would silently
+    /// alias onto another wire.
+    pub fn try_from_u64(value: u64) -> Result<Self> {
+        if value > 0x3_FFFF_FFFF {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("wire ID {} exceeds 34-bit limit", value),
+            ));
+        }
+        Ok(Self::from_u64(value))
+    }
+
     /// Converts the CompactWireId back to a u64.
     ///
     /// The result is always in the range [0, 0x3_FFFF_FFFF] (34 bits).
@@ -81,3 +99,20 @@ impl std::fmt::Debug for CompactWireId {
         write!(f, "WireId({})", self.to_u64())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_u64_rejects_35_bit_ids() {
+        let max = (1u64 << 34) - 1;
+        assert_eq!(CompactWireId::try_from_u64(max).unwrap().to_u64(), max);
+
+        let err = CompactWireId::try_from_u64(1 << 34).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("exceeds 34-bit limit"));
+        // The unchecked constructor wraps it onto wire 0
+        assert_eq!(CompactWireId::from_u64(1 << 34).to_u64(), 0);
+    }
+}
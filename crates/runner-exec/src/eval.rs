@@ -10,24 +10,34 @@ use ckt_gobble::{
 };
 use ckt_runner_types::{CircuitTask, GateBlock};
 
+use crate::WireTrace;
+
 /// Internal evaluation state.
 pub struct EvalState<R> {
     instance: PlatformEvaluationInstance,
     ct_reader: R,
+    trace: Option<WireTrace>,
 }
 
 /// Output from the evaluation task.
 #[derive(Debug)]
 pub struct EvalTaskOutput {
+    /// The evaluation instance, for applying output translation after
+    /// evaluation.
+    pub instance: PlatformEvaluationInstance,
     /// The output wire labels.
     pub output_labels: Vec<[u8; 16]>,
     /// The output values.
     pub output_values: Vec<bool>,
+    /// The value decoded from every gate's output label, if tracing was
+    /// enabled.
+    pub trace: Option<WireTrace>,
 }
 
 /// Task for evaluating a garbled circuit.
 pub struct EvalTask<'c, R> {
     eval_config: EvaluationInstanceConfig<'c>,
+    trace: bool,
     _phantom: std::marker::PhantomData<R>,
 }
 
@@ -36,9 +46,18 @@ impl<'c, R> EvalTask<'c, R> {
     pub fn new(eval_config: EvaluationInstanceConfig<'c>) -> Self {
         Self {
             eval_config,
+            trace: false,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Record the value decoded from every gate's output label, for
+    /// comparing against cleartext execution with
+    /// [`first_divergence`](crate::first_divergence).
+    pub fn with_trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
 }
 
 impl<'c, R: Read> CircuitTask for EvalTask<'c, R> {
@@ -49,16 +68,21 @@ impl<'c, R: Read> CircuitTask for EvalTask<'c, R> {
 
     fn initialize(
         &self,
-        _header: &HeaderV5c,
+        header: &HeaderV5c,
         ct_reader: Self::InitInput,
     ) -> Result<Self::State, Self::Error> {
         // Create the engine and evaluation instance.
         let engine = Engine::new();
         let instance = engine.new_evaluation_instance(self.eval_config);
 
+        let trace = self
+            .trace
+            .then(|| WireTrace::with_capacity(header.total_gates() as usize));
+
         Ok(EvalState {
             instance,
             ct_reader,
+            trace,
         })
     }
 
@@ -91,6 +115,12 @@ impl<'c, R: Read> CircuitTask for EvalTask<'c, R> {
                     ginfo.out as usize,
                 ),
             }
+
+            if let Some(trace) = &mut state.trace {
+                let mut value = [false];
+                state.instance.get_values(&[ginfo.out as u64], &mut value);
+                trace.push(ginfo.out, value[0]);
+            }
         }
 
         Ok(())
@@ -115,8 +145,10 @@ impl<'c, R: Read> CircuitTask for EvalTask<'c, R> {
             .get_values(output_wire_idxs, &mut output_values);
 
         Ok(EvalTaskOutput {
+            instance: state.instance,
             output_labels,
             output_values,
+            trace: state.trace,
         })
     }
 
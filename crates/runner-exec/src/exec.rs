@@ -2,17 +2,21 @@
 
 use std::io::{Error, ErrorKind};
 
+use bitvec::vec::BitVec;
 use ckt_fmtv5_types::GateType;
 use ckt_fmtv5_types::v5::c::HeaderV5c;
 use ckt_gobble::{
     Engine, ExecutionInstance as PlatformExecutionInstance,
     traits::{ExecutionInstance, ExecutionInstanceConfig, GobbleEngine},
 };
-use ckt_runner_types::{CircuitTask, GateBlock};
+use ckt_runner_types::{CircuitReader, CircuitTask, GateBlock};
+
+use crate::{WireTrace, process_task};
 
 /// Internal execution state.
 pub struct ExecState {
     instance: PlatformExecutionInstance,
+    trace: Option<WireTrace>,
 }
 
 /// Output from the execution task.
@@ -20,17 +24,30 @@ pub struct ExecState {
 pub struct ExecTaskOutput {
     /// The output values.
     pub output_values: Vec<bool>,
+    /// The value written by every gate, if tracing was enabled.
+    pub trace: Option<WireTrace>,
 }
 
 /// Task for executing a circuit in plaintext.
 pub struct ExecTask<'c> {
     exec_config: ExecutionInstanceConfig<'c>,
+    trace: bool,
 }
 
 impl<'c> ExecTask<'c> {
     /// Create a new execution task with the given configuration.
     pub fn new(exec_config: ExecutionInstanceConfig<'c>) -> Self {
-        Self { exec_config }
+        Self {
+            exec_config,
+            trace: false,
+        }
+    }
+
+    /// Record the value of every gate output, for comparing against a
+    /// garbled evaluation with [`first_divergence`](crate::first_divergence).
+    pub fn with_trace(mut self) -> Self {
+        self.trace = true;
+        self
     }
 }
 
@@ -67,7 +84,11 @@ impl<'c> CircuitTask for ExecTask<'c> {
         let engine = Engine::new();
         let instance = engine.new_execution_instance(self.exec_config);
 
-        Ok(ExecState { instance })
+        let trace = self
+            .trace
+            .then(|| WireTrace::with_capacity(header.total_gates() as usize));
+
+        Ok(ExecState { instance, trace })
     }
 
     fn on_block(&self, state: &mut Self::State, block: &GateBlock<'_>) -> Result<(), Self::Error> {
@@ -87,6 +108,12 @@ impl<'c> CircuitTask for ExecTask<'c> {
                     ginfo.out as usize,
                 ),
             }
+
+            if let Some(trace) = &mut state.trace {
+                let mut value = [false];
+                state.instance.get_values(&[ginfo.out as u64], &mut value);
+                trace.push(ginfo.out, value[0]);
+            }
         }
 
         Ok(())
@@ -106,10 +133,59 @@ impl<'c> CircuitTask for ExecTask<'c> {
             .instance
             .get_values(output_wire_idxs, &mut output_values);
 
-        Ok(ExecTaskOutput { output_values })
+        Ok(ExecTaskOutput {
+            output_values,
+            trace: state.trace,
+        })
     }
 
     fn on_abort(&self, _state: Self::State) {
         // Nothing to clean up for execution.
     }
 }
+
+/// Execute a whole circuit in plaintext and return its output bits.
+///
+/// Sizes the working space from the header and runs an [`ExecTask`]
+/// through [`process_task`], for callers that don't need to wrap the task.
+pub async fn execute<R: CircuitReader>(
+    reader: &mut R,
+    inputs: &BitVec,
+) -> anyhow::Result<Vec<bool>> {
+    let config = ExecutionInstanceConfig {
+        scratch_space: reader.header().scratch_space as u32,
+        input_values: inputs,
+    };
+    let output = process_task(&ExecTask::new(config), (), reader).await?;
+    Ok(output.output_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use adder::generate_adder;
+    use ckt_fmtv5_types::v5::c::ReaderV5c;
+
+    use super::*;
+    use crate::ReaderV5cWrapper;
+    use crate::test_util::write_v5c;
+
+    fn to_bits(value: u64, n: usize) -> impl Iterator<Item = bool> {
+        (0..n).map(move |i| (value >> i) & 1 == 1)
+    }
+
+    #[monoio::test]
+    async fn test_execute_adder_sum() {
+        let n_bits = 16;
+        let circuit = generate_adder(n_bits);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5c");
+
+        write_v5c(&circuit, &path).await;
+
+        let (a, b) = (51_234u64, 40_000u64);
+        let inputs: BitVec = to_bits(a, n_bits).chain(to_bits(b, n_bits)).collect();
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap());
+        let sum = execute(&mut reader, &inputs).await.unwrap();
+        assert_eq!(sum, to_bits(a + b, n_bits + 1).collect::<Vec<_>>());
+    }
+}
@@ -40,6 +40,7 @@ impl fmt::Debug for GarbleTaskOutput {
 /// Task for garbling a circuit.
 pub struct GarbleTask<'c, W> {
     garb_config: GarblingInstanceConfig<'c>,
+    prefetch_distance: usize,
     _phantom: std::marker::PhantomData<W>,
 }
 
@@ -48,9 +49,22 @@ impl<'c, W> GarbleTask<'c, W> {
     pub fn new(garb_config: GarblingInstanceConfig<'c>) -> Self {
         Self {
             garb_config,
+            prefetch_distance: 0,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Prefetch the input labels of the gate `distance` gates ahead while
+    /// garbling each gate, within a block. 0, the default, disables it.
+    ///
+    /// Only pays off when the working space is far larger than the cache
+    /// and gates read it in no particular order; the ciphertexts and labels
+    /// are the same either way. Around 8 works well on x86_64, while much
+    /// larger distances evict labels before they are used.
+    pub fn with_prefetch_distance(mut self, distance: usize) -> Self {
+        self.prefetch_distance = distance;
+        self
+    }
 }
 
 impl<'c, W: Write> CircuitTask for GarbleTask<'c, W> {
@@ -90,7 +104,15 @@ impl<'c, W: Write> CircuitTask for GarbleTask<'c, W> {
     }
 
     fn on_block(&self, state: &mut Self::State, block: &GateBlock<'_>) -> Result<(), Self::Error> {
-        for (ginfo, gty) in block.gates_iter() {
+        let gates = block.gates();
+        for (i, (ginfo, gty)) in block.gates_iter().enumerate() {
+            if self.prefetch_distance > 0
+                && let Some(ahead) = gates.get(i + self.prefetch_distance)
+            {
+                state.instance.prefetch_wire(ahead.in1 as usize);
+                state.instance.prefetch_wire(ahead.in2 as usize);
+            }
+
             match gty {
                 GateType::AND => {
                     let ct = state.instance.feed_and_gate(
@@ -153,3 +175,64 @@ impl<'c, W: Write> CircuitTask for GarbleTask<'c, W> {
             .expect("garble: flush output table on abort");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ckt_fmtv5_types::v5::c::{GateV5c, ReaderV5c, WriterV5c};
+    use ckt_gobble::HashScheme;
+
+    use super::*;
+    use crate::{ReaderV5cWrapper, process_task};
+
+    const SCRATCH_SPACE: u32 = 1 << 16;
+
+    #[monoio::test]
+    async fn test_prefetching_garbles_identically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scattered.v5c");
+
+        // More than one block, reading and writing all over working space
+        let mut writer = WriterV5c::new(&path, 4, 2, [0u8; 32]).await.unwrap();
+        let addr = |x: u32| 6 + x.wrapping_mul(0x9E37_79B9) % (SCRATCH_SPACE - 6);
+        for i in 0..30_000u32 {
+            let gate_type = if i % 2 == 0 {
+                GateType::AND
+            } else {
+                GateType::XOR
+            };
+            let gate = GateV5c::new(addr(3 * i) % (i + 6), addr(3 * i + 1) % (i + 6), addr(i));
+            writer.write_gate(gate, gate_type).await.unwrap();
+        }
+        writer
+            .finalize(SCRATCH_SPACE as u64, vec![addr(0), addr(1)])
+            .await
+            .unwrap();
+
+        let input_labels: Vec<[u8; 16]> = (0..4u8).map(|i| [0xA0 + i; 16]).collect();
+        let config = GarblingInstanceConfig {
+            scratch_space: SCRATCH_SPACE,
+            delta: [0x11; 16],
+            primary_input_false_labels: &input_labels,
+            aes128_key: [0x22; 16],
+            public_s: [0x33; 16],
+            constant_zero_label: [0x44; 16],
+            constant_one_label: [0x55; 16],
+            hash_scheme: HashScheme::Ccrnd,
+        };
+
+        let mut garbled = Vec::new();
+        for distance in [0, 1, 8, 64] {
+            let task = GarbleTask::new(config).with_prefetch_distance(distance);
+            let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap());
+            let mut ciphertexts = Vec::new();
+            let output = process_task(&task, &mut ciphertexts, &mut reader)
+                .await
+                .unwrap();
+            garbled.push((ciphertexts, output.garbler_output_labels));
+        }
+        assert_eq!(garbled[0].0.len(), 15_000 * 16);
+        for other in &garbled[1..] {
+            assert!(*other == garbled[0]);
+        }
+    }
+}
@@ -8,6 +8,10 @@ mod exec;
 mod garble;
 mod hash_writer;
 mod reader_impl;
+#[cfg(test)]
+mod test_util;
+mod trace;
+mod validate;
 mod worker;
 
 pub use ckt_runner_types::CircuitReader;
@@ -16,4 +20,6 @@ pub use exec::*;
 pub use garble::*;
 pub use hash_writer::*;
 pub use reader_impl::*;
+pub use trace::*;
+pub use validate::*;
 pub use worker::*;
@@ -5,6 +5,8 @@ use std::io;
 use ckt_fmtv5_types::v5::c::{Chunk, HeaderV5c, ReaderV5c};
 use ckt_runner_types::CircuitReader;
 
+use crate::ValidatingReader;
+
 /// Wrapper around [`ReaderV5c`] that implements [`CircuitReader`].
 // this is in a weird place and the inner thing doesn't implement it because I
 // didn't want to think through restructuring the crates again to make it make
@@ -21,6 +23,11 @@ impl ReaderV5cWrapper {
     pub fn into_inner(self) -> ReaderV5c {
         self.0
     }
+
+    /// Wrap this reader so every gate is validated as it is read.
+    pub fn with_validation(self) -> ValidatingReader<Self> {
+        ValidatingReader::new(self)
+    }
 }
 
 impl CircuitReader for ReaderV5cWrapper {
@@ -0,0 +1,34 @@
+//! Circuit fixtures shared by the unit tests.
+
+use std::path::Path;
+
+use adder::AdderCircuit;
+use ckt_fmtv5_types::v5::c::{GateV5c, WriterV5c};
+
+/// Write `circuit` as a v5c file without preallocation, returning its
+/// scratch space.
+///
+/// Wire IDs double as addresses, so no slot is ever reused.
+pub(crate) async fn write_v5c(circuit: &AdderCircuit, path: &Path) -> u64 {
+    let mut writer = WriterV5c::new(
+        path,
+        circuit.primary_inputs,
+        circuit.outputs.len() as u64,
+        [0u8; 32],
+    )
+    .await
+    .unwrap();
+    for g in &circuit.gates {
+        writer
+            .write_gate(
+                GateV5c::new(g.in1 as u32, g.in2 as u32, g.out as u32),
+                g.gate_type,
+            )
+            .await
+            .unwrap();
+    }
+    let scratch_space = circuit.gates.iter().map(|g| g.out).max().unwrap() + 1;
+    let outputs = circuit.outputs.iter().map(|&w| w as u32).collect();
+    writer.finalize(scratch_space, outputs).await.unwrap();
+    scratch_space
+}
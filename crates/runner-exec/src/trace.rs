@@ -0,0 +1,216 @@
+//! Per-gate value traces for debugging garbling mismatches.
+//!
+//! When garbled evaluation decodes to the wrong outputs, the outputs alone
+//! don't say where things went wrong. [`ExecTask::with_trace`] and
+//! [`EvalTask::with_trace`] record the value each gate writes, in gate
+//! order, and [`first_divergence`] lines the two traces up to find the gate
+//! whose decoded label first disagrees with cleartext execution. Since v5c
+//! addresses are reused, a wire is identified by the gate that wrote it
+//! rather than by its address alone.
+
+use std::io::Read;
+use std::path::Path;
+
+use bitvec::vec::BitVec;
+use ckt_fmtv5_types::v5::c::ReaderV5c;
+use ckt_gobble::traits::{EvaluationInstanceConfig, ExecutionInstanceConfig};
+
+use crate::{EvalTask, ExecTask, ReaderV5cWrapper, process_task};
+
+/// The address and value of every gate output, in gate order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WireTrace {
+    addrs: Vec<u32>,
+    values: BitVec,
+}
+
+impl WireTrace {
+    pub(crate) fn with_capacity(gates: usize) -> Self {
+        Self {
+            addrs: Vec::with_capacity(gates),
+            values: BitVec::with_capacity(gates),
+        }
+    }
+
+    pub(crate) fn push(&mut self, addr: u32, value: bool) {
+        self.addrs.push(addr);
+        self.values.push(value);
+    }
+
+    /// Number of gates recorded.
+    pub fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    /// Whether no gates were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.addrs.is_empty()
+    }
+
+    /// The output address and value of gate `index`.
+    pub fn get(&self, index: usize) -> Option<(u32, bool)> {
+        Some((*self.addrs.get(index)?, self.values[index]))
+    }
+}
+
+/// The first gate whose evaluated value disagrees with cleartext execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the gate in circuit order.
+    pub gate_index: usize,
+    /// Working space address the gate writes.
+    pub addr: u32,
+    /// Value from cleartext execution.
+    pub expected: bool,
+    /// Value decoded from the evaluator's label.
+    pub actual: bool,
+}
+
+/// Compare two traces of the same circuit gate by gate.
+///
+/// Returns the first gate where the values differ, or `None` if they agree
+/// on every gate both traces cover.
+///
+/// # Panics
+///
+/// If the traces write to different addresses, meaning they come from
+/// different circuits.
+pub fn first_divergence(expected: &WireTrace, actual: &WireTrace) -> Option<Divergence> {
+    let gates = expected.len().min(actual.len());
+    assert_eq!(
+        expected.addrs[..gates],
+        actual.addrs[..gates],
+        "traces are of different circuits"
+    );
+    let gate_index = (0..gates).find(|&i| expected.values[i] != actual.values[i])?;
+    Some(Divergence {
+        gate_index,
+        addr: expected.addrs[gate_index],
+        expected: expected.values[gate_index],
+        actual: actual.values[gate_index],
+    })
+}
+
+/// Execute and evaluate the v5c circuit at `path` with tracing, returning
+/// the first gate where the garbled evaluation goes wrong.
+///
+/// The cleartext inputs are the evaluator's selected input values, so the
+/// two runs agree on every gate unless a label or ciphertext is bad.
+pub async fn find_divergence<C: Read>(
+    path: impl AsRef<Path>,
+    eval_config: EvaluationInstanceConfig<'_>,
+    ct_reader: C,
+) -> anyhow::Result<Option<Divergence>> {
+    let path = path.as_ref();
+
+    let exec_config = ExecutionInstanceConfig {
+        scratch_space: eval_config.scratch_space,
+        input_values: eval_config.selected_primary_input_values,
+    };
+    let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(path)?);
+    let executed = process_task(&ExecTask::new(exec_config).with_trace(), (), &mut reader).await?;
+
+    let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(path)?);
+    let evaluated = process_task(
+        &EvalTask::new(eval_config).with_trace(),
+        ct_reader,
+        &mut reader,
+    )
+    .await?;
+
+    let expected = executed.trace.expect("tracing enabled");
+    let actual = evaluated.trace.expect("tracing enabled");
+    Ok(first_divergence(&expected, &actual))
+}
+
+#[cfg(test)]
+mod tests {
+    use adder::generate_adder;
+    use ckt_fmtv5_types::GateType;
+    use ckt_gobble::HashScheme;
+    use ckt_gobble::traits::{GarblingInstance, GarblingInstanceConfig};
+
+    use super::*;
+    use crate::GarbleTask;
+    use crate::test_util::write_v5c;
+
+    #[monoio::test]
+    async fn test_corrupted_ciphertext_is_pinpointed() {
+        let n_bits = 16;
+        let circuit = generate_adder(n_bits);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adder.v5c");
+
+        let scratch_space = write_v5c(&circuit, &path).await;
+
+        let false_labels: Vec<[u8; 16]> = (0..circuit.primary_inputs as u8)
+            .map(|i| [0xA0 ^ i; 16])
+            .collect();
+        let garbling_config = GarblingInstanceConfig {
+            scratch_space: scratch_space as u32,
+            delta: [0x11; 16],
+            primary_input_false_labels: &false_labels,
+            aes128_key: [0x22; 16],
+            public_s: [0x33; 16],
+            constant_zero_label: [0x44; 16],
+            constant_one_label: [0x55; 16],
+            hash_scheme: HashScheme::Ccrnd,
+        };
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap());
+        let mut ciphertexts = Vec::new();
+        let garbled = process_task(
+            &GarbleTask::new(garbling_config),
+            &mut ciphertexts,
+            &mut reader,
+        )
+        .await
+        .unwrap();
+
+        // With every bit of `a` set, an AND gate reading `a` directly uses
+        // its ciphertext, so corrupting it must change the output label
+        let inputs: BitVec = (0..2 * n_bits).map(|i| i < n_bits || i % 3 == 0).collect();
+        let input_wires: Vec<u64> = (2..2 + circuit.primary_inputs).collect();
+        let mut input_labels = vec![[0u8; 16]; input_wires.len()];
+        garbled
+            .instance
+            .get_selected_labels(&input_wires, &inputs, &mut input_labels);
+        let eval_config = EvaluationInstanceConfig {
+            scratch_space: scratch_space as u32,
+            selected_primary_input_labels: &input_labels,
+            selected_primary_input_values: &inputs,
+            aes128_key: garbling_config.aes128_key,
+            public_s: garbling_config.public_s,
+            constant_zero_label: garbling_config.constant_zero_label,
+            constant_one_label: garbling_config.constant_one_label,
+            hash_scheme: garbling_config.hash_scheme,
+        };
+
+        let untouched = find_divergence(&path, eval_config, ciphertexts.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(untouched, None);
+
+        let a_wires = 2..2 + n_bits as u64;
+        let (gate_index, and_index) = circuit
+            .gates
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| g.gate_type == GateType::AND)
+            .enumerate()
+            .filter(|(_, (_, g))| a_wires.contains(&g.in1))
+            .map(|(and_index, (gate_index, _))| (gate_index, and_index))
+            .nth(5)
+            .unwrap();
+        for byte in &mut ciphertexts[16 * and_index..16 * (and_index + 1)] {
+            *byte ^= 0xFF;
+        }
+
+        let divergence = find_divergence(&path, eval_config, ciphertexts.as_slice())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.gate_index, gate_index);
+        assert_eq!(divergence.addr, circuit.gates[gate_index].out as u32);
+        assert_ne!(divergence.expected, divergence.actual);
+    }
+}
@@ -0,0 +1,174 @@
+//! [`CircuitReader`] wrapper that validates gates as they are read.
+
+use std::io::{self, Error, ErrorKind};
+
+use ckt_fmtv5_types::v5::c::{Chunk, HeaderV5c};
+use ckt_runner_types::CircuitReader;
+
+/// Wrapper around a [`CircuitReader`] that checks every gate's addresses
+/// against the header's scratch space before handing a chunk out.
+///
+/// Meant for untrusted circuit files, where an out-of-range address would
+/// otherwise only be caught (or not) by the task consuming the block. The
+/// first violation is returned as an [`ErrorKind::InvalidData`] error naming
+/// the gate's index in execution order.
+pub struct ValidatingReader<R> {
+    inner: R,
+    gates_seen: u64,
+}
+
+impl<R: CircuitReader> ValidatingReader<R> {
+    /// Create a new validating wrapper.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            gates_seen: 0,
+        }
+    }
+
+    /// Unwrap and return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Number of gates validated so far.
+    pub fn gates_validated(&self) -> u64 {
+        self.gates_seen
+    }
+}
+
+impl<R: CircuitReader> CircuitReader for ValidatingReader<R> {
+    fn header(&self) -> &HeaderV5c {
+        self.inner.header()
+    }
+
+    fn outputs(&self) -> &[u32] {
+        self.inner.outputs()
+    }
+
+    async fn next_chunk(&mut self) -> io::Result<Option<Chunk<'_>>> {
+        let header = *self.inner.header();
+        let total_gates = header.total_gates();
+        let gates_per_block = header.block_layout().gates_per_block() as u64;
+
+        let Some(chunk) = self.inner.next_chunk().await? else {
+            return Ok(None);
+        };
+
+        for block in chunk.blocks_iter() {
+            // Only the last block is partial; don't validate its padding
+            let num_gates = total_gates
+                .saturating_sub(self.gates_seen)
+                .min(gates_per_block) as usize;
+            for (i, gate) in block.gates[..num_gates].iter().enumerate() {
+                gate.validate(header.scratch_space).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("gate {}: {}", self.gates_seen + i as u64, e),
+                    )
+                })?;
+            }
+            self.gates_seen += num_gates as u64;
+        }
+
+        Ok(Some(chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ckt_fmtv5_types::GateType;
+    use ckt_fmtv5_types::v5::c::{
+        ALIGNMENT, BLOCK_SIZE, GATE_SIZE, GATES_PER_BLOCK, GateV5c, ReaderV5c, WriterV5c,
+        padded_size,
+    };
+
+    use super::*;
+    use crate::ReaderV5cWrapper;
+
+    async fn write_chain(path: &std::path::Path, num_gates: u32) -> u64 {
+        let mut writer = WriterV5c::new(path, 2, 1, [0u8; 32]).await.unwrap();
+        for i in 0..num_gates {
+            writer
+                .write_gate(GateV5c::new(2, 3, 4 + i % 8), GateType::XOR)
+                .await
+                .unwrap();
+        }
+        writer.finalize(12, vec![4]).await.unwrap().total_gates
+    }
+
+    async fn drain<R: CircuitReader>(reader: &mut R) -> io::Result<()> {
+        while reader.next_chunk().await?.is_some() {}
+        Ok(())
+    }
+
+    #[monoio::test]
+    async fn test_valid_file_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ok.v5c");
+        let total = write_chain(&path, 100).await;
+
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap()).with_validation();
+        drain(&mut reader).await.unwrap();
+        assert_eq!(reader.gates_validated(), total);
+    }
+
+    #[monoio::test]
+    async fn test_out_of_range_address_reports_gate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.v5c");
+        let num_gates = GATES_PER_BLOCK as u32 + 500;
+        write_chain(&path, num_gates).await;
+
+        // Corrupt in2 of a gate in the second block; the reader doesn't
+        // check the checksum, so only validation can catch this
+        let bad_gate = GATES_PER_BLOCK + 123;
+        let offset = ALIGNMENT
+            + padded_size(4)
+            + BLOCK_SIZE * (bad_gate / GATES_PER_BLOCK)
+            + GATE_SIZE * (bad_gate % GATES_PER_BLOCK)
+            + 4;
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[offset..offset + 4].copy_from_slice(&1000u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        // The unvalidated reader hands the bad gate out
+        let mut plain = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap());
+        drain(&mut plain).await.unwrap();
+
+        let mut reader =
+            ValidatingReader::new(ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap()));
+        let err = drain(&mut reader).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "gate {}: in2 address 1000 exceeds scratch_space 12",
+                bad_gate
+            )
+        );
+    }
+
+    #[monoio::test]
+    async fn test_out_of_range_output_in_last_gate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad_out.v5c");
+        write_chain(&path, 100).await;
+
+        // Point the last real gate's out past scratch space; the padding
+        // after it in the same block must not be flagged
+        let offset = ALIGNMENT + padded_size(4) + GATE_SIZE * 99 + 8;
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[offset..offset + 4].copy_from_slice(&12u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&path).unwrap()).with_validation();
+        let err = drain(&mut reader).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "gate 99: out address 12 exceeds scratch_space 12"
+        );
+        assert_eq!(reader.gates_validated(), 0);
+    }
+}
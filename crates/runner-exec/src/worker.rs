@@ -1,4 +1,3 @@
-use ckt_fmtv5_types::v5::c::get_block_num_gates;
 use ckt_runner_types::{CircuitReader, CircuitTask, GateBlock};
 
 /// Processes a circuit task by reading blocks from a [`CircuitReader`].
@@ -30,14 +29,14 @@ async fn process_task_inner<T: CircuitTask, R: CircuitReader>(
     task_state: &mut T::State,
     reader: &mut R,
 ) -> anyhow::Result<()> {
-    let total_gates = reader.header().total_gates();
+    let header = *reader.header();
 
     // Iterate over every chunk, keeping track of where we are.
     let mut cur_block_idx = 0;
     while let Some(chunk) = reader.next_chunk().await? {
         // Iterate over each block in the chunk and pass it to the task to do something with it.
         for block in chunk.blocks_iter() {
-            let gates_in_block = get_block_num_gates(total_gates, cur_block_idx);
+            let gates_in_block = header.block_num_gates(cur_block_idx);
             cur_block_idx += 1;
 
             let safe_block = GateBlock::new(block, gates_in_block);
@@ -3,7 +3,7 @@ use ckt_fmtv5_types::v5::c::*;
 
 /// Safer container around the gates in a block.
 pub struct GateBlock<'b> {
-    raw_block: &'b Block,
+    raw_block: BlockRef<'b>,
     num_gates: usize,
 }
 
@@ -14,7 +14,7 @@ impl<'b> GateBlock<'b> {
     ///
     /// Must be called such that we don't try to read outside the bounds of the
     /// arrays in the `raw_block`.
-    pub fn new(raw_block: &'b Block, num_gates: usize) -> Self {
+    pub fn new(raw_block: BlockRef<'b>, num_gates: usize) -> Self {
         Self {
             raw_block,
             num_gates,
@@ -25,8 +25,13 @@ impl<'b> GateBlock<'b> {
         self.num_gates
     }
 
+    /// Gets the block's valid gates, for looking ahead of the current one.
+    pub fn gates(&self) -> &'b [GateV5c] {
+        &self.raw_block.gates[..self.num_gates]
+    }
+
     /// Gets an iterator over the gates and their types.
     pub fn gates_iter(&self) -> impl Iterator<Item = (GateV5c, GateType)> {
-        (0..self.num_gates).map(|i| (self.raw_block.gates[i], self.raw_block.gate_type(i)))
+        self.raw_block.iter_gates(self.num_gates).map(Into::into)
     }
 }
@@ -4,6 +4,7 @@ use std::error::Error;
 use std::future::Future;
 use std::io;
 
+use ckt_fmtv5_types::v5::GateCounts;
 use ckt_fmtv5_types::v5::c::{Chunk, HeaderV5c};
 
 use crate::block::GateBlock;
@@ -20,6 +21,11 @@ pub trait CircuitReader {
     /// Returns the output wire indices.
     fn outputs(&self) -> &[u32];
 
+    /// Returns the XOR, AND and total gate counts from the header.
+    fn gate_counts(&self) -> GateCounts {
+        self.header().gate_counts()
+    }
+
     /// Asynchronously reads the next chunk of blocks.
     ///
     /// Returns `Ok(Some(chunk))` if there are more blocks to read,
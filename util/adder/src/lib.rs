@@ -1,8 +1,9 @@
 #![allow(unused_crate_dependencies)]
 //! N-bit adder circuit generator for v5a format
 //!
-//! This module provides utilities for generating ripple-carry adder circuits
-//! that can be used for testing and validation of CKT tooling.
+//! This module provides utilities for generating ripple-carry and
+//! carry-lookahead adders and a subtractor, plus an array multiplier, that
+//! can be used for testing and validation of CKT tooling.
 
 use ckt_fmtv5_types::GateType;
 use ckt_fmtv5_types::v5::a::{CREDITS_OUTPUT, GateV5a, MAX_CREDITS, MAX_WIRE_ID};
@@ -101,6 +102,307 @@ pub fn generate_adder(n_bits: usize) -> AdderCircuit {
     }
 }
 
+/// Generate an n-bit carry-lookahead adder circuit.
+///
+/// Same inputs and outputs as [`generate_adder`], but the carries come from
+/// a Sklansky parallel prefix over per-bit (generate, propagate) pairs
+/// instead of a ripple chain, so the depth is O(log n) rather than O(n) at
+/// the cost of O(n log n) gates.
+///
+/// ```text
+/// g[i] = a[i] AND b[i]
+/// p[i] = a[i] XOR b[i]
+/// (G, P) over bits j..=k, merged with bits i..j:
+///   G = G_hi XOR (P_hi AND G_lo)
+///   P = P_hi AND P_lo
+/// sum[i] = p[i] XOR G[0..i]
+/// ```
+///
+/// `G_hi` and `P_hi AND G_lo` are never both set, so XOR stands in for OR.
+///
+/// # Example
+///
+/// ```
+/// use adder::{generate_adder, generate_adder_cla};
+///
+/// let circuit = generate_adder_cla(8);
+/// assert_eq!(circuit.primary_inputs, 16);
+/// assert_eq!(circuit.outputs.len(), 9);
+/// assert!(circuit.num_gates() > generate_adder(8).num_gates());
+/// ```
+pub fn generate_adder_cla(n_bits: usize) -> AdderCircuit {
+    if n_bits == 0 {
+        panic!("n_bits must be at least 1");
+    }
+
+    if n_bits > 1_000_000 {
+        panic!("n_bits too large (max 1,000,000 for practical use)");
+    }
+
+    let n = n_bits as u64;
+    let primary_inputs = 2 * n;
+    let mut builder = GateBuilder {
+        gates: Vec::new(),
+        next_wire: 2 + primary_inputs,
+    };
+
+    let bits: Vec<(u64, u64)> = (0..n)
+        .map(|i| {
+            let (a, b) = (2 + i, 2 + n + i);
+            let g = builder.push(a, b, GateType::AND);
+            let p = builder.push(a, b, GateType::XOR);
+            (g, p)
+        })
+        .collect();
+
+    let prefix = sklansky_prefix(&mut builder, &bits, false);
+
+    // Bit 0 has no carry in, and nothing else reads its propagate
+    let mut outputs = Vec::with_capacity(n_bits + 1);
+    outputs.push(bits[0].1);
+    for i in 1..n_bits {
+        let carry = prefix[i - 1].0;
+        outputs.push(builder.push(bits[i].1, carry, GateType::XOR));
+    }
+    outputs.push(prefix[n_bits - 1].0);
+
+    let gates_with_credits = assign_credits(&builder.gates, &outputs, primary_inputs);
+
+    AdderCircuit {
+        primary_inputs,
+        gates: gates_with_credits,
+        outputs,
+        n_bits,
+    }
+}
+
+/// Prefix (generate, propagate) pairs over `bits`: entry `k` covers bits
+/// `0..=k` of the slice.
+///
+/// Group propagates are only computed when `with_propagates` is set, which
+/// is the case for high halves, whose propagates every merge reads. The
+/// outermost low half never needs them, so no gate output goes unread.
+fn sklansky_prefix(
+    builder: &mut GateBuilder,
+    bits: &[(u64, u64)],
+    with_propagates: bool,
+) -> Vec<(u64, Option<u64>)> {
+    if let [(g, p)] = bits {
+        return vec![(*g, Some(*p))];
+    }
+
+    let (lo, hi) = bits.split_at(bits.len() / 2);
+    let mut prefix = sklansky_prefix(builder, lo, with_propagates);
+    let hi_prefix = sklansky_prefix(builder, hi, true);
+    let (lo_g, lo_p) = *prefix.last().expect("halves are non-empty");
+
+    for (hi_g, hi_p) in hi_prefix {
+        let hi_p = hi_p.expect("every propagate of the high half is computed");
+        let carried = builder.push(hi_p, lo_g, GateType::AND);
+        let g = builder.push(hi_g, carried, GateType::XOR);
+        let p = with_propagates
+            .then(|| builder.push(hi_p, lo_p.expect("low half has propagates"), GateType::AND));
+        prefix.push((g, p));
+    }
+    prefix
+}
+
+/// Gates plus the next free wire ID, for generators that build a gate at a
+/// time.
+struct GateBuilder {
+    gates: Vec<GateV5a>,
+    next_wire: u64,
+}
+
+impl GateBuilder {
+    /// Append a gate on a fresh output wire and return that wire.
+    fn push(&mut self, in1: u64, in2: u64, gate_type: GateType) -> u64 {
+        let out = self.next_wire;
+        self.gates.push(GateV5a {
+            in1,
+            in2,
+            out,
+            credits: 0,
+            gate_type,
+        });
+        self.next_wire += 1;
+        out
+    }
+}
+
+/// Generate an n-bit ripple-borrow subtractor computing A - B.
+///
+/// Uses two's complement: each B bit is inverted (XOR with the true
+/// constant) and the ripple-carry chain of [`generate_adder`] starts with a
+/// carry-in of 1.
+///
+/// **Inputs**: same layout as [`generate_adder`]
+///
+/// **Outputs**: (n+1) outputs
+/// - n difference bits, `(A - B) mod 2^n`
+/// - 1 borrow bit, set when A < B (the inverted carry-out)
+///
+/// Uses 6n + 1 gates: n inverters, 5n for the full adders and one more
+/// inverter for the borrow.
+///
+/// # Example
+///
+/// ```
+/// use adder::generate_subtractor;
+///
+/// let circuit = generate_subtractor(8);
+/// assert_eq!(circuit.primary_inputs, 16);
+/// assert_eq!(circuit.outputs.len(), 9);   // 8 difference bits + 1 borrow
+/// assert_eq!(circuit.gates.len(), 49);    // 6 gates per bit + 1
+/// ```
+pub fn generate_subtractor(n_bits: usize) -> AdderCircuit {
+    if n_bits == 0 {
+        panic!("n_bits must be at least 1");
+    }
+
+    if n_bits > 1_000_000 {
+        panic!("n_bits too large (max 1,000,000 for practical use)");
+    }
+
+    let primary_inputs = 2 * n_bits as u64;
+    let mut gates = Vec::with_capacity(6 * n_bits + 1);
+    let mut next_wire = 2 + primary_inputs;
+    let mut outputs = Vec::with_capacity(n_bits + 1);
+
+    // Carry-in of 1 completes the two's complement of B
+    let mut carry = 1u64;
+
+    for i in 0..n_bits {
+        let a = 2 + i as u64;
+        let b = 2 + n_bits as u64 + i as u64;
+
+        let not_b = next_wire;
+        gates.push(not_gate(b, not_b));
+        next_wire += 1;
+
+        let (diff_bit, carry_out, bit_gates) = full_adder(a, not_b, carry, next_wire);
+        next_wire += bit_gates.len() as u64;
+        gates.extend(bit_gates);
+
+        outputs.push(diff_bit);
+        carry = carry_out;
+    }
+
+    // No carry out of the top bit means the subtraction borrowed
+    let borrow = next_wire;
+    gates.push(not_gate(carry, borrow));
+    outputs.push(borrow);
+
+    let gates_with_credits = assign_credits(&gates, &outputs, primary_inputs);
+
+    AdderCircuit {
+        primary_inputs,
+        gates: gates_with_credits,
+        outputs,
+        n_bits,
+    }
+}
+
+/// Generate an n x n shift-and-add array multiplier.
+///
+/// Unlike the adder, this is AND-dominated, which makes it a better
+/// garbling benchmark.
+///
+/// **Inputs**: same layout as [`generate_adder`]
+///
+/// **Outputs**: 2n product bits, least significant first
+///
+/// Every partial product `a[j] AND b[i]` is computed (n² AND gates), then
+/// each row i >= 1 is added into the running sum at offset i with a
+/// ripple-carry adder whose final carry becomes the next product bit. The
+/// first column of each row, and the top column of row 1 where the running
+/// sum is still the false constant, use half adders. A 1-bit multiplier has
+/// no rows to add, so its top bit is `p XOR p` for the single product `p`.
+///
+/// # Example
+///
+/// ```
+/// use adder::generate_multiplier;
+///
+/// let circuit = generate_multiplier(8);
+/// assert_eq!(circuit.primary_inputs, 16);
+/// assert_eq!(circuit.outputs.len(), 16); // 2n product bits
+/// assert!(circuit.num_and_gates() > circuit.num_xor_gates());
+/// ```
+pub fn generate_multiplier(n_bits: usize) -> AdderCircuit {
+    if n_bits == 0 {
+        panic!("n_bits must be at least 1");
+    }
+
+    if n_bits > 10_000 {
+        panic!("n_bits too large (max 10,000 for practical use)");
+    }
+
+    let n = n_bits as u64;
+    let primary_inputs = 2 * n;
+    let mut gates = Vec::with_capacity(3 * n_bits * n_bits);
+    let mut next_wire = 2 + primary_inputs;
+
+    // Partial products, pp[i][j] = a[j] AND b[i]
+    let mut pp = vec![vec![0u64; n_bits]; n_bits];
+    for (i, row) in pp.iter_mut().enumerate() {
+        for (j, product) in row.iter_mut().enumerate() {
+            *product = next_wire;
+            gates.push(GateV5a {
+                in1: 2 + j as u64,
+                in2: 2 + n + i as u64,
+                out: next_wire,
+                credits: 0,
+                gate_type: GateType::AND,
+            });
+            next_wire += 1;
+        }
+    }
+
+    // Running sum, starting as row 0 with false (wire 0) above it
+    let mut acc = vec![0u64; 2 * n_bits];
+    acc[..n_bits].copy_from_slice(&pp[0]);
+
+    for (i, row) in pp.iter().enumerate().skip(1) {
+        let mut carry = None;
+        for (j, &product) in row.iter().enumerate() {
+            let (sum, carry_out, column_gates) = match (acc[i + j], carry) {
+                (x, None) => half_adder(x, product, next_wire),
+                (0, Some(c)) => half_adder(product, c, next_wire),
+                (x, Some(c)) => full_adder(x, product, c, next_wire),
+            };
+            next_wire += column_gates.len() as u64;
+            gates.extend(column_gates);
+            acc[i + j] = sum;
+            carry = Some(carry_out);
+        }
+        acc[i + n_bits] = carry.expect("rows are non-empty");
+    }
+
+    // With a single row there is no carry into the top bit, which is
+    // always false. Compute it rather than exposing the constant wire.
+    if n_bits == 1 {
+        gates.push(GateV5a {
+            in1: acc[0],
+            in2: acc[0],
+            out: next_wire,
+            credits: 0,
+            gate_type: GateType::XOR,
+        });
+        acc[1] = next_wire;
+    }
+
+    let outputs = acc;
+    let gates_with_credits = assign_credits(&gates, &outputs, primary_inputs);
+
+    AdderCircuit {
+        primary_inputs,
+        gates: gates_with_credits,
+        outputs,
+        n_bits,
+    }
+}
+
 /// A complete adder circuit with all metadata
 #[derive(Debug, Clone)]
 pub struct AdderCircuit {
@@ -108,7 +410,7 @@ pub struct AdderCircuit {
     pub primary_inputs: u64,
     /// All gates in the circuit with credits assigned
     pub gates: Vec<GateV5a>,
-    /// Output wire IDs (n_bits sum bits + 1 carry bit)
+    /// Output wire IDs (n_bits result bits + 1 carry or borrow bit)
     pub outputs: Vec<u64>,
     /// Number of bits in each operand
     pub n_bits: usize,
@@ -229,6 +531,42 @@ fn full_adder(a: u64, b: u64, carry_in: u64, next_wire: u64) -> (u64, u64, Vec<G
     (sum, carry_out, gates)
 }
 
+/// Generate a half adder.
+///
+/// Returns: (sum_output_wire, carry_output_wire, gates)
+fn half_adder(a: u64, b: u64, next_wire: u64) -> (u64, u64, Vec<GateV5a>) {
+    let sum = next_wire;
+    let carry = next_wire + 1;
+    let gates = vec![
+        GateV5a {
+            in1: a,
+            in2: b,
+            out: sum,
+            credits: 0,
+            gate_type: GateType::XOR,
+        },
+        GateV5a {
+            in1: a,
+            in2: b,
+            out: carry,
+            credits: 0,
+            gate_type: GateType::AND,
+        },
+    ];
+    (sum, carry, gates)
+}
+
+/// NOT gate: `out = input XOR 1`.
+fn not_gate(input: u64, out: u64) -> GateV5a {
+    GateV5a {
+        in1: input,
+        in2: 1,
+        out,
+        credits: 0,
+        gate_type: GateType::XOR,
+    }
+}
+
 /// Assign credits to all gates based on usage analysis.
 ///
 /// Credits indicate how many times a wire will be consumed:
@@ -376,6 +714,167 @@ mod tests {
         }
     }
 
+    /// Evaluate a circuit on plain bits, returning the output values.
+    fn evaluate(circuit: &AdderCircuit, inputs: &[bool]) -> Vec<bool> {
+        let mut values: std::collections::HashMap<u64, bool> =
+            [(0, false), (1, true)].into_iter().collect();
+        for (i, &bit) in inputs.iter().enumerate() {
+            values.insert(2 + i as u64, bit);
+        }
+        for gate in &circuit.gates {
+            let (a, b) = (values[&gate.in1], values[&gate.in2]);
+            let out = match gate.gate_type {
+                GateType::XOR => a ^ b,
+                GateType::AND => a & b,
+            };
+            values.insert(gate.out, out);
+        }
+        circuit.outputs.iter().map(|o| values[o]).collect()
+    }
+
+    #[test]
+    fn test_cla_adder_computes_sum() {
+        for n in [1, 2, 3, 5] {
+            let circuit = generate_adder_cla(n);
+            assert!(circuit.validate().is_ok());
+            for a in 0..1u32 << n {
+                for b in 0..1u32 << n {
+                    let inputs: Vec<bool> = (0..n)
+                        .map(|i| (a >> i) & 1 == 1)
+                        .chain((0..n).map(|i| (b >> i) & 1 == 1))
+                        .collect();
+                    let expected: Vec<bool> = (0..=n).map(|i| ((a + b) >> i) & 1 == 1).collect();
+                    assert_eq!(evaluate(&circuit, &inputs), expected, "{} + {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cla_adder_reads_every_non_output_wire() {
+        for n in [1, 7, 32, 33] {
+            let circuit = generate_adder_cla(n);
+            let outputs: std::collections::HashSet<_> = circuit.outputs.iter().collect();
+            for gate in &circuit.gates {
+                if outputs.contains(&gate.out) {
+                    assert_eq!(gate.credits, CREDITS_OUTPUT, "n = {}", n);
+                } else {
+                    assert!(
+                        gate.credits > 0,
+                        "wire {} is never read, n = {}",
+                        gate.out,
+                        n
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_8bit_subtractor() {
+        let circuit = generate_subtractor(8);
+        assert_eq!(circuit.primary_inputs, 16);
+        assert_eq!(circuit.outputs.len(), 9); // 8 difference + 1 borrow
+        assert_eq!(circuit.gates.len(), 49); // 6 gates per bit * 8 + 1
+        assert_eq!(circuit.num_xor_gates(), 33);
+        assert_eq!(circuit.num_and_gates(), 16);
+        assert!(circuit.validate().is_ok());
+    }
+
+    #[test]
+    fn test_subtractor_computes_difference() {
+        let n = 4;
+        let circuit = generate_subtractor(n);
+        for a in 0..1u32 << n {
+            for b in 0..1u32 << n {
+                let inputs: Vec<bool> = (0..n)
+                    .map(|i| (a >> i) & 1 == 1)
+                    .chain((0..n).map(|i| (b >> i) & 1 == 1))
+                    .collect();
+                let outputs = evaluate(&circuit, &inputs);
+
+                let diff = a.wrapping_sub(b) & ((1 << n) - 1);
+                let expected: Vec<bool> = (0..n)
+                    .map(|i| (diff >> i) & 1 == 1)
+                    .chain([a < b])
+                    .collect();
+                assert_eq!(outputs, expected, "{} - {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_subtractor_wire_numbering() {
+        let circuit = generate_subtractor(64);
+        let first_gate_output = 2 + circuit.primary_inputs;
+        let last_wire = first_gate_output + circuit.num_gates() as u64 - 1;
+        assert!(last_wire <= MAX_WIRE_ID);
+        for gate in &circuit.gates {
+            assert!(gate.out >= first_gate_output && gate.out <= last_wire);
+            assert!(gate.in1 <= MAX_WIRE_ID && gate.in2 <= MAX_WIRE_ID);
+        }
+        assert!(circuit.validate().is_ok());
+    }
+
+    #[test]
+    fn test_8bit_multiplier() {
+        let circuit = generate_multiplier(8);
+        assert_eq!(circuit.primary_inputs, 16);
+        assert_eq!(circuit.outputs.len(), 16);
+        // 64 partial products, then per row a half adder and 7 full
+        // adders, except row 1's top column which is a half adder too
+        assert_eq!(circuit.num_and_gates(), 64 + 7 * (1 + 2 * 7) - 1);
+        assert_eq!(circuit.num_xor_gates(), 7 * (1 + 3 * 7) - 2);
+        assert!(circuit.validate().is_ok());
+    }
+
+    #[test]
+    fn test_multiplier_is_and_heavy_up_to_64_bits() {
+        for n in [2, 5, 16, 31, 64] {
+            let circuit = generate_multiplier(n);
+            let and_gates = circuit.num_and_gates();
+            assert!(and_gates >= n * n && and_gates <= 3 * n * n, "n = {}", n);
+            assert!(and_gates > circuit.num_xor_gates(), "n = {}", n);
+            assert_eq!(circuit.outputs.len(), 2 * n);
+            assert!(circuit.validate().is_ok(), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn test_1bit_multiplier() {
+        let circuit = generate_multiplier(1);
+        assert_eq!(circuit.num_and_gates(), 1);
+        assert_eq!(circuit.num_xor_gates(), 1);
+        assert!(circuit.validate().is_ok());
+        // Both product bits are computed, neither is a constant wire
+        assert!(
+            circuit
+                .outputs
+                .iter()
+                .all(|&o| o >= 2 + circuit.primary_inputs)
+        );
+
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(evaluate(&circuit, &[a, b]), [a & b, false], "{} * {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_multiplier_computes_product() {
+        let n = 4;
+        let circuit = generate_multiplier(n);
+        for a in 0..1u32 << n {
+            for b in 0..1u32 << n {
+                let inputs: Vec<bool> = (0..n)
+                    .map(|i| (a >> i) & 1 == 1)
+                    .chain((0..n).map(|i| (b >> i) & 1 == 1))
+                    .collect();
+                let expected: Vec<bool> = (0..2 * n).map(|i| ((a * b) >> i) & 1 == 1).collect();
+                assert_eq!(evaluate(&circuit, &inputs), expected, "{} * {}", a, b);
+            }
+        }
+    }
+
     #[test]
     #[should_panic(expected = "n_bits must be at least 1")]
     fn test_zero_bits_panics() {
@@ -32,7 +32,16 @@ async fn main() {
         pb.println(format!("{}", slab.max_allocated_concurrently()));
     }
     pb.finish();
-    dbg!(slab.max_allocated_concurrently());
+
+    let stats = slab.stats();
+    println!(
+        "slab: high water {}, live {}, {} allocations, {} frees, {} free slots",
+        stats.high_water,
+        stats.live,
+        stats.total_allocations,
+        stats.total_frees,
+        stats.free_list_len
+    );
 }
 
 fn lookup_wire(
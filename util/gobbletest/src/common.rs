@@ -39,6 +39,30 @@ pub(crate) fn read_inputs(input_file: &str, expected_num_inputs: usize) -> BitVe
     input_values_bits
 }
 
+/// Read expected output bits from a text file containing 0s and 1s
+///
+/// Whitespace is ignored so outputs can be split across lines.
+pub(crate) fn read_expected_outputs(path: &str) -> Vec<bool> {
+    let contents = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Failed to open expected outputs file: {}", path));
+
+    contents
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .enumerate()
+        .map(|(idx, char)| match char {
+            '0' => false,
+            '1' => true,
+            _ => panic!("Invalid output character '{}' at position {}", char, idx),
+        })
+        .collect()
+}
+
+/// Format bits as a string of 0s and 1s, matching the input file format
+pub(crate) fn bits_to_string(bits: &[bool]) -> String {
+    bits.iter().map(|&b| if b { '1' } else { '0' }).collect()
+}
+
 /// Convert bits to bytes (LSB first within each byte)
 pub(crate) fn bits_to_bytes(bits: &BitVec, num_bytes: usize) -> Vec<u8> {
     let mut bytes = vec![0u8; num_bytes];
@@ -124,6 +124,7 @@ pub async fn test_end_to_end_translate(
         public_s: garble_output.public_s,
         constant_zero_label: garble_output.constant_zero_label,
         constant_one_label: garble_output.constant_one_label,
+        hash_scheme: garble_output.hash_scheme,
     };
     let eval_output = eval_translate::eval_with_translation(EvalTranslationConfig {
         circuit_file,
@@ -224,3 +225,155 @@ pub async fn test_end_to_end_translate(
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{BufReader, BufWriter};
+
+    use bitvec::vec::BitVec;
+    use ckt_fmtv5_types::v5::c::ReaderV5c;
+    use ckt_gobble::HashScheme;
+    use ckt_gobble::traits::{
+        EvaluationInstance, EvaluationInstanceConfig, GarblingInstance, GarblingInstanceConfig,
+    };
+    use ckt_runner_exec::{CircuitReader, EvalTask, GarbleTask, ReaderV5cWrapper, process_task};
+    use rand_chacha::ChaCha20Rng;
+    use rand_chacha::rand_core::SeedableRng;
+
+    use super::*;
+    use crate::common::bits_to_string;
+    use crate::test_util::{to_bits, write_adder};
+
+    const SECRET: [u8; 16] = *b"cut-and-choose!!";
+
+    #[monoio::test]
+    async fn test_decoded_outputs_match_cleartext() {
+        let n_bits = 8;
+        let dir = tempfile::tempdir().unwrap();
+        let circuit_path = write_adder(dir.path(), n_bits).await;
+        let circuit_file = circuit_path.to_str().unwrap();
+        let input_path = dir.path().join("inputs.txt");
+        let input_file = input_path.to_str().unwrap();
+        let gc_path = dir.path().join("gc.bin");
+        let garbled_file = gc_path.to_str().unwrap();
+
+        // Labels are drawn at random, so their LSBs exercise the garbler's
+        // point-and-permute normalisation
+        let mut rng = ChaCha20Rng::seed_from_u64(261);
+        for (a, b) in [(0u64, 0u64), (200, 99), (255, 255), (170, 85)] {
+            let mut inputs = to_bits(a, n_bits);
+            inputs.extend(to_bits(b, n_bits));
+            std::fs::write(&input_path, bits_to_string(&inputs)).unwrap();
+
+            let cleartext = exec::exec(circuit_file, input_file).await;
+            assert_eq!(cleartext, to_bits(a + b, n_bits + 1));
+
+            let (delta, input_bits, input_labels, garbler_labels, params) =
+                garble::garble(circuit_file, input_file, garbled_file, &mut rng).await;
+            let (eval_labels, eval_values) = eval::eval(
+                circuit_file,
+                garbled_file,
+                &input_bits,
+                &input_labels,
+                &params,
+            )
+            .await;
+
+            assert_eq!(eval_values, cleartext, "{} + {}", a, b);
+            for ((garbler_label, eval_label), &value) in
+                garbler_labels.iter().zip(&eval_labels).zip(&eval_values)
+            {
+                let expected = if value {
+                    xor_labels(garbler_label, &delta)
+                } else {
+                    *garbler_label
+                };
+                assert_eq!(*eval_label, expected, "{} + {}", a, b);
+            }
+        }
+    }
+
+    #[monoio::test]
+    async fn test_translated_outputs_recover_fixed_secret() {
+        let n_bits = 8;
+        let dir = tempfile::tempdir().unwrap();
+        let v5c_path = write_adder(dir.path(), n_bits).await;
+        let gc_path = dir.path().join("gc.bin");
+
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&v5c_path).unwrap());
+        let header = *reader.header();
+        let output_wires: Vec<u64> = reader.outputs().iter().map(|&w| w as u64).collect();
+
+        let false_labels: Vec<[u8; 16]> = (0..header.primary_inputs as u8)
+            .map(|i| [0xA0 ^ i; 16])
+            .collect();
+        let garbling_config = GarblingInstanceConfig {
+            scratch_space: header.scratch_space as u32,
+            delta: [0x11; 16],
+            primary_input_false_labels: &false_labels,
+            aes128_key: [0x22; 16],
+            public_s: [0x33; 16],
+            constant_zero_label: [0x44; 16],
+            constant_one_label: [0x55; 16],
+            hash_scheme: HashScheme::Ccrnd,
+        };
+
+        let writer = BufWriter::new(File::create(&gc_path).unwrap());
+        let mut garbled = process_task(&GarbleTask::new(garbling_config), writer, &mut reader)
+            .await
+            .unwrap();
+
+        // Every output's false label becomes the same fixed secret
+        let targets = vec![SECRET; output_wires.len()];
+        let translation = garbled.instance.translate_outputs(&output_wires, &targets);
+        assert_eq!(translation.len(), output_wires.len());
+
+        // 200 + 99 = 299 leaves a mix of true and false output bits
+        let inputs: BitVec = to_bits(200, n_bits)
+            .into_iter()
+            .chain(to_bits(99, n_bits))
+            .collect();
+        let input_wires: Vec<u64> = (2..2 + header.primary_inputs).collect();
+        let mut input_labels = vec![[0u8; 16]; input_wires.len()];
+        garbled
+            .instance
+            .get_selected_labels(&input_wires, &inputs, &mut input_labels);
+
+        let eval_config = EvaluationInstanceConfig {
+            scratch_space: header.scratch_space as u32,
+            selected_primary_input_labels: &input_labels,
+            selected_primary_input_values: &inputs,
+            aes128_key: garbling_config.aes128_key,
+            public_s: garbling_config.public_s,
+            constant_zero_label: garbling_config.constant_zero_label,
+            constant_one_label: garbling_config.constant_one_label,
+            hash_scheme: garbling_config.hash_scheme,
+        };
+        let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(&v5c_path).unwrap());
+        let ct_reader = BufReader::new(File::open(&gc_path).unwrap());
+        let mut evaluated = process_task(&EvalTask::new(eval_config), ct_reader, &mut reader)
+            .await
+            .unwrap();
+        let expected = to_bits(299, n_bits + 1);
+        assert_eq!(evaluated.output_values, expected);
+
+        evaluated
+            .instance
+            .apply_translation(&output_wires, &translation);
+        let mut recovered = vec![[0u8; 16]; output_wires.len()];
+        evaluated.instance.get_labels(&output_wires, &mut recovered);
+
+        for (i, (label, &value)) in recovered.iter().zip(&expected).enumerate() {
+            if value {
+                assert_ne!(*label, SECRET, "true output {} revealed the secret", i);
+            } else {
+                assert_eq!(
+                    *label, SECRET,
+                    "false output {} did not recover the secret",
+                    i
+                );
+            }
+        }
+    }
+}
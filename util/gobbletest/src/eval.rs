@@ -28,6 +28,7 @@ pub async fn eval(
         public_s: garbling_params.public_s,
         constant_zero_label: garbling_params.constant_zero_label,
         constant_one_label: garbling_params.constant_one_label,
+        hash_scheme: garbling_params.hash_scheme,
     };
 
     let task_info = EvalTask::new(config);
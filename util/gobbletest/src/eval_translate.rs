@@ -111,6 +111,7 @@ pub async fn eval_with_translation(config: EvalTranslationConfig<'_>) -> EvalTra
         public_s: config.garbling_params.public_s,
         constant_zero_label: config.garbling_params.constant_zero_label,
         constant_one_label: config.garbling_params.constant_one_label,
+        hash_scheme: config.garbling_params.hash_scheme,
     };
 
     let task_info = EvalTask::new(eval_config);
@@ -2,7 +2,7 @@ use ckt_fmtv5_types::v5::c::ReaderV5c;
 use ckt_gobble::traits::ExecutionInstanceConfig;
 use ckt_runner_exec::{CircuitReader, ExecTask, ReaderV5cWrapper, process_task};
 
-use crate::common::{ProgressBarTask, read_inputs};
+use crate::common::{ProgressBarTask, bits_to_string, read_inputs};
 
 pub async fn exec(circuit_file: &str, input_file: &str) -> Vec<bool> {
     let mut reader = ReaderV5cWrapper::new(ReaderV5c::open(circuit_file).unwrap());
@@ -25,6 +25,68 @@ pub async fn exec(circuit_file: &str, input_file: &str) -> Vec<bool> {
         .expect("exec: process task");
 
     println!("Output values: {:?}", output.output_values);
+    println!("Output bits:   {}", bits_to_string(&output.output_values));
 
     output.output_values
 }
+
+/// Compare produced outputs against the expected ones.
+///
+/// Returns a description of every mismatching output on failure.
+pub fn check_outputs(outputs: &[bool], expected: &[bool]) -> Result<(), String> {
+    if outputs.len() != expected.len() {
+        return Err(format!(
+            "circuit produced {} outputs but {} were expected",
+            outputs.len(),
+            expected.len()
+        ));
+    }
+
+    let mismatches: Vec<String> = outputs
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|(_, (got, want))| got != want)
+        .map(|(i, (got, want))| {
+            format!("output {}: expected {}, got {}", i, *want as u8, *got as u8)
+        })
+        .collect();
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::read_expected_outputs;
+    use crate::test_util::{to_bits, write_adder};
+
+    #[monoio::test]
+    async fn test_exec_adder_matches_expected_file() {
+        let n_bits = 8;
+        let dir = tempfile::tempdir().unwrap();
+        let v5c_path = write_adder(dir.path(), n_bits).await;
+        let inputs_path = dir.path().join("inputs.txt");
+        let expected_path = dir.path().join("expected.txt");
+
+        let (a, b) = (200u64, 99u64);
+        let mut inputs = to_bits(a, n_bits);
+        inputs.extend(to_bits(b, n_bits));
+        std::fs::write(&inputs_path, bits_to_string(&inputs)).unwrap();
+        std::fs::write(&expected_path, bits_to_string(&to_bits(a + b, n_bits + 1))).unwrap();
+
+        let outputs = exec(v5c_path.to_str().unwrap(), inputs_path.to_str().unwrap()).await;
+        let expected = read_expected_outputs(expected_path.to_str().unwrap());
+        assert_eq!(check_outputs(&outputs, &expected), Ok(()));
+
+        // A single flipped bit must be reported
+        let mut wrong = expected.clone();
+        wrong[3] = !wrong[3];
+        let err = check_outputs(&outputs, &wrong).unwrap_err();
+        assert!(err.contains("output 3"), "{}", err);
+    }
+}
@@ -3,6 +3,7 @@ use std::io::{BufWriter, sink};
 
 use bitvec::vec::BitVec;
 use ckt_fmtv5_types::v5::c::*;
+use ckt_gobble::HashScheme;
 use ckt_gobble::traits::{GarblingInstance, GarblingInstanceConfig};
 use ckt_runner_exec::{CircuitReader, GarbleTask, HashWriter, ReaderV5cWrapper, process_task};
 use rand_chacha::ChaCha20Rng;
@@ -21,6 +22,8 @@ pub struct GarblingParams {
     pub constant_zero_label: [u8; 16],
     /// Label for constant wire 1 (always true).
     pub constant_one_label: [u8; 16],
+    /// Hash function used for AND gates.
+    pub hash_scheme: HashScheme,
 }
 
 pub async fn garble(
@@ -50,6 +53,8 @@ pub async fn garble(
 
     let mut delta = [0u8; 16];
     rng.fill_bytes(&mut delta);
+    // The garbler forces the delta's point-and-permute bit; match it here
+    delta[0] |= 1;
 
     // Generate random AES key and public S for this garbling instance
     let mut aes128_key = [0u8; 16];
@@ -73,6 +78,7 @@ pub async fn garble(
         public_s,
         constant_zero_label,
         constant_one_label,
+        hash_scheme: HashScheme::default(),
     };
 
     let garbling_params = GarblingParams {
@@ -80,6 +86,7 @@ pub async fn garble(
         public_s,
         constant_zero_label,
         constant_one_label,
+        hash_scheme: config.hash_scheme,
     };
 
     let task_info = GarbleTask::new(config);
@@ -153,6 +160,7 @@ pub async fn garble_discard(circuit_file: &str, rng: &mut ChaCha20Rng) -> Vec<[u
         public_s,
         constant_zero_label,
         constant_one_label,
+        hash_scheme: HashScheme::default(),
     };
 
     let task_info = GarbleTask::new(config);
@@ -3,7 +3,7 @@ use std::io::BufWriter;
 
 use ckt_fmtv5_types::v5::c::*;
 use ckt_gobble::{
-    BitLabel, ByteLabel, Label, generate_input_translation_material,
+    BitLabel, ByteLabel, HashScheme, Label, generate_input_translation_material,
     generate_output_translation_material, traits::GarblingInstanceConfig,
 };
 use ckt_runner_exec::{CircuitReader, GarbleTask, ReaderV5cWrapper, process_task};
@@ -38,6 +38,8 @@ pub struct GarbleTranslationOutput {
     pub constant_zero_label: [u8; 16],
     /// Label for constant wire 1 (always true).
     pub constant_one_label: [u8; 16],
+    /// Hash function used for AND gates.
+    pub hash_scheme: HashScheme,
 }
 
 /// Garbling with translation support.
@@ -72,6 +74,8 @@ pub async fn garble_with_translation(
     // This delta ensures FreeXOR optimization: true_label = false_label XOR delta
     let mut delta_bytes = [0u8; 16];
     rng.fill_bytes(&mut delta_bytes);
+    // The garbler forces the delta's point-and-permute bit; match it here
+    delta_bytes[0] |= 1;
     let delta = Label::from(delta_bytes);
 
     // Import xor128 function
@@ -93,6 +97,8 @@ pub async fn garble_with_translation(
             // Generate false label (for bit value 0)
             let mut false_label_bytes = [0u8; 16];
             rng.fill_bytes(&mut false_label_bytes);
+            // False labels carry a point-and-permute bit of 0
+            false_label_bytes[0] &= !1;
             let false_label = Label::from(false_label_bytes);
 
             // Compute true label (for bit value 1) = false_label XOR delta
@@ -170,6 +176,7 @@ pub async fn garble_with_translation(
         public_s,
         constant_zero_label,
         constant_one_label,
+        hash_scheme: HashScheme::default(),
     };
 
     let task_info = GarbleTask::new(config);
@@ -242,5 +249,6 @@ pub async fn garble_with_translation(
         public_s,
         constant_zero_label,
         constant_one_label,
+        hash_scheme: config.hash_scheme,
     }
 }
@@ -3,3 +3,5 @@
 #[allow(dead_code)]
 mod common;
 pub mod exec;
+#[cfg(test)]
+mod test_util;
@@ -5,6 +5,8 @@ mod eval_translate;
 mod exec;
 mod garble;
 mod garble_translate;
+#[cfg(test)]
+mod test_util;
 
 use rand_chacha::ChaCha20Rng;
 use rand_chacha::rand_core::SeedableRng;
@@ -16,6 +18,9 @@ async fn main() {
     if args.len() < 2 {
         eprintln!("Usage: {} <mode> [args...]", args[0]);
         eprintln!("Modes:");
+        eprintln!(
+            "  exec <circuit> <inputs> [expected_outputs]    - Run cleartext execution, optionally checking outputs"
+        );
         eprintln!("  garble <circuit>                              - Run garble test");
         eprintln!(
             "  e2e <circuit> <inputs> [garbled_circuit_path] - Run end-to-end test: exec → garble → eval"
@@ -30,6 +35,29 @@ async fn main() {
     let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
 
     match mode.as_str() {
+        "exec" => {
+            if args.len() != 4 && args.len() != 5 {
+                eprintln!(
+                    "Usage: {} exec <circuit> <inputs> [expected_outputs]",
+                    args[0]
+                );
+                std::process::exit(1);
+            }
+            let circuit = &args[2];
+            let inputs = &args[3];
+            let outputs = exec::exec(circuit, inputs).await;
+            if let Some(expected_file) = args.get(4) {
+                let expected = common::read_expected_outputs(expected_file);
+                match exec::check_outputs(&outputs, &expected) {
+                    Ok(()) => println!("✓ All {} outputs match expected", outputs.len()),
+                    Err(e) => {
+                        println!("❌ FAILED: outputs don't match expected!");
+                        println!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
         "garble" => {
             if args.len() != 3 {
                 eprintln!("Usage: {} garble <circuit>", args[0]);
@@ -67,7 +95,7 @@ async fn main() {
         }
         _ => {
             eprintln!("Unknown mode: {}", mode);
-            eprintln!("Valid modes: garble, e2e, e2e-translate");
+            eprintln!("Valid modes: exec, garble, e2e, e2e-translate");
             std::process::exit(1);
         }
     }
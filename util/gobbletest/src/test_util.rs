@@ -0,0 +1,31 @@
+//! Fixtures shared by the command tests.
+
+use std::path::{Path, PathBuf};
+
+use adder::generate_adder;
+use ckt_fmtv5_types::v5::a::writer::CircuitWriterV5a;
+
+/// The low `n` bits of `value`, least significant first.
+pub(crate) fn to_bits(value: u64, n: usize) -> Vec<bool> {
+    (0..n).map(|i| (value >> i) & 1 == 1).collect()
+}
+
+/// Writes an `n_bits` adder as v5c into `dir`.
+pub(crate) async fn write_adder(dir: &Path, n_bits: usize) -> PathBuf {
+    let circuit = generate_adder(n_bits);
+    let v5a_path = dir.join("adder.v5a");
+    let v5c_path = dir.join("adder.v5c");
+
+    let mut writer = CircuitWriterV5a::new(
+        &v5a_path,
+        circuit.primary_inputs,
+        circuit.outputs.clone(),
+        [0u8; 32],
+    )
+    .await
+    .unwrap();
+    writer.write_gates(&circuit.gates).await.unwrap();
+    writer.finalize().await.unwrap();
+    ckt_lvl::prealloc::prealloc(v5a_path.to_str().unwrap(), v5c_path.to_str().unwrap()).await;
+    v5c_path
+}